@@ -42,6 +42,83 @@ fn implied_vol_criterion_benchmark(c: &mut Criterion) {
             0.001
         );
     }));
+
+    c.bench_function("implied volatility f32x8 approx", |b| b.iter(|| {
+        let _ = vol32x8::implied_vol_approx(
+            consts::OptionDir::CALL,
+            &call_prices,
+            &spot,
+            &call_strikes,
+            &risk_free_rate,
+            &dividend_yield,
+            &years_to_expiry,
+            consts::VolMethod::Li
+        );
+    }));
+}
+
+/*
+    Intraday recomputation: prices move 0.5% and the previous snapshot's vols are the guess.
+    Every contract is included, also the 7,524 whose previous inversion failed (vol at the bottom
+    of the bracket). A vector stops only when all of its lanes converge, so those that fall back
+    to the full bracket hold their whole vector to the cold step count, and the warm start is
+    about 1.5x faster on this data rather than the 16 / 7 of its step count.
+*/
+fn warm_start_criterion_benchmark(c: &mut Criterion) {
+    let (spot, call_prices, call_strikes, _, _, years_to_expiry, _) =
+    read_hist::get_appl_data();
+
+    let n = call_prices.len();
+    let spot: Vec<f32> = vec![spot; n];
+    let risk_free_rate: Vec<f32> = vec![0.01; n];
+    let dividend_yield: Vec<f32> = vec![0.0; n];
+
+    let previous_vol = vol32x8::implied_vol(
+        consts::OptionDir::CALL,
+        &call_prices,
+        &spot,
+        &call_strikes,
+        &risk_free_rate,
+        &dividend_yield,
+        &years_to_expiry,
+        20,
+        0.0001
+    );
+
+    let call_prices: Vec<f32> = call_prices
+        .iter()
+        .map(|price| price * 1.005)
+        .collect();
+
+    c.bench_function("implied volatility f32x8 cold (AAPL +0.5%)", |b| b.iter(|| {
+        let _ = vol32x8::implied_vol(
+            consts::OptionDir::CALL,
+            &call_prices,
+            &spot,
+            &call_strikes,
+            &risk_free_rate,
+            &dividend_yield,
+            &years_to_expiry,
+            20,
+            0.0001
+        );
+    }));
+
+    c.bench_function("implied volatility f32x8 warm (AAPL +0.5%, ~1.5x)", |b| b.iter(|| {
+        let _ = vol32x8::implied_vol_guess(
+            consts::OptionDir::CALL,
+            &call_prices,
+            &spot,
+            &call_strikes,
+            &risk_free_rate,
+            &dividend_yield,
+            &years_to_expiry,
+            &previous_vol,
+            0.005,
+            20,
+            0.0001
+        );
+    }));
 }
 
 fn parity_interest_rate_criterion_benchmark(c: &mut Criterion) {
@@ -56,7 +133,7 @@ fn parity_interest_rate_criterion_benchmark(c: &mut Criterion) {
             let _ = bs::parity_interest_rate(
                 call_prices[i],
                 put_prices[i],
-                call_strikes[i],
+                spot[i],
                 call_strikes[i],
                 years_to_expiry[i],
             );
@@ -67,12 +144,17 @@ fn parity_interest_rate_criterion_benchmark(c: &mut Criterion) {
         let _ = vol32x8::parity_interest_rate(
             &call_prices,
             &put_prices,
-            &call_strikes,
+            &spot,
             &call_strikes,
             &years_to_expiry,
         );
     }));
 }
 
-criterion_group!(benches, implied_vol_criterion_benchmark, parity_interest_rate_criterion_benchmark);
+criterion_group!(
+    benches,
+    implied_vol_criterion_benchmark,
+    warm_start_criterion_benchmark,
+    parity_interest_rate_criterion_benchmark
+);
 criterion_main!(benches);
\ No newline at end of file
@@ -1,4 +1,5 @@
-import init, { OptionDir, implied_vol, parity_interest_rate } from "./pkg/simd_vol.js";
+import init, { ChainBuffer, OptionDir, parity_interest_rate } from "./pkg/simd_vol.js";
+import * as wasm from "./pkg/simd_vol.js";
 import { AAPL_DATA, SPY_DATA } from "./js/data.js";
 import { plot2D, plot3D } from "./js/plot.js";
 import { parseOptionData } from "./js/parse.js";
@@ -19,9 +20,21 @@ var isCall = true;
 var plotType = "mesh3d";
 var shouldPlot2D = false;
 var option_name = ""
+var call_buffer;
+var put_buffer;
 
 // Initialize WASM and set the initial graph
-init().then(() => {
+init().then(async () => {
+    // initThreadPool only exists in the wasm-threads build and needs SharedArrayBuffer, which
+    // browsers only enable on cross-origin isolated pages. Otherwise stay single-threaded.
+    if (wasm.initThreadPool && window.crossOriginIsolated) {
+        try {
+            await wasm.initThreadPool(navigator.hardwareConcurrency);
+        } catch (err) {
+            console.warn("Falling back to a single thread:", err);
+        }
+    }
+
     setOptionData();
     update(true);
 });
@@ -41,6 +54,23 @@ function setOptionData() {
     years_to_expiry = selectedData.years_to_expiry;
     time = selectedData.time;
     option_name = selectedData.option_name;
+    setBuffers();
+}
+
+// Copies the chain into wasm memory once, so updates only rewrite the rate and dividend columns
+function setBuffers() {
+    call_buffer?.free();
+    put_buffer?.free();
+
+    call_buffer = new ChainBuffer(call_prices.length);
+    put_buffer = new ChainBuffer(put_prices.length);
+
+    // Views are fetched right before use, as any allocation in wasm memory invalidates them
+    for (const [buffer, prices, strikes] of [[call_buffer, call_prices, call_strikes], [put_buffer, put_prices, put_strikes]]) {
+        buffer.price().set(prices);
+        buffer.strike().set(strikes);
+        buffer.years_to_expiry().set(years_to_expiry);
+    }
 }
 
 function handleDataTypeChange() {
@@ -94,6 +124,7 @@ function handleOptionFileChange() {
             years_to_expiry = data.years_to_expiry;
             time = data.time;
             option_name = data.option_name;
+            setBuffers();
             update(true);
         } catch {
             alert("Error parsing option data. Please upload a CSV file from the CBOE Quotes Dashboard.")
@@ -153,35 +184,20 @@ function update(shouldUpdate2D = false) {
     document.getElementById("interestRateText").textContent = `Interest Rate: ${roundToDecimalPlaces(interest_rate * 100)}%`;
     document.getElementById("dividendYieldText").textContent = `Dividend Yield: ${roundToDecimalPlaces(dividend_yield * 100)}%`;
 
-    const n = years_to_expiry.length;
-    const interest_rates = Array(n).fill(interest_rate);
-    const dividend_yields = Array(n).fill(dividend_yield);
-    const spots = Array(n).fill(spot);
     const strikes = isCall ? call_strikes : put_strikes;
 
-    let call_impl_vol = implied_vol(
-        OptionDir.CALL,
-        call_prices,
-        spots,
-        call_strikes,
-        interest_rates,
-        dividend_yields,
-        years_to_expiry,
-        20,
-        0.0001
-    );
-
-    let put_impl_vol = implied_vol(
-        OptionDir.PUT,
-        put_prices,
-        spots,
-        put_strikes,
-        interest_rates,
-        dividend_yields,
-        years_to_expiry,
-        20,
-        0.0001
-    );
+    for (const buffer of [call_buffer, put_buffer]) {
+        buffer.spot().fill(spot);
+        buffer.risk_free_rate().fill(interest_rate);
+        buffer.dividend_yield().fill(dividend_yield);
+    }
+
+    call_buffer.compute_implied_vol(OptionDir.CALL, 20, 0.0001);
+    put_buffer.compute_implied_vol(OptionDir.PUT, 20, 0.0001);
+
+    // Copied out since the plots outlive the views
+    let call_impl_vol = call_buffer.implied_vol().slice();
+    let put_impl_vol = put_buffer.implied_vol().slice();
 
     plot3D(option_name, isCall ? call_impl_vol : put_impl_vol, spot, strikes, years_to_expiry, time, plotType);
 
@@ -1,63 +1,193 @@
 /* tslint:disable */
 /* eslint-disable */
+
+export class ChainBuffer {
+    free(): void;
+    [Symbol.dispose](): void;
+    compute_greeks(option_dir: OptionDir): void;
+    compute_implied_vol(option_dir: OptionDir, max_iterations: number, threshold: number): boolean;
+    compute_implied_vol_guess(option_dir: OptionDir, half_width: number, max_iterations: number, threshold: number): boolean;
+    delta(): Float32Array;
+    dividend_yield(): Float32Array;
+    gamma(): Float32Array;
+    implied_vol(): Float32Array;
+    is_empty(): boolean;
+    constructor(len: number);
+    price(): Float32Array;
+    rho(): Float32Array;
+    risk_free_rate(): Float32Array;
+    spot(): Float32Array;
+    strike(): Float32Array;
+    theta(): Float32Array;
+    vega(): Float32Array;
+    years_to_expiry(): Float32Array;
+    readonly len: number;
+}
+
 /**
-* @param {OptionDir} option_dir
-* @param {Float32Array} price
-* @param {Float32Array} spot
-* @param {Float32Array} strike
-* @param {Float32Array} risk_free_rate
-* @param {Float32Array} dividend_yield
-* @param {Float32Array} years_to_expiry
-* @param {number} max_iterations
-* @param {number} threshold
-* @returns {Float32Array}
-*/
-export function implied_vol(option_dir: OptionDir, price: Float32Array, spot: Float32Array, strike: Float32Array, risk_free_rate: Float32Array, dividend_yield: Float32Array, years_to_expiry: Float32Array, max_iterations: number, threshold: number): Float32Array;
+ * Payoffs priced by montecarlo::monte_carlo_price. The barriers are monitored at the end of
+ * every time step and the Asian option pays off on the average of those spots.
+ */
+export enum ExoticKind {
+    European = 0,
+    UpAndOut = 1,
+    UpAndIn = 2,
+    DownAndOut = 3,
+    DownAndIn = 4,
+    Asian = 5,
+}
+
 /**
-* @param {Float32Array} call_price
-* @param {Float32Array} put_price
-* @param {Float32Array} spot
-* @param {Float32Array} strike
-* @param {Float32Array} years_to_expiry
-* @returns {number}
-*/
-export function parity_interest_rate(call_price: Float32Array, put_price: Float32Array, spot: Float32Array, strike: Float32Array, years_to_expiry: Float32Array): number;
+ * Dynamics of the spot in the Monte Carlo pricer
+ */
+export enum MonteCarloModel {
+    Gbm = 0,
+    LocalVol = 1,
+    Heston = 2,
+}
+
 /**
-* Specify whether an option is put or call
-*/
+ * Specify whether an option is put or call
+ */
 export enum OptionDir {
-  CALL = 2,
-  PUT = 1,
+    CALL = 2,
+    PUT = 1,
+}
+
+/**
+ * Option strategies built by strategy::strategy_legs. Each takes its strikes in ascending
+ * order, see there for the legs.
+ */
+export enum StrategyKind {
+    CallVertical = 0,
+    PutVertical = 1,
+    Straddle = 2,
+    Strangle = 3,
+    IronCondor = 4,
+    Calendar = 5,
+    Butterfly = 6,
+}
+
+/**
+ * Closed-form approximation used by vol32x8::implied_vol_approx. These skip the iterative
+ * solver of vol32x8::implied_vol and are meant for previews or as initial guesses.
+ */
+export enum VolMethod {
+    BrennerSubrahmanyam = 0,
+    CorradoMiller = 1,
+    Li = 2,
 }
 
+export function delta(option_dir: OptionDir, spot: Float32Array, strike: Float32Array, volatility: Float32Array, risk_free_rate: Float32Array, dividend_yield: Float32Array, years_to_expiry: Float32Array): Float32Array;
+
+export function expected_move_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, horizons: Float32Array): string;
+
+export function heston_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, max_iterations: number): string;
+
+export function implied_vol(option_dir: OptionDir, price: Float32Array, spot: Float32Array, strike: Float32Array, risk_free_rate: Float32Array, dividend_yield: Float32Array, years_to_expiry: Float32Array, max_iterations: number, threshold: number): Float32Array;
+
+export function implied_vol_approx(option_dir: OptionDir, price: Float32Array, spot: Float32Array, strike: Float32Array, risk_free_rate: Float32Array, dividend_yield: Float32Array, years_to_expiry: Float32Array, method: VolMethod): Float32Array;
+
+export function implied_vol_guess(option_dir: OptionDir, price: Float32Array, spot: Float32Array, strike: Float32Array, risk_free_rate: Float32Array, dividend_yield: Float32Array, years_to_expiry: Float32Array, guess: Float32Array, half_width: number, max_iterations: number, threshold: number): Float32Array;
+
+export function local_vol_check_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, dividend_yield: number, check_strikes: Float32Array, check_years_to_expiry: Float32Array, num_paths: number, num_steps: number, seed: number): string;
+
+export function local_vol_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, grid_strikes: Float32Array, grid_years_to_expiry: Float32Array): string;
+
+export function monte_carlo_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, dividend_yield: number, kind: ExoticKind, option_dir: OptionDir, exotic_strike: number, barrier: number, exotic_years_to_expiry: number, model: MonteCarloModel, v0: number, kappa: number, theta: number, xi: number, rho: number, num_paths: number, num_steps: number, seed: number): string;
+
+export function num_threads(): number;
+
+export function otm_implied_vol(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, blend_width: number, max_iterations: number, threshold: number): Float32Array;
+
+export function parity_interest_rate(call_price: Float32Array, put_price: Float32Array, spot: Float32Array, strike: Float32Array, years_to_expiry: Float32Array): number;
+
+export function portfolio_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, dividend_yield: number, option_dir: Int32Array, position_strike: Float32Array, position_years_to_expiry: Float32Array, quantity: Float32Array, multiplier: Float32Array): string;
+
+export function price(option_dir: OptionDir, spot: Float32Array, strike: Float32Array, volatility: Float32Array, risk_free_rate: Float32Array, dividend_yield: Float32Array, years_to_expiry: Float32Array): Float32Array;
+
+export function probability_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, option_dir: OptionDir, strikes: Float32Array, expiry: number): string;
+
+export function scenario_grid_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, dividend_yield: number, option_dir: Int32Array, position_strike: Float32Array, position_years_to_expiry: Float32Array, quantity: Float32Array, multiplier: Float32Array, spot_shifts: Float32Array, vol_shifts: Float32Array, parallel: number, skew: number, term: number): string;
+
+export function simd_backend(): string;
+
+export function smile_metrics_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number): string;
+
+export function strategy_json(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number, dividend_yield: number, kind: StrategyKind, strategy_strikes: Float32Array, strategy_years_to_expiry: Float32Array, quantity: number, multiplier: number, spots: Float32Array, horizons: Float32Array): string;
+
+export function term_structure_json(spot: number, strike: Float32Array, years_to_expiry: Float32Array, implied_vol: Float32Array): string;
+
+export function vix_index(call_price: Float32Array, put_price: Float32Array, spot: number, strike: Float32Array, years_to_expiry: Float32Array, risk_free_rate: number): number;
+
 export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
 
 export interface InitOutput {
-  readonly memory: WebAssembly.Memory;
-  readonly implied_vol: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number) => void;
-  readonly parity_interest_rate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => number;
-  readonly __wbindgen_add_to_stack_pointer: (a: number) => number;
-  readonly __wbindgen_malloc: (a: number, b: number) => number;
-  readonly __wbindgen_free: (a: number, b: number, c: number) => void;
+    readonly memory: WebAssembly.Memory;
+    readonly __wbg_chainbuffer_free: (a: number, b: number) => void;
+    readonly chainbuffer_compute_greeks: (a: number, b: number) => void;
+    readonly chainbuffer_compute_implied_vol: (a: number, b: number, c: number, d: number) => number;
+    readonly chainbuffer_compute_implied_vol_guess: (a: number, b: number, c: number, d: number, e: number) => number;
+    readonly chainbuffer_delta: (a: number) => any;
+    readonly chainbuffer_dividend_yield: (a: number) => any;
+    readonly chainbuffer_gamma: (a: number) => any;
+    readonly chainbuffer_implied_vol: (a: number) => any;
+    readonly chainbuffer_is_empty: (a: number) => number;
+    readonly chainbuffer_len: (a: number) => number;
+    readonly chainbuffer_new: (a: number) => number;
+    readonly chainbuffer_price: (a: number) => any;
+    readonly chainbuffer_rho: (a: number) => any;
+    readonly chainbuffer_risk_free_rate: (a: number) => any;
+    readonly chainbuffer_spot: (a: number) => any;
+    readonly chainbuffer_strike: (a: number) => any;
+    readonly chainbuffer_theta: (a: number) => any;
+    readonly chainbuffer_vega: (a: number) => any;
+    readonly chainbuffer_years_to_expiry: (a: number) => any;
+    readonly delta: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number) => [number, number];
+    readonly expected_move_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number) => [number, number];
+    readonly heston_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number) => [number, number];
+    readonly implied_vol: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number) => [number, number];
+    readonly implied_vol_approx: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number) => [number, number];
+    readonly implied_vol_guess: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number) => [number, number];
+    readonly local_vol_check_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number) => [number, number];
+    readonly local_vol_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number) => [number, number];
+    readonly monte_carlo_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number, w: number, x: number, y: number) => [number, number];
+    readonly num_threads: () => number;
+    readonly otm_implied_vol: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number) => [number, number];
+    readonly parity_interest_rate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => number;
+    readonly portfolio_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number) => [number, number];
+    readonly price: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number) => [number, number];
+    readonly probability_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number) => [number, number];
+    readonly scenario_grid_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number, w: number, x: number, y: number, z: number, a1: number, b1: number) => [number, number];
+    readonly simd_backend: () => [number, number];
+    readonly smile_metrics_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => [number, number];
+    readonly strategy_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number) => [number, number];
+    readonly term_structure_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number];
+    readonly vix_index: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => number;
+    readonly __wbindgen_externrefs: WebAssembly.Table;
+    readonly __wbindgen_malloc: (a: number, b: number) => number;
+    readonly __wbindgen_free: (a: number, b: number, c: number) => void;
+    readonly __wbindgen_start: () => void;
 }
 
 export type SyncInitInput = BufferSource | WebAssembly.Module;
+
 /**
-* Instantiates the given `module`, which can either be bytes or
-* a precompiled `WebAssembly.Module`.
-*
-* @param {SyncInitInput} module
-*
-* @returns {InitOutput}
-*/
-export function initSync(module: SyncInitInput): InitOutput;
+ * Instantiates the given `module`, which can either be bytes or
+ * a precompiled `WebAssembly.Module`.
+ *
+ * @param {{ module: SyncInitInput }} module - Passing `SyncInitInput` directly is deprecated.
+ *
+ * @returns {InitOutput}
+ */
+export function initSync(module: { module: SyncInitInput } | SyncInitInput): InitOutput;
 
 /**
-* If `module_or_path` is {RequestInfo} or {URL}, makes a request and
-* for everything else, calls `WebAssembly.instantiate` directly.
-*
-* @param {InitInput | Promise<InitInput>} module_or_path
-*
-* @returns {Promise<InitOutput>}
-*/
-export default function __wbg_init (module_or_path?: InitInput | Promise<InitInput>): Promise<InitOutput>;
+ * If `module_or_path` is {RequestInfo} or {URL}, makes a request and
+ * for everything else, calls `WebAssembly.instantiate` directly.
+ *
+ * @param {{ module_or_path: InitInput | Promise<InitInput> }} module_or_path - Passing `InitInput` directly is deprecated.
+ *
+ * @returns {Promise<InitOutput>}
+ */
+export default function __wbg_init (module_or_path?: { module_or_path: InitInput | Promise<InitInput> } | InitInput | Promise<InitInput>): Promise<InitOutput>;
@@ -1,100 +1,565 @@
-let wasm;
+/* @ts-self-types="./simd_vol.d.ts" */
 
-const cachedTextDecoder = (typeof TextDecoder !== 'undefined' ? new TextDecoder('utf-8', { ignoreBOM: true, fatal: true }) : { decode: () => { throw Error('TextDecoder not available') } } );
+export class ChainBuffer {
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        ChainBufferFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_chainbuffer_free(ptr, 0);
+    }
+    /**
+     * @param {OptionDir} option_dir
+     */
+    compute_greeks(option_dir) {
+        wasm.chainbuffer_compute_greeks(this.__wbg_ptr, option_dir);
+    }
+    /**
+     * @param {OptionDir} option_dir
+     * @param {number} max_iterations
+     * @param {number} threshold
+     * @returns {boolean}
+     */
+    compute_implied_vol(option_dir, max_iterations, threshold) {
+        const ret = wasm.chainbuffer_compute_implied_vol(this.__wbg_ptr, option_dir, max_iterations, threshold);
+        return ret !== 0;
+    }
+    /**
+     * @param {OptionDir} option_dir
+     * @param {number} half_width
+     * @param {number} max_iterations
+     * @param {number} threshold
+     * @returns {boolean}
+     */
+    compute_implied_vol_guess(option_dir, half_width, max_iterations, threshold) {
+        const ret = wasm.chainbuffer_compute_implied_vol_guess(this.__wbg_ptr, option_dir, half_width, max_iterations, threshold);
+        return ret !== 0;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    delta() {
+        const ret = wasm.chainbuffer_delta(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    dividend_yield() {
+        const ret = wasm.chainbuffer_dividend_yield(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    gamma() {
+        const ret = wasm.chainbuffer_gamma(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    implied_vol() {
+        const ret = wasm.chainbuffer_implied_vol(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {boolean}
+     */
+    is_empty() {
+        const ret = wasm.chainbuffer_is_empty(this.__wbg_ptr);
+        return ret !== 0;
+    }
+    /**
+     * @returns {number}
+     */
+    get len() {
+        const ret = wasm.chainbuffer_len(this.__wbg_ptr);
+        return ret >>> 0;
+    }
+    /**
+     * @param {number} len
+     */
+    constructor(len) {
+        const ret = wasm.chainbuffer_new(len);
+        this.__wbg_ptr = ret;
+        ChainBufferFinalization.register(this, this.__wbg_ptr, this);
+        return this;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    price() {
+        const ret = wasm.chainbuffer_price(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    rho() {
+        const ret = wasm.chainbuffer_rho(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    risk_free_rate() {
+        const ret = wasm.chainbuffer_risk_free_rate(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    spot() {
+        const ret = wasm.chainbuffer_spot(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    strike() {
+        const ret = wasm.chainbuffer_strike(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    theta() {
+        const ret = wasm.chainbuffer_theta(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    vega() {
+        const ret = wasm.chainbuffer_vega(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * @returns {Float32Array}
+     */
+    years_to_expiry() {
+        const ret = wasm.chainbuffer_years_to_expiry(this.__wbg_ptr);
+        return ret;
+    }
+}
+if (Symbol.dispose) ChainBuffer.prototype[Symbol.dispose] = ChainBuffer.prototype.free;
 
-if (typeof TextDecoder !== 'undefined') { cachedTextDecoder.decode(); };
+/**
+ * Payoffs priced by montecarlo::monte_carlo_price. The barriers are monitored at the end of
+ * every time step and the Asian option pays off on the average of those spots.
+ * @enum {0 | 1 | 2 | 3 | 4 | 5}
+ */
+export const ExoticKind = Object.freeze({
+    European: 0, "0": "European",
+    UpAndOut: 1, "1": "UpAndOut",
+    UpAndIn: 2, "2": "UpAndIn",
+    DownAndOut: 3, "3": "DownAndOut",
+    DownAndIn: 4, "4": "DownAndIn",
+    Asian: 5, "5": "Asian",
+});
 
-let cachedUint8Memory0 = null;
+/**
+ * Dynamics of the spot in the Monte Carlo pricer
+ * @enum {0 | 1 | 2}
+ */
+export const MonteCarloModel = Object.freeze({
+    Gbm: 0, "0": "Gbm",
+    LocalVol: 1, "1": "LocalVol",
+    Heston: 2, "2": "Heston",
+});
 
-function getUint8Memory0() {
-    if (cachedUint8Memory0 === null || cachedUint8Memory0.byteLength === 0) {
-        cachedUint8Memory0 = new Uint8Array(wasm.memory.buffer);
-    }
-    return cachedUint8Memory0;
-}
+/**
+ * Specify whether an option is put or call
+ * @enum {2 | 1}
+ */
+export const OptionDir = Object.freeze({
+    CALL: 2, "2": "CALL",
+    PUT: 1, "1": "PUT",
+});
 
-function getStringFromWasm0(ptr, len) {
-    ptr = ptr >>> 0;
-    return cachedTextDecoder.decode(getUint8Memory0().subarray(ptr, ptr + len));
+/**
+ * Option strategies built by strategy::strategy_legs. Each takes its strikes in ascending
+ * order, see there for the legs.
+ * @enum {0 | 1 | 2 | 3 | 4 | 5 | 6}
+ */
+export const StrategyKind = Object.freeze({
+    CallVertical: 0, "0": "CallVertical",
+    PutVertical: 1, "1": "PutVertical",
+    Straddle: 2, "2": "Straddle",
+    Strangle: 3, "3": "Strangle",
+    IronCondor: 4, "4": "IronCondor",
+    Calendar: 5, "5": "Calendar",
+    Butterfly: 6, "6": "Butterfly",
+});
+
+/**
+ * Closed-form approximation used by vol32x8::implied_vol_approx. These skip the iterative
+ * solver of vol32x8::implied_vol and are meant for previews or as initial guesses.
+ * @enum {0 | 1 | 2}
+ */
+export const VolMethod = Object.freeze({
+    BrennerSubrahmanyam: 0, "0": "BrennerSubrahmanyam",
+    CorradoMiller: 1, "1": "CorradoMiller",
+    Li: 2, "2": "Li",
+});
+
+/**
+ * @param {OptionDir} option_dir
+ * @param {Float32Array} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} volatility
+ * @param {Float32Array} risk_free_rate
+ * @param {Float32Array} dividend_yield
+ * @param {Float32Array} years_to_expiry
+ * @returns {Float32Array}
+ */
+export function delta(option_dir, spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry) {
+    const ptr0 = passArrayF32ToWasm0(spot, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(volatility, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(risk_free_rate, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ptr4 = passArrayF32ToWasm0(dividend_yield, wasm.__wbindgen_malloc);
+    const len4 = WASM_VECTOR_LEN;
+    const ptr5 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len5 = WASM_VECTOR_LEN;
+    const ret = wasm.delta(option_dir, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5);
+    var v7 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v7;
 }
 
-let cachedFloat32Memory0 = null;
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {Float32Array} horizons
+ * @returns {string}
+ */
+export function expected_move_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, horizons) {
+    let deferred6_0;
+    let deferred6_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ptr4 = passArrayF32ToWasm0(horizons, wasm.__wbindgen_malloc);
+        const len4 = WASM_VECTOR_LEN;
+        const ret = wasm.expected_move_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, ptr4, len4);
+        deferred6_0 = ret[0];
+        deferred6_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred6_0, deferred6_1, 1);
+    }
+}
 
-function getFloat32Memory0() {
-    if (cachedFloat32Memory0 === null || cachedFloat32Memory0.byteLength === 0) {
-        cachedFloat32Memory0 = new Float32Array(wasm.memory.buffer);
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} max_iterations
+ * @returns {string}
+ */
+export function heston_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, max_iterations) {
+    let deferred5_0;
+    let deferred5_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ret = wasm.heston_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, max_iterations);
+        deferred5_0 = ret[0];
+        deferred5_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred5_0, deferred5_1, 1);
     }
-    return cachedFloat32Memory0;
 }
 
-let WASM_VECTOR_LEN = 0;
+/**
+ * @param {OptionDir} option_dir
+ * @param {Float32Array} price
+ * @param {Float32Array} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} risk_free_rate
+ * @param {Float32Array} dividend_yield
+ * @param {Float32Array} years_to_expiry
+ * @param {number} max_iterations
+ * @param {number} threshold
+ * @returns {Float32Array}
+ */
+export function implied_vol(option_dir, price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry, max_iterations, threshold) {
+    const ptr0 = passArrayF32ToWasm0(price, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(spot, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(risk_free_rate, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ptr4 = passArrayF32ToWasm0(dividend_yield, wasm.__wbindgen_malloc);
+    const len4 = WASM_VECTOR_LEN;
+    const ptr5 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len5 = WASM_VECTOR_LEN;
+    const ret = wasm.implied_vol(option_dir, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5, max_iterations, threshold);
+    var v7 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v7;
+}
 
-function passArrayF32ToWasm0(arg, malloc) {
-    const ptr = malloc(arg.length * 4, 4) >>> 0;
-    getFloat32Memory0().set(arg, ptr / 4);
-    WASM_VECTOR_LEN = arg.length;
-    return ptr;
+/**
+ * @param {OptionDir} option_dir
+ * @param {Float32Array} price
+ * @param {Float32Array} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} risk_free_rate
+ * @param {Float32Array} dividend_yield
+ * @param {Float32Array} years_to_expiry
+ * @param {VolMethod} method
+ * @returns {Float32Array}
+ */
+export function implied_vol_approx(option_dir, price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry, method) {
+    const ptr0 = passArrayF32ToWasm0(price, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(spot, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(risk_free_rate, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ptr4 = passArrayF32ToWasm0(dividend_yield, wasm.__wbindgen_malloc);
+    const len4 = WASM_VECTOR_LEN;
+    const ptr5 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len5 = WASM_VECTOR_LEN;
+    const ret = wasm.implied_vol_approx(option_dir, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5, method);
+    var v7 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v7;
 }
 
-let cachedInt32Memory0 = null;
+/**
+ * @param {OptionDir} option_dir
+ * @param {Float32Array} price
+ * @param {Float32Array} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} risk_free_rate
+ * @param {Float32Array} dividend_yield
+ * @param {Float32Array} years_to_expiry
+ * @param {Float32Array} guess
+ * @param {number} half_width
+ * @param {number} max_iterations
+ * @param {number} threshold
+ * @returns {Float32Array}
+ */
+export function implied_vol_guess(option_dir, price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry, guess, half_width, max_iterations, threshold) {
+    const ptr0 = passArrayF32ToWasm0(price, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(spot, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(risk_free_rate, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ptr4 = passArrayF32ToWasm0(dividend_yield, wasm.__wbindgen_malloc);
+    const len4 = WASM_VECTOR_LEN;
+    const ptr5 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len5 = WASM_VECTOR_LEN;
+    const ptr6 = passArrayF32ToWasm0(guess, wasm.__wbindgen_malloc);
+    const len6 = WASM_VECTOR_LEN;
+    const ret = wasm.implied_vol_guess(option_dir, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5, ptr6, len6, half_width, max_iterations, threshold);
+    var v8 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v8;
+}
 
-function getInt32Memory0() {
-    if (cachedInt32Memory0 === null || cachedInt32Memory0.byteLength === 0) {
-        cachedInt32Memory0 = new Int32Array(wasm.memory.buffer);
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} dividend_yield
+ * @param {Float32Array} check_strikes
+ * @param {Float32Array} check_years_to_expiry
+ * @param {number} num_paths
+ * @param {number} num_steps
+ * @param {number} seed
+ * @returns {string}
+ */
+export function local_vol_check_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, dividend_yield, check_strikes, check_years_to_expiry, num_paths, num_steps, seed) {
+    let deferred7_0;
+    let deferred7_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ptr4 = passArrayF32ToWasm0(check_strikes, wasm.__wbindgen_malloc);
+        const len4 = WASM_VECTOR_LEN;
+        const ptr5 = passArrayF32ToWasm0(check_years_to_expiry, wasm.__wbindgen_malloc);
+        const len5 = WASM_VECTOR_LEN;
+        const ret = wasm.local_vol_check_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, dividend_yield, ptr4, len4, ptr5, len5, num_paths, num_steps, seed);
+        deferred7_0 = ret[0];
+        deferred7_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred7_0, deferred7_1, 1);
     }
-    return cachedInt32Memory0;
 }
 
-function getArrayF32FromWasm0(ptr, len) {
-    ptr = ptr >>> 0;
-    return getFloat32Memory0().subarray(ptr / 4, ptr / 4 + len);
-}
-/**
-* @param {OptionDir} option_dir
-* @param {Float32Array} price
-* @param {Float32Array} spot
-* @param {Float32Array} strike
-* @param {Float32Array} risk_free_rate
-* @param {Float32Array} dividend_yield
-* @param {Float32Array} years_to_expiry
-* @param {number} max_iterations
-* @param {number} threshold
-* @returns {Float32Array}
-*/
-export function implied_vol(option_dir, price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry, max_iterations, threshold) {
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {Float32Array} grid_strikes
+ * @param {Float32Array} grid_years_to_expiry
+ * @returns {string}
+ */
+export function local_vol_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, grid_strikes, grid_years_to_expiry) {
+    let deferred7_0;
+    let deferred7_1;
     try {
-        const retptr = wasm.__wbindgen_add_to_stack_pointer(-16);
-        const ptr0 = passArrayF32ToWasm0(price, wasm.__wbindgen_malloc);
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
         const len0 = WASM_VECTOR_LEN;
-        const ptr1 = passArrayF32ToWasm0(spot, wasm.__wbindgen_malloc);
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
         const len1 = WASM_VECTOR_LEN;
         const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
         const len2 = WASM_VECTOR_LEN;
-        const ptr3 = passArrayF32ToWasm0(risk_free_rate, wasm.__wbindgen_malloc);
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
         const len3 = WASM_VECTOR_LEN;
-        const ptr4 = passArrayF32ToWasm0(dividend_yield, wasm.__wbindgen_malloc);
+        const ptr4 = passArrayF32ToWasm0(grid_strikes, wasm.__wbindgen_malloc);
         const len4 = WASM_VECTOR_LEN;
-        const ptr5 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const ptr5 = passArrayF32ToWasm0(grid_years_to_expiry, wasm.__wbindgen_malloc);
         const len5 = WASM_VECTOR_LEN;
-        wasm.implied_vol(retptr, option_dir, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5, max_iterations, threshold);
-        var r0 = getInt32Memory0()[retptr / 4 + 0];
-        var r1 = getInt32Memory0()[retptr / 4 + 1];
-        var v7 = getArrayF32FromWasm0(r0, r1).slice();
-        wasm.__wbindgen_free(r0, r1 * 4, 4);
-        return v7;
+        const ret = wasm.local_vol_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, ptr4, len4, ptr5, len5);
+        deferred7_0 = ret[0];
+        deferred7_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred7_0, deferred7_1, 1);
+    }
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} dividend_yield
+ * @param {ExoticKind} kind
+ * @param {OptionDir} option_dir
+ * @param {number} exotic_strike
+ * @param {number} barrier
+ * @param {number} exotic_years_to_expiry
+ * @param {MonteCarloModel} model
+ * @param {number} v0
+ * @param {number} kappa
+ * @param {number} theta
+ * @param {number} xi
+ * @param {number} rho
+ * @param {number} num_paths
+ * @param {number} num_steps
+ * @param {number} seed
+ * @returns {string}
+ */
+export function monte_carlo_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, dividend_yield, kind, option_dir, exotic_strike, barrier, exotic_years_to_expiry, model, v0, kappa, theta, xi, rho, num_paths, num_steps, seed) {
+    let deferred5_0;
+    let deferred5_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ret = wasm.monte_carlo_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, dividend_yield, kind, option_dir, exotic_strike, barrier, exotic_years_to_expiry, model, v0, kappa, theta, xi, rho, num_paths, num_steps, seed);
+        deferred5_0 = ret[0];
+        deferred5_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
     } finally {
-        wasm.__wbindgen_add_to_stack_pointer(16);
+        wasm.__wbindgen_free(deferred5_0, deferred5_1, 1);
     }
 }
 
 /**
-* @param {Float32Array} call_price
-* @param {Float32Array} put_price
-* @param {Float32Array} spot
-* @param {Float32Array} strike
-* @param {Float32Array} years_to_expiry
-* @returns {number}
-*/
+ * @returns {number}
+ */
+export function num_threads() {
+    const ret = wasm.num_threads();
+    return ret >>> 0;
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} blend_width
+ * @param {number} max_iterations
+ * @param {number} threshold
+ * @returns {Float32Array}
+ */
+export function otm_implied_vol(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, blend_width, max_iterations, threshold) {
+    const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ret = wasm.otm_implied_vol(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, blend_width, max_iterations, threshold);
+    var v5 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v5;
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {Float32Array} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @returns {number}
+ */
 export function parity_interest_rate(call_price, put_price, spot, strike, years_to_expiry) {
     const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
     const len0 = WASM_VECTOR_LEN;
@@ -111,100 +576,498 @@ export function parity_interest_rate(call_price, put_price, spot, strike, years_
 }
 
 /**
-* Specify whether an option is put or call
-*/
-export const OptionDir = Object.freeze({ CALL:2,"2":"CALL",PUT:1,"1":"PUT", });
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} dividend_yield
+ * @param {Int32Array} option_dir
+ * @param {Float32Array} position_strike
+ * @param {Float32Array} position_years_to_expiry
+ * @param {Float32Array} quantity
+ * @param {Float32Array} multiplier
+ * @returns {string}
+ */
+export function portfolio_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, dividend_yield, option_dir, position_strike, position_years_to_expiry, quantity, multiplier) {
+    let deferred10_0;
+    let deferred10_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ptr4 = passArray32ToWasm0(option_dir, wasm.__wbindgen_malloc);
+        const len4 = WASM_VECTOR_LEN;
+        const ptr5 = passArrayF32ToWasm0(position_strike, wasm.__wbindgen_malloc);
+        const len5 = WASM_VECTOR_LEN;
+        const ptr6 = passArrayF32ToWasm0(position_years_to_expiry, wasm.__wbindgen_malloc);
+        const len6 = WASM_VECTOR_LEN;
+        const ptr7 = passArrayF32ToWasm0(quantity, wasm.__wbindgen_malloc);
+        const len7 = WASM_VECTOR_LEN;
+        const ptr8 = passArrayF32ToWasm0(multiplier, wasm.__wbindgen_malloc);
+        const len8 = WASM_VECTOR_LEN;
+        const ret = wasm.portfolio_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, dividend_yield, ptr4, len4, ptr5, len5, ptr6, len6, ptr7, len7, ptr8, len8);
+        deferred10_0 = ret[0];
+        deferred10_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred10_0, deferred10_1, 1);
+    }
+}
+
+/**
+ * @param {OptionDir} option_dir
+ * @param {Float32Array} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} volatility
+ * @param {Float32Array} risk_free_rate
+ * @param {Float32Array} dividend_yield
+ * @param {Float32Array} years_to_expiry
+ * @returns {Float32Array}
+ */
+export function price(option_dir, spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry) {
+    const ptr0 = passArrayF32ToWasm0(spot, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(volatility, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(risk_free_rate, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ptr4 = passArrayF32ToWasm0(dividend_yield, wasm.__wbindgen_malloc);
+    const len4 = WASM_VECTOR_LEN;
+    const ptr5 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len5 = WASM_VECTOR_LEN;
+    const ret = wasm.price(option_dir, ptr0, len0, ptr1, len1, ptr2, len2, ptr3, len3, ptr4, len4, ptr5, len5);
+    var v7 = getArrayF32FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 4, 4);
+    return v7;
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {OptionDir} option_dir
+ * @param {Float32Array} strikes
+ * @param {number} expiry
+ * @returns {string}
+ */
+export function probability_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, option_dir, strikes, expiry) {
+    let deferred6_0;
+    let deferred6_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ptr4 = passArrayF32ToWasm0(strikes, wasm.__wbindgen_malloc);
+        const len4 = WASM_VECTOR_LEN;
+        const ret = wasm.probability_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, option_dir, ptr4, len4, expiry);
+        deferred6_0 = ret[0];
+        deferred6_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred6_0, deferred6_1, 1);
+    }
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} dividend_yield
+ * @param {Int32Array} option_dir
+ * @param {Float32Array} position_strike
+ * @param {Float32Array} position_years_to_expiry
+ * @param {Float32Array} quantity
+ * @param {Float32Array} multiplier
+ * @param {Float32Array} spot_shifts
+ * @param {Float32Array} vol_shifts
+ * @param {number} parallel
+ * @param {number} skew
+ * @param {number} term
+ * @returns {string}
+ */
+export function scenario_grid_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, dividend_yield, option_dir, position_strike, position_years_to_expiry, quantity, multiplier, spot_shifts, vol_shifts, parallel, skew, term) {
+    let deferred12_0;
+    let deferred12_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ptr4 = passArray32ToWasm0(option_dir, wasm.__wbindgen_malloc);
+        const len4 = WASM_VECTOR_LEN;
+        const ptr5 = passArrayF32ToWasm0(position_strike, wasm.__wbindgen_malloc);
+        const len5 = WASM_VECTOR_LEN;
+        const ptr6 = passArrayF32ToWasm0(position_years_to_expiry, wasm.__wbindgen_malloc);
+        const len6 = WASM_VECTOR_LEN;
+        const ptr7 = passArrayF32ToWasm0(quantity, wasm.__wbindgen_malloc);
+        const len7 = WASM_VECTOR_LEN;
+        const ptr8 = passArrayF32ToWasm0(multiplier, wasm.__wbindgen_malloc);
+        const len8 = WASM_VECTOR_LEN;
+        const ptr9 = passArrayF32ToWasm0(spot_shifts, wasm.__wbindgen_malloc);
+        const len9 = WASM_VECTOR_LEN;
+        const ptr10 = passArrayF32ToWasm0(vol_shifts, wasm.__wbindgen_malloc);
+        const len10 = WASM_VECTOR_LEN;
+        const ret = wasm.scenario_grid_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, dividend_yield, ptr4, len4, ptr5, len5, ptr6, len6, ptr7, len7, ptr8, len8, ptr9, len9, ptr10, len10, parallel, skew, term);
+        deferred12_0 = ret[0];
+        deferred12_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred12_0, deferred12_1, 1);
+    }
+}
+
+/**
+ * @returns {string}
+ */
+export function simd_backend() {
+    let deferred1_0;
+    let deferred1_1;
+    try {
+        const ret = wasm.simd_backend();
+        deferred1_0 = ret[0];
+        deferred1_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred1_0, deferred1_1, 1);
+    }
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @returns {string}
+ */
+export function smile_metrics_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+    let deferred5_0;
+    let deferred5_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ret = wasm.smile_metrics_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate);
+        deferred5_0 = ret[0];
+        deferred5_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred5_0, deferred5_1, 1);
+    }
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @param {number} dividend_yield
+ * @param {StrategyKind} kind
+ * @param {Float32Array} strategy_strikes
+ * @param {Float32Array} strategy_years_to_expiry
+ * @param {number} quantity
+ * @param {number} multiplier
+ * @param {Float32Array} spots
+ * @param {Float32Array} horizons
+ * @returns {string}
+ */
+export function strategy_json(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate, dividend_yield, kind, strategy_strikes, strategy_years_to_expiry, quantity, multiplier, spots, horizons) {
+    let deferred9_0;
+    let deferred9_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len3 = WASM_VECTOR_LEN;
+        const ptr4 = passArrayF32ToWasm0(strategy_strikes, wasm.__wbindgen_malloc);
+        const len4 = WASM_VECTOR_LEN;
+        const ptr5 = passArrayF32ToWasm0(strategy_years_to_expiry, wasm.__wbindgen_malloc);
+        const len5 = WASM_VECTOR_LEN;
+        const ptr6 = passArrayF32ToWasm0(spots, wasm.__wbindgen_malloc);
+        const len6 = WASM_VECTOR_LEN;
+        const ptr7 = passArrayF32ToWasm0(horizons, wasm.__wbindgen_malloc);
+        const len7 = WASM_VECTOR_LEN;
+        const ret = wasm.strategy_json(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate, dividend_yield, kind, ptr4, len4, ptr5, len5, quantity, multiplier, ptr6, len6, ptr7, len7);
+        deferred9_0 = ret[0];
+        deferred9_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred9_0, deferred9_1, 1);
+    }
+}
+
+/**
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {Float32Array} implied_vol
+ * @returns {string}
+ */
+export function term_structure_json(spot, strike, years_to_expiry, implied_vol) {
+    let deferred4_0;
+    let deferred4_1;
+    try {
+        const ptr0 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ptr1 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+        const len1 = WASM_VECTOR_LEN;
+        const ptr2 = passArrayF32ToWasm0(implied_vol, wasm.__wbindgen_malloc);
+        const len2 = WASM_VECTOR_LEN;
+        const ret = wasm.term_structure_json(spot, ptr0, len0, ptr1, len1, ptr2, len2);
+        deferred4_0 = ret[0];
+        deferred4_1 = ret[1];
+        return getStringFromWasm0(ret[0], ret[1]);
+    } finally {
+        wasm.__wbindgen_free(deferred4_0, deferred4_1, 1);
+    }
+}
+
+/**
+ * @param {Float32Array} call_price
+ * @param {Float32Array} put_price
+ * @param {number} spot
+ * @param {Float32Array} strike
+ * @param {Float32Array} years_to_expiry
+ * @param {number} risk_free_rate
+ * @returns {number}
+ */
+export function vix_index(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+    const ptr0 = passArrayF32ToWasm0(call_price, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ptr1 = passArrayF32ToWasm0(put_price, wasm.__wbindgen_malloc);
+    const len1 = WASM_VECTOR_LEN;
+    const ptr2 = passArrayF32ToWasm0(strike, wasm.__wbindgen_malloc);
+    const len2 = WASM_VECTOR_LEN;
+    const ptr3 = passArrayF32ToWasm0(years_to_expiry, wasm.__wbindgen_malloc);
+    const len3 = WASM_VECTOR_LEN;
+    const ret = wasm.vix_index(ptr0, len0, ptr1, len1, spot, ptr2, len2, ptr3, len3, risk_free_rate);
+    return ret;
+}
+function __wbg_get_imports() {
+    const import0 = {
+        __proto__: null,
+        __wbg___wbindgen_throw_41e9ee4f547fc59a: function(arg0, arg1) {
+            throw new Error(getStringFromWasm0(arg0, arg1));
+        },
+        __wbindgen_generic_0000000000000001: function(arg0, arg1) {
+            // Cast intrinsic for `Ref(Slice(F32)) -> NamedExternref("Float32Array")`.
+            const ret = getArrayF32FromWasm0(arg0, arg1);
+            return ret;
+        },
+        __wbindgen_init_externref_table: function() {
+            const table = wasm.__wbindgen_externrefs;
+            const offset = table.grow(4);
+            table.set(0, undefined);
+            table.set(offset + 0, undefined);
+            table.set(offset + 1, null);
+            table.set(offset + 2, true);
+            table.set(offset + 3, false);
+        },
+    };
+    return {
+        __proto__: null,
+        "./simd_vol_bg.js": import0,
+    };
+}
+
+const ChainBufferFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_chainbuffer_free(ptr, 1));
+
+function getArrayF32FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getFloat32ArrayMemory0().subarray(ptr / 4, ptr / 4 + len);
+}
+
+let cachedFloat32ArrayMemory0 = null;
+function getFloat32ArrayMemory0() {
+    if (cachedFloat32ArrayMemory0 === null || cachedFloat32ArrayMemory0.byteLength === 0) {
+        cachedFloat32ArrayMemory0 = new Float32Array(wasm.memory.buffer);
+    }
+    return cachedFloat32ArrayMemory0;
+}
+
+function getStringFromWasm0(ptr, len) {
+    return decodeText(ptr >>> 0, len);
+}
+
+let cachedUint32ArrayMemory0 = null;
+function getUint32ArrayMemory0() {
+    if (cachedUint32ArrayMemory0 === null || cachedUint32ArrayMemory0.byteLength === 0) {
+        cachedUint32ArrayMemory0 = new Uint32Array(wasm.memory.buffer);
+    }
+    return cachedUint32ArrayMemory0;
+}
+
+let cachedUint8ArrayMemory0 = null;
+function getUint8ArrayMemory0() {
+    if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
+        cachedUint8ArrayMemory0 = new Uint8Array(wasm.memory.buffer);
+    }
+    return cachedUint8ArrayMemory0;
+}
+
+function passArray32ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 4, 4) >>> 0;
+    getUint32ArrayMemory0().set(arg, ptr / 4);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
+function passArrayF32ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 4, 4) >>> 0;
+    getFloat32ArrayMemory0().set(arg, ptr / 4);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
+let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+cachedTextDecoder.decode();
+const MAX_SAFARI_DECODE_BYTES = 2146435072;
+let numBytesDecoded = 0;
+function decodeText(ptr, len) {
+    numBytesDecoded += len;
+    if (numBytesDecoded >= MAX_SAFARI_DECODE_BYTES) {
+        cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+        cachedTextDecoder.decode();
+        numBytesDecoded = len;
+    }
+    return cachedTextDecoder.decode(getUint8ArrayMemory0().subarray(ptr, ptr + len));
+}
+
+let WASM_VECTOR_LEN = 0;
+
+let wasmModule, wasmInstance, wasm;
+function __wbg_finalize_init(instance, module) {
+    wasmInstance = instance;
+    wasm = instance.exports;
+    wasmModule = module;
+    cachedFloat32ArrayMemory0 = null;
+    cachedUint32ArrayMemory0 = null;
+    cachedUint8ArrayMemory0 = null;
+    wasm.__wbindgen_start();
+    return wasm;
+}
 
 async function __wbg_load(module, imports) {
     if (typeof Response === 'function' && module instanceof Response) {
+        if (!module.ok) {
+            throw new Error(`failed to fetch Wasm: ${module.status} ${module.statusText} fetching '${module.url}'`);
+        }
+
         if (typeof WebAssembly.instantiateStreaming === 'function') {
             try {
                 return await WebAssembly.instantiateStreaming(module, imports);
-
             } catch (e) {
-                if (module.headers.get('Content-Type') != 'application/wasm') {
-                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
+                const validResponse = expectedResponseType(module.type);
+
+                if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
+                    console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
 
-                } else {
-                    throw e;
-                }
+                } else { throw e; }
             }
         }
 
         const bytes = await module.arrayBuffer();
         return await WebAssembly.instantiate(bytes, imports);
-
     } else {
         const instance = await WebAssembly.instantiate(module, imports);
 
         if (instance instanceof WebAssembly.Instance) {
             return { instance, module };
-
         } else {
             return instance;
         }
     }
-}
-
-function __wbg_get_imports() {
-    const imports = {};
-    imports.wbg = {};
-    imports.wbg.__wbindgen_throw = function(arg0, arg1) {
-        throw new Error(getStringFromWasm0(arg0, arg1));
-    };
-
-    return imports;
-}
-
-function __wbg_init_memory(imports, maybe_memory) {
-
-}
-
-function __wbg_finalize_init(instance, module) {
-    wasm = instance.exports;
-    __wbg_init.__wbindgen_wasm_module = module;
-    cachedFloat32Memory0 = null;
-    cachedInt32Memory0 = null;
-    cachedUint8Memory0 = null;
-
 
-    return wasm;
+    function expectedResponseType(type) {
+        switch (type) {
+            case 'basic': case 'cors': case 'default': return true;
+        }
+        return false;
+    }
 }
 
 function initSync(module) {
     if (wasm !== undefined) return wasm;
 
-    const imports = __wbg_get_imports();
 
-    __wbg_init_memory(imports);
+    if (module !== undefined) {
+        if (Object.getPrototypeOf(module) === Object.prototype) {
+            ({module} = module)
+        } else {
+            console.warn('using deprecated parameters for `initSync()`; pass a single object instead')
+        }
+    }
 
+    const imports = __wbg_get_imports();
     if (!(module instanceof WebAssembly.Module)) {
         module = new WebAssembly.Module(module);
     }
-
     const instance = new WebAssembly.Instance(module, imports);
-
     return __wbg_finalize_init(instance, module);
 }
 
-async function __wbg_init(input) {
+async function __wbg_init(module_or_path) {
     if (wasm !== undefined) return wasm;
 
-    if (typeof input === 'undefined') {
-        input = new URL('simd_vol_bg.wasm', import.meta.url);
+
+    if (module_or_path !== undefined) {
+        if (Object.getPrototypeOf(module_or_path) === Object.prototype) {
+            ({module_or_path} = module_or_path)
+        } else {
+            console.warn('using deprecated parameters for the initialization function; pass a single object instead')
+        }
     }
-    const imports = __wbg_get_imports();
 
-    if (typeof input === 'string' || (typeof Request === 'function' && input instanceof Request) || (typeof URL === 'function' && input instanceof URL)) {
-        input = fetch(input);
+    if (module_or_path === undefined) {
+        module_or_path = new URL('simd_vol_bg.wasm', import.meta.url);
     }
+    const imports = __wbg_get_imports();
 
-    __wbg_init_memory(imports);
+    if (typeof module_or_path === 'string' || (typeof Request === 'function' && module_or_path instanceof Request) || (typeof URL === 'function' && module_or_path instanceof URL)) {
+        module_or_path = fetch(module_or_path);
+    }
 
-    const { instance, module } = await __wbg_load(await input, imports);
+    const { instance, module } = await __wbg_load(await module_or_path, imports);
 
     return __wbg_finalize_init(instance, module);
 }
 
-export { initSync }
-export default __wbg_init;
+export { initSync, __wbg_init as default };
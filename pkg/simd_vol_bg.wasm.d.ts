@@ -1,8 +1,47 @@
 /* tslint:disable */
 /* eslint-disable */
 export const memory: WebAssembly.Memory;
-export function implied_vol(a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number): void;
-export function parity_interest_rate(a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number): number;
-export function __wbindgen_add_to_stack_pointer(a: number): number;
-export function __wbindgen_malloc(a: number, b: number): number;
-export function __wbindgen_free(a: number, b: number, c: number): void;
+export const __wbg_chainbuffer_free: (a: number, b: number) => void;
+export const chainbuffer_compute_greeks: (a: number, b: number) => void;
+export const chainbuffer_compute_implied_vol: (a: number, b: number, c: number, d: number) => number;
+export const chainbuffer_compute_implied_vol_guess: (a: number, b: number, c: number, d: number, e: number) => number;
+export const chainbuffer_delta: (a: number) => any;
+export const chainbuffer_dividend_yield: (a: number) => any;
+export const chainbuffer_gamma: (a: number) => any;
+export const chainbuffer_implied_vol: (a: number) => any;
+export const chainbuffer_is_empty: (a: number) => number;
+export const chainbuffer_len: (a: number) => number;
+export const chainbuffer_new: (a: number) => number;
+export const chainbuffer_price: (a: number) => any;
+export const chainbuffer_rho: (a: number) => any;
+export const chainbuffer_risk_free_rate: (a: number) => any;
+export const chainbuffer_spot: (a: number) => any;
+export const chainbuffer_strike: (a: number) => any;
+export const chainbuffer_theta: (a: number) => any;
+export const chainbuffer_vega: (a: number) => any;
+export const chainbuffer_years_to_expiry: (a: number) => any;
+export const delta: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number) => [number, number];
+export const expected_move_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number) => [number, number];
+export const heston_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number) => [number, number];
+export const implied_vol: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number) => [number, number];
+export const implied_vol_approx: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number) => [number, number];
+export const implied_vol_guess: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number) => [number, number];
+export const local_vol_check_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number) => [number, number];
+export const local_vol_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number) => [number, number];
+export const monte_carlo_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number, w: number, x: number, y: number) => [number, number];
+export const num_threads: () => number;
+export const otm_implied_vol: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number) => [number, number];
+export const parity_interest_rate: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => number;
+export const portfolio_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number) => [number, number];
+export const price: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number) => [number, number];
+export const probability_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number) => [number, number];
+export const scenario_grid_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number, w: number, x: number, y: number, z: number, a1: number, b1: number) => [number, number];
+export const simd_backend: () => [number, number];
+export const smile_metrics_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => [number, number];
+export const strategy_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number, k: number, l: number, m: number, n: number, o: number, p: number, q: number, r: number, s: number, t: number, u: number, v: number) => [number, number];
+export const term_structure_json: (a: number, b: number, c: number, d: number, e: number, f: number, g: number) => [number, number];
+export const vix_index: (a: number, b: number, c: number, d: number, e: number, f: number, g: number, h: number, i: number, j: number) => number;
+export const __wbindgen_externrefs: WebAssembly.Table;
+export const __wbindgen_malloc: (a: number, b: number) => number;
+export const __wbindgen_free: (a: number, b: number, c: number) => void;
+export const __wbindgen_start: () => void;
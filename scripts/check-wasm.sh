@@ -0,0 +1,13 @@
+#!/bin/sh
+# Checks that both browser builds compile:
+#   - the default single-threaded build (stable, simd128)
+#   - the wasm-threads build (nightly, atomics and a rebuilt std, as wasm-bindgen-rayon requires)
+# Needs the wasm32-unknown-unknown target for stable and nightly, and rust-src for nightly.
+set -e
+
+RUSTFLAGS='-C target-feature=+simd128' \
+    cargo check --lib --target wasm32-unknown-unknown
+
+RUSTFLAGS='-C target-feature=+simd128,+atomics,+bulk-memory,+mutable-globals' \
+    cargo +nightly check --lib --target wasm32-unknown-unknown --features wasm-threads \
+    -Z build-std=panic_abort,std
@@ -0,0 +1,29 @@
+use simd_vol::server;
+
+// Serves the surface engine over HTTP/JSON on localhost, see src/server.rs for the endpoints.
+
+const USAGE: &str = "Usage: simd-vol-server [--addr <host:port>]    (default: 127.0.0.1:8080)";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let addr = match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>().as_slice() {
+        [] => "127.0.0.1:8080",
+        ["--addr", addr] => addr,
+        ["-h" | "--help"] => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    eprintln!("simd-vol-server: listening on http://{}", addr);
+
+    if let Err(err) = server::serve(addr) {
+        eprintln!("simd-vol-server: {}", err);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,329 @@
+use std::fs;
+use std::io::{ self, Write };
+use simd_vol::chain::{ OptionChain, QuoteAnalytics };
+use simd_vol::filter::{ self, FilterConfig };
+use simd_vol::read_hist;
+
+// Batch implied volatility and Greeks for a whole chain, for jobs that run without the browser.
+
+const USAGE: &str =
+    "Usage: simd-vol <input> [options]
+
+Reads a CBOE quote table (.csv, .dat) or a JSON chain (.json) and writes the implied
+volatility and Greeks of every contract.
+
+Options:
+  -o, --output <path>         Output file (default: stdout)
+  -f, --format <format>       csv, json or parquet (default: output extension, else csv)
+  -r, --rate <rate>           Risk free rate (default: implied by put-call parity)
+  -q, --dividend-yield <q>    Dividend yield (default: 0)
+      --min-bid <bid>         Drop contracts bid below this (default: 0)
+      --max-spread <spread>   Drop contracts with a wider (ask - bid) / mid
+      --otm-only              Keep only out-of-the-money contracts
+      --max-iterations <n>    Bisection iterations (default: 20)
+      --threshold <t>         Bisection tolerance (default: 0.0001)
+  -h, --help                  Print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+    Parquet,
+}
+
+struct Args {
+    input: String,
+    output: Option<String>,
+    format: Format,
+    risk_free_rate: Option<f32>,
+    dividend_yield: f32,
+    filter: FilterConfig,
+    max_iterations: i32,
+    threshold: f32,
+}
+
+fn parse_format(format: &str) -> Result<Format, String> {
+    match format.to_lowercase().as_str() {
+        "csv" => Ok(Format::Csv),
+        "json" => Ok(Format::Json),
+        "parquet" => Ok(Format::Parquet),
+        _ => Err(format!("unknown format '{}'", format)),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let mut input = None;
+    let mut output: Option<String> = None;
+    let mut format = None;
+    let mut risk_free_rate = None;
+    let mut dividend_yield = 0.0;
+    let mut filter = FilterConfig::default();
+    let mut max_iterations = 20;
+    let mut threshold = 0.0001;
+
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("missing value for {}", arg));
+        let number = |value: &String| {
+            value.parse::<f32>().map_err(|_| format!("invalid number '{}' for {}", value, arg))
+        };
+
+        match arg.as_str() {
+            "-h" | "--help" => {
+                return Ok(None);
+            }
+            "-o" | "--output" => {
+                output = Some(value()?.clone());
+            }
+            "-f" | "--format" => {
+                format = Some(parse_format(value()?)?);
+            }
+            "-r" | "--rate" => {
+                risk_free_rate = Some(number(value()?)?);
+            }
+            "-q" | "--dividend-yield" => {
+                dividend_yield = number(value()?)?;
+            }
+            "--min-bid" => {
+                filter.min_bid = number(value()?)?;
+            }
+            "--max-spread" => {
+                filter.max_relative_spread = number(value()?)?;
+            }
+            "--otm-only" => {
+                filter.otm_only = true;
+            }
+            "--max-iterations" => {
+                let value = value()?;
+                max_iterations = value
+                    .parse()
+                    .map_err(|_| format!("invalid integer '{}' for {}", value, arg))?;
+            }
+            "--threshold" => {
+                threshold = number(value()?)?;
+            }
+            _ if arg.starts_with('-') => {
+                return Err(format!("unknown option {}", arg));
+            }
+            _ if input.is_none() => {
+                input = Some(arg.clone());
+            }
+            _ => {
+                return Err(format!("unexpected argument {}", arg));
+            }
+        }
+    }
+
+    let input = input.ok_or("missing input file")?;
+
+    // Fall back to the output extension, then CSV
+    let format = match format {
+        Some(format) => format,
+        None =>
+            output
+                .as_deref()
+                .and_then(|path| path.rsplit_once('.'))
+                .and_then(|(_, extension)| parse_format(extension).ok())
+                .unwrap_or(Format::Csv),
+    };
+
+    Ok(
+        Some(Args {
+            input,
+            output,
+            format,
+            risk_free_rate,
+            dividend_yield,
+            filter,
+            max_iterations,
+            threshold,
+        })
+    )
+}
+
+fn read_chain(path: &str) -> Result<OptionChain, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    let chain = if path.to_lowercase().ends_with(".json") {
+        read_hist::parse_json_chain(&contents)
+    } else {
+        read_hist::parse_cboe_chain(&contents)
+    };
+
+    chain.map_err(|err| format!("{}: {}", path, err))
+}
+
+fn write_csv(rows: &[QuoteAnalytics], out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "name,option_type,strike,years_to_expiry,bid,ask,mid,implied_vol,delta,gamma,vega,theta,rho"
+    )?;
+
+    for row in rows {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            row.name,
+            row.option_type,
+            row.strike,
+            row.years_to_expiry,
+            row.bid,
+            row.ask,
+            row.mid,
+            row.implied_vol,
+            row.delta,
+            row.gamma,
+            row.vega,
+            row.theta,
+            row.rho
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet(rows: &[QuoteAnalytics], path: &str) -> Result<(), String> {
+    use std::sync::Arc;
+    use parquet::data_type::{ ByteArray, ByteArrayType, FloatType };
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    const SCHEMA: &str =
+        "message option_row {
+        REQUIRED BYTE_ARRAY name (UTF8);
+        REQUIRED BYTE_ARRAY option_type (UTF8);
+        REQUIRED FLOAT strike;
+        REQUIRED FLOAT years_to_expiry;
+        REQUIRED FLOAT bid;
+        REQUIRED FLOAT ask;
+        REQUIRED FLOAT mid;
+        REQUIRED FLOAT implied_vol;
+        REQUIRED FLOAT delta;
+        REQUIRED FLOAT gamma;
+        REQUIRED FLOAT vega;
+        REQUIRED FLOAT theta;
+        REQUIRED FLOAT rho;
+    }";
+
+    let to_string = |err: parquet::errors::ParquetError| err.to_string();
+
+    let schema = Arc::new(parse_message_type(SCHEMA).map_err(to_string)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+    let file = fs::File::create(path).map_err(|err| format!("{}: {}", path, err))?;
+    let mut writer = SerializedFileWriter::new(file, schema, properties).map_err(to_string)?;
+    let mut row_group = writer.next_row_group().map_err(to_string)?;
+
+    let float_columns: [fn(&QuoteAnalytics) -> f32; 11] = [
+        |row| row.strike,
+        |row| row.years_to_expiry,
+        |row| row.bid,
+        |row| row.ask,
+        |row| row.mid,
+        |row| row.implied_vol,
+        |row| row.delta,
+        |row| row.gamma,
+        |row| row.vega,
+        |row| row.theta,
+        |row| row.rho,
+    ];
+    let mut column_idx = 0;
+
+    while let Some(mut column) = row_group.next_column().map_err(to_string)? {
+        match column_idx {
+            0 | 1 => {
+                let values: Vec<ByteArray> = rows
+                    .iter()
+                    .map(|row| {
+                        ByteArray::from(if column_idx == 0 { row.name.as_str() } else { row.option_type })
+                    })
+                    .collect();
+                column.typed::<ByteArrayType>().write_batch(&values, None, None).map_err(to_string)?;
+            }
+            _ => {
+                let values: Vec<f32> = rows.iter().map(float_columns[column_idx - 2]).collect();
+                column.typed::<FloatType>().write_batch(&values, None, None).map_err(to_string)?;
+            }
+        }
+
+        column.close().map_err(to_string)?;
+        column_idx += 1;
+    }
+
+    row_group.close().map_err(to_string)?;
+    writer.close().map_err(to_string)?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_parquet(_rows: &[QuoteAnalytics], _path: &str) -> Result<(), String> {
+    Err(String::from("parquet output requires building with --features parquet"))
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let chain = read_chain(&args.input)?;
+    let (chain, report) = filter::filter_chain(&chain, &args.filter);
+
+    if !report.dropped.is_empty() {
+        eprintln!("simd-vol: filtered out {} of {} contracts", report.dropped.len(), chain.len() + report.dropped.len());
+    }
+
+    let risk_free_rate = args.risk_free_rate.unwrap_or_else(|| chain.parity_interest_rate());
+    let rows = chain.analytics(
+        risk_free_rate,
+        args.dividend_yield,
+        args.max_iterations,
+        args.threshold
+    );
+
+    match (args.format, args.output.as_deref()) {
+        (Format::Parquet, Some(path)) => write_parquet(&rows, path),
+        (Format::Parquet, None) => Err(String::from("parquet output requires --output")),
+        (format, output) => {
+            let mut out: Box<dyn Write> = match output {
+                Some(path) =>
+                    Box::new(
+                        io::BufWriter::new(
+                            fs::File::create(path).map_err(|err| format!("{}: {}", path, err))?
+                        )
+                    ),
+                None => Box::new(io::BufWriter::new(io::stdout().lock())),
+            };
+
+            let result = if format == Format::Json {
+                serde_json
+                    ::to_writer(&mut out, &rows)
+                    .map_err(io::Error::from)
+                    .and_then(|_| writeln!(out))
+            } else {
+                write_csv(&rows, &mut out)
+            };
+
+            result.and_then(|_| out.flush()).map_err(|err| err.to_string())
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let args = match parse_args(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("simd-vol: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(&args) {
+        eprintln!("simd-vol: {}", err);
+        std::process::exit(1);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::consts::OptionDir;
 
 // Black-scholes pricer, used to test the other function
+#[allow(clippy::excessive_precision)]
 fn erf(x: f32) -> f32 {
     let t = x.signum();
     let e = x.abs();
@@ -19,9 +20,11 @@ fn normal_cdf(x: f32) -> f32 {
     0.5 * (1.0 + erf(x / (2.0f32).sqrt()))
 }
 
+// pdf, vega and delta are the scalar references the SIMD Greeks are tested against
+#[cfg(test)]
 fn pdf(x: f32, mu: f32, sigma: f32) -> f32 {
-    ((-1.0 * (x - mu) * (x - mu)) / (2.0 * sigma * sigma)).exp() /
-        (sigma * (2.0 * 3.14159f32).sqrt())
+    (-((x - mu) * (x - mu)) / (2.0 * sigma * sigma)).exp() /
+        (sigma * (2.0 * std::f32::consts::PI).sqrt())
 }
 
 fn d(
@@ -103,6 +106,7 @@ pub(crate) fn price(
     }
 }
 
+#[cfg(test)]
 pub(crate) fn vega(
     spot: f32,
     strike: f32,
@@ -116,7 +120,27 @@ pub(crate) fn vega(
     (-dividend_yield * years_to_expiry).exp() * nd1 * (spot * years_to_expiry.sqrt())
 }
 
+#[cfg(test)]
+pub(crate) fn delta(
+    dir: OptionDir,
+    spot: f32,
+    strike: f32,
+    volatility: f32,
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    years_to_expiry: f32
+) -> f32 {
+    let (d1, _) = d(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
+    let la = (-dividend_yield * years_to_expiry).exp();
+
+    match dir {
+        OptionDir::CALL => la * normal_cdf(d1),
+        OptionDir::PUT => la * (normal_cdf(d1) - 1.0),
+    }
+}
 
+// The bisection always runs max_iterations steps, so the threshold is not used
+#[allow(clippy::too_many_arguments)]
 pub fn implied_vol(
     option_dir: OptionDir,
     option_price: &[f32],
@@ -126,7 +150,7 @@ pub fn implied_vol(
     dividend_yield: &[f32],
     years_to_expiry: &[f32],
     max_iterations: i32,
-    threshold: f32
+    _threshold: f32
 ) -> Vec<f32> {
     let n = option_price.len();
     let mut impl_vol = Vec::new();
@@ -149,7 +173,7 @@ pub fn implied_vol(
             if count > max_iterations {
                 break;
             } else {
-                count = count + 1;
+                count += 1;
             }
         }
 
@@ -167,5 +191,5 @@ pub fn parity_interest_rate(
     strike: f32,
     years_to_expiry: f32
 ) -> f32 {
-    return (strike / (spot - call_price + put_price)).ln() / years_to_expiry;
+    (strike / (spot - call_price + put_price)).ln() / years_to_expiry
 }
\ No newline at end of file
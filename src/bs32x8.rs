@@ -1,169 +1,409 @@
-use wide::*;
-use crate::consts::OptionDir;
+use crate::consts::{ OptionDir, VolMethod };
+use crate::simd::{ Kernel, Portable, Simd };
+use std::f32::consts::{ FRAC_1_SQRT_2, PI, SQRT_2 };
 
 // Source: https://github.com/ronniec95/black_scholes
+//
+// Generic over the lane width of crate::simd, and run over slices through the kernels at the
+// end of this file. Every function is #[inline(always)] in release builds so it inlines into the
+// kernel and with it into the backend's #[target_feature] function.
 
-fn erf_f32x8(x: f32x8) -> f32x8 {
-    let t = x.sign_bit();
-    let e = x.abs();
-    let n: f32x8 = f32x8::splat(0.3275911);
-    let a: f32x8 = f32x8::splat(0.254829592);
-    let r: f32x8 = f32x8::splat(-0.284496736);
-    let i: f32x8 = f32x8::splat(1.421413741);
-    let l: f32x8 = f32x8::splat(-1.453152027);
-    let d: f32x8 = f32x8::splat(1.061405429);
-    let u = f32x8::ONE / e.mul_add(n, f32x8::ONE);
-    let eu = u * (-e * e).exp();
-    let m = eu.mul_neg_add(u.mul_add(u.mul_add(u.mul_add(d.mul_add(u, l), i), r), a), f32x8::ONE);
-    t.blend(-m, m)
-}
+/*
+    Standard normal CDF, accurate to a few ulps of the result over the whole f32 range. The
+    Abramowitz-Stegun erf used before has an absolute error of about 1e-7, which in the wings is
+    larger than the CDF itself and with it the price of far OTM options.
+
+    With z = |x| / sqrt(2) these are Cephes' single precision erff and erfcf: 0.5 +- 0.5 erf(z)
+    for z < 1, and the lower tail 0.5 erfc(z) further out, subtracted from 1 only for x > 0. The
+    tail is never the difference of two numbers close to 1, so phi(-x) is as accurate an upper
+    tail as phi(x) is a lower one.
+
+    Rounding x^2 in exp(-x^2 / 2) alone is a relative error of x^2 / 2 ulps in the tail, so the
+    exponent is split into -h^2 / 2 - (x - h)(x + h) / 2, where h is x rounded to 1/256 and h^2 is
+    exact up to the cutoff.
+*/
+
+// erf(z) = z T(z^2) for z < 1
+#[allow(clippy::excessive_precision)]
+const ERF_T: [f32; 7] = [
+    7.853861353153693e-5,
+    -8.010193625184903e-4,
+    5.188327685732524e-3,
+    -2.685381193529856e-2,
+    1.128358514861418e-1,
+    -3.7612625824233e-1,
+    1.12837916572671,
+];
+
+// erfc(z) = exp(-z^2) P(1 / z^2) / z for 1 <= z < 2
+#[allow(clippy::excessive_precision)]
+const ERFC_P: [f32; 9] = [
+    2.326819970068386e-2,
+    -1.387039388740657e-1,
+    3.687424674597105e-1,
+    -5.824733027278666e-1,
+    6.210004621745983e-1,
+    -4.944515323274145e-1,
+    3.404879937665872e-1,
+    -2.741127028184656e-1,
+    5.638259427386472e-1,
+];
+
+// Same as ERFC_P for z >= 2
+#[allow(clippy::excessive_precision)]
+const ERFC_R: [f32; 8] = [
+    -1.047766399936249e1,
+    1.297719955372516e1,
+    -7.495518717768503,
+    2.921019019210786,
+    -1.0152652792027,
+    4.218463358204948e-1,
+    -2.820767439740514e-1,
+    5.641895067754075e-1,
+];
 
-fn phi_f32x8(e: f32x8) -> f32x8 {
-    let v = f32x8::HALF * (f32x8::ONE + erf_f32x8(e / f32x8::SQRT_2));
-    let min: f32x8 = f32x8::splat(-1.0e5);
-    let max: f32x8 = f32x8::splat(1.0e5);
+// |x| past which the lower tail is below the smallest normal f32 and is flushed to 0
+const PHI_TAIL_CUTOFF: f32 = 13.0;
 
-    let zero_mask = e.cmp_lt(min);
-    let one_mask = e.cmp_gt(max);
-    let v = zero_mask.blend(f32x8::ZERO, v);
-    let v = one_mask.blend(f32x8::ONE, v);
-    v
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn phi<V: Simd>(x: V) -> V {
+    let half = V::splat(0.5);
+    let a = x.abs();
+    let z = a * V::splat(FRAC_1_SQRT_2);
+
+    // 0.5 erf(z)
+    let zz = z * z;
+    let mut t = V::splat(ERF_T[0]);
+    for c in &ERF_T[1..] {
+        t = t.mul_add(zz, V::splat(*c));
+    }
+    let center = half * z * t;
+
+    // 0.5 erfc(z)
+    let q = V::splat(1.0) / z;
+    let qq = q * q;
+    let far = z.cmp_ge(V::splat(2.0));
+    let mut p = V::splat(ERFC_P[0]);
+    for c in &ERFC_P[1..] {
+        p = p.mul_add(qq, V::splat(*c));
+    }
+    let mut r = V::splat(ERFC_R[0]);
+    for c in &ERFC_R[1..] {
+        r = r.mul_add(qq, V::splat(*c));
+    }
+    let p = far.blend(r, p);
+    let h = (a * V::splat(256.0)).round() / V::splat(256.0);
+    let gauss = (-(h * h) * half).exp_sum(-((a - h) * (a + h)) * half);
+    let tail = a.cmp_gt(V::splat(PHI_TAIL_CUTOFF)).blend(V::splat(0.0), half * gauss * q * p);
+
+    let central = z.cmp_lt(V::splat(1.0));
+    let lower = central.blend(half - center, tail);
+    let upper = central.blend(half + center, V::splat(1.0) - tail);
+    x.cmp_lt(V::splat(0.0)).blend(lower, upper)
 }
 
-fn pdf_f32x8(x: f32x8, mu: f32x8, sigma: f32x8) -> f32x8 {
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn pdf<V: Simd>(x: V, mu: V, sigma: V) -> V {
     const P: f32 = 2.506628274631000502415765284811;
-    ((-1.0 * (x - mu) * (x - mu)) / (2.0 * sigma * sigma)).exp() / (sigma * P)
+    ((-(x - mu) * (x - mu)) / (V::splat(2.0) * sigma * sigma)).exp() / (sigma * V::splat(P))
+}
+
+// Scalar phi and standard normal density, for the analytics that work one strike at a time
+pub(crate) fn normal_cdf(x: f32) -> f32 {
+    phi(Portable([x])).0[0]
+}
+
+pub(crate) fn normal_pdf(x: f32) -> f32 {
+    pdf(Portable([x]), Portable([0.0]), Portable([1.0])).0[0]
 }
 
-fn call_price_f32x8(
-    spot: f32x8,
-    strike: f32x8,
-    volatility: f32x8,
-    risk_free_rate: f32x8,
-    dividend_yield: f32x8,
-    years_to_expiry: f32x8
-) -> f32x8 {
+// Shared by every pricer: (sqrt(T), sigma sqrt(T), d1)
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn d1<V: Simd>(
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> (V, V, V) {
     let d = years_to_expiry.sqrt();
     let rd = volatility * d;
-    let vs2 = (volatility * volatility) / 2.0;
+    let vs2 = (volatility * volatility) / V::splat(2.0);
     let ssln = (spot / strike).ln();
     let il = risk_free_rate - dividend_yield;
-    let d1 = (1.0 / rd) * (ssln + (il + vs2) * years_to_expiry);
+    let d1 = (V::splat(1.0) / rd) * (ssln + (il + vs2) * years_to_expiry);
+
+    (d, rd, d1)
+}
+
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn call_price<V: Simd>(
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> V {
+    let (_, rd, d1) = d1(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
     let d2 = d1 - rd;
     let la = (-dividend_yield * years_to_expiry).exp();
     let ia = (-risk_free_rate * years_to_expiry).exp();
     let g = strike * ia;
 
     // Call specific
-    let o = phi_f32x8(d1);
-    let c = phi_f32x8(d2);
+    let o = phi(d1);
+    let c = phi(d2);
     o * spot * la - c * g
 }
 
-fn put_price_f32x8(
-    spot: f32x8,
-    strike: f32x8,
-    volatility: f32x8,
-    risk_free_rate: f32x8,
-    dividend_yield: f32x8,
-    years_to_expiry: f32x8
-) -> f32x8 {
-    let d = years_to_expiry.sqrt();
-    let rd = volatility * d;
-    let vs2 = (volatility * volatility) / 2.0;
-    let ssln = (spot / strike).ln();
-    let il = risk_free_rate - dividend_yield;
-    let d1 = (f32x8::ONE / rd) * (ssln + (il + vs2) * years_to_expiry);
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn put_price<V: Simd>(
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> V {
+    let (_, rd, d1) = d1(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
     let d2 = d1 - rd;
-
     let la = (-dividend_yield * years_to_expiry).exp();
     let ia = (-risk_free_rate * years_to_expiry).exp();
     let g = strike * ia;
+
     // Put specific
-    let o = phi_f32x8(-d1);
-    let c = phi_f32x8(-d2);
+    let o = phi(-d1);
+    let c = phi(-d2);
     c * g - o * spot * la
 }
 
-fn vega_f32x8(
-    spot: f32x8,
-    strike: f32x8,
-    volatility: f32x8,
-    risk_free_rate: f32x8,
-    dividend_yield: f32x8,
-    years_to_expiry: f32x8
-) -> f32x8 {
-    let d = years_to_expiry.sqrt();
-    let rd = volatility * d;
-    let vs2 = (volatility * volatility) / 2.0;
-    let ssln = (spot / strike).ln();
-    let il = risk_free_rate - dividend_yield;
-    let d1 = (f32x8::ONE / rd) * (ssln + (il + vs2) * years_to_expiry);
-    let v = pdf_f32x8(d1, f32x8::ZERO, f32x8::ONE);
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn vega<V: Simd>(
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> V {
+    let (d, _, d1) = d1(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
+    let v = pdf(d1, V::splat(0.0), V::splat(1.0));
     let la = (-dividend_yield * years_to_expiry).exp();
     spot * la * v * d
 }
 
-pub(crate) fn price_f32x8(
+#[cfg_attr(not(debug_assertions), inline(always))]
+pub(crate) fn delta<V: Simd>(
+    dir: OptionDir,
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> V {
+    let (_, _, d1) = d1(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
+    let la = (-dividend_yield * years_to_expiry).exp();
+
+    match dir {
+        OptionDir::CALL => la * phi(d1),
+        OptionDir::PUT => -la * phi(-d1),
+    }
+}
+
+// (delta, gamma, vega, theta, rho) sharing a single evaluation of d1 and d2.
+// Theta is per year and vega and rho are per unit (1.0 = 100%) change.
+#[cfg_attr(not(debug_assertions), inline(always))]
+pub(crate) fn greeks<V: Simd>(
+    dir: OptionDir,
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> (V, V, V, V, V) {
+    let (d, rd, d1) = d1(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
+    let d2 = d1 - rd;
+    let la = (-dividend_yield * years_to_expiry).exp();
+    let ia = (-risk_free_rate * years_to_expiry).exp();
+    let g = strike * ia;
+    let v = pdf(d1, V::splat(0.0), V::splat(1.0));
+
+    let gamma = (la * v) / (spot * rd);
+    let vega = spot * la * v * d;
+    let decay = -(spot * la * v * volatility) / (V::splat(2.0) * d);
+
+    match dir {
+        OptionDir::CALL => {
+            let o = phi(d1);
+            let c = phi(d2);
+            let theta = decay - risk_free_rate * g * c + dividend_yield * spot * la * o;
+            (la * o, gamma, vega, theta, years_to_expiry * g * c)
+        }
+        OptionDir::PUT => {
+            let o = phi(-d1);
+            let c = phi(-d2);
+            let theta = decay + risk_free_rate * g * c - dividend_yield * spot * la * o;
+            (-la * o, gamma, vega, theta, -years_to_expiry * g * c)
+        }
+    }
+}
+
+#[cfg_attr(not(debug_assertions), inline(always))]
+pub(crate) fn price<V: Simd>(
     dir: OptionDir,
-    spot: f32x8,
-    strike: f32x8,
-    volatility: f32x8,
-    risk_free_rate: f32x8,
-    dividend_yield: f32x8,
-    years_to_expiry: f32x8
-) -> f32x8 {
+    spot: V,
+    strike: V,
+    volatility: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> V {
     match dir {
         OptionDir::CALL =>
-            call_price_f32x8(
-                spot,
-                strike,
-                volatility,
-                risk_free_rate,
-                dividend_yield,
-                years_to_expiry
-            ),
+            call_price(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry),
         OptionDir::PUT =>
-            put_price_f32x8(
-                spot,
-                strike,
-                volatility,
-                risk_free_rate,
-                dividend_yield,
-                years_to_expiry
-            ),
+            put_price(spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry),
     }
 }
 
-pub(crate) fn implied_vol_f32x8(
+// Bracket searched by the bisection when there is no initial guess
+const MIN_BRACKET: f32 = 0.0;
+const MAX_BRACKET: f32 = 5.0;
+
+// Vega below which implied_vol_guess does not take a Newton step
+const MIN_NEWTON_VEGA: f32 = 1e-3;
+
+#[cfg_attr(not(debug_assertions), inline(always))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn implied_vol<V: Simd>(
+    option_dir: OptionDir,
+    price: V,
+    spot: V,
+    strike: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V,
+    diff_threshold: f32,
+    max_iterations: i32
+) -> V {
+    // Min volatility of 0%, max volatility of 500%
+    implied_vol_bracket(
+        option_dir,
+        price,
+        spot,
+        strike,
+        risk_free_rate,
+        dividend_yield,
+        years_to_expiry,
+        V::splat(MIN_BRACKET),
+        V::splat(MAX_BRACKET),
+        diff_threshold,
+        max_iterations
+    )
+}
+
+/*
+    Bisection started from a bracket of +/- half_width around one Newton step from the guess,
+    clamped to the full bracket. Where the prices at the ends show the root is outside of the
+    narrow bracket, the search continues between the nearest end and the edge of the full
+    bracket on that side. Lanes whose guess is not a positive number use the full bracket. With a
+    close guess (e.g. the previous vol of the same contract) the Newton step lands close enough
+    to the root for a half_width of 0.01 or less, saving log2(5 / (2 half_width)) iterations.
+*/
+#[cfg_attr(not(debug_assertions), inline(always))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn implied_vol_guess<V: Simd>(
+    option_dir: OptionDir,
+    price: V,
+    spot: V,
+    strike: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V,
+    guess: V,
+    half_width: f32,
+    diff_threshold: f32,
+    max_iterations: i32
+) -> V {
+    let min = V::splat(MIN_BRACKET);
+    let max = V::splat(MAX_BRACKET);
+    let half_width = V::splat(half_width);
+
+    // One Newton step from the guess centers the bracket on the new root. Where vega is too
+    // small for the step to be meaningful the guess itself is the center.
+    let guess_price = self::price(option_dir, spot, strike, guess, risk_free_rate, dividend_yield, years_to_expiry);
+    let vega = vega(spot, strike, guess, risk_free_rate, dividend_yield, years_to_expiry);
+    let step = (price - guess_price) / vega;
+    let has_step = vega.cmp_gt(V::splat(MIN_NEWTON_VEGA)).and(step.cmp_eq(step));
+    let center = has_step.blend(guess + step, guess);
+
+    let low = (center - half_width).max(min).min(max);
+    let high = (center + half_width).max(min).min(max);
+
+    let low_price = self::price(option_dir, spot, strike, low, risk_free_rate, dividend_yield, years_to_expiry);
+    let high_price = self::price(option_dir, spot, strike, high, risk_free_rate, dividend_yield, years_to_expiry);
+
+    // Prices increase with volatility, so the ends of the narrow bracket tell which side of it
+    // the root is on. NaN guesses and prices fail every comparison and use the full bracket.
+    let valid = guess.cmp_gt(V::splat(0.0));
+    let inside = valid.and(low_price.cmp_le(price)).and(high_price.cmp_ge(price));
+    let below = valid.and(low_price.cmp_gt(price));
+    let above = valid.and(high_price.cmp_lt(price));
+
+    let new_low = inside.blend(low, above.blend(high, min));
+    let new_high = inside.blend(high, below.blend(low, max));
+
+    implied_vol_bracket(
+        option_dir,
+        price,
+        spot,
+        strike,
+        risk_free_rate,
+        dividend_yield,
+        years_to_expiry,
+        new_low,
+        new_high,
+        diff_threshold,
+        max_iterations
+    )
+}
+
+#[cfg_attr(not(debug_assertions), inline(always))]
+#[allow(clippy::too_many_arguments)]
+fn implied_vol_bracket<V: Simd>(
     option_dir: OptionDir,
-    price: f32x8,
-    spot: f32x8,
-    strike: f32x8,
-    risk_free_rate: f32x8,
-    dividend_yield: f32x8,
-    years_to_expiry: f32x8,
+    price: V,
+    spot: V,
+    strike: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V,
+    mut low: V,
+    mut high: V,
     diff_threshold: f32,
     max_iterations: i32
-) -> f32x8 {
+) -> V {
     let mut count = 0;
 
-    // Min volatility of 0%, max volatility of 500%
-    let mut low = f32x8::splat(0.0);
-    let mut high = f32x8::splat(5.0);
-    let two_f32x8 = f32x8::splat(2.0);
-    let one_f32x8 = f32x8::splat(1.0);
+    let two = V::splat(2.0);
+    let threshold = V::splat(diff_threshold);
 
     // Run bisection method
     loop {
-        let mask = (high - low).abs().cmp_lt(f32x8::splat(diff_threshold));
-        if mask.all() {
+        // Converged lanes stop moving, so every lane takes the same steps whatever the lane
+        // width and whichever options share its vector
+        let converged = (high - low).abs().cmp_lt(threshold);
+        if converged.all() {
             break;
         }
 
-        let middle = (high + low) / two_f32x8;
+        let middle = (high + low) / two;
 
-        let option_value = price_f32x8(
+        let option_value = self::price(
             option_dir,
             spot,
             strike,
@@ -173,48 +413,382 @@ pub(crate) fn implied_vol_f32x8(
             years_to_expiry
         );
 
-        // 0 if diff is positive, 1 if diff is negative
-        // 0 means we need to update high, 1 means we need to update low
-        let is_positive_diff = (option_value - price)
-            .cmp_lt(f32x8::splat(0.0))
-            .is_nan()
-            .min(f32x8::splat(1.0));
-        let is_negative_diff = one_f32x8 - is_positive_diff;
+        // Below the price the root is above middle, otherwise (including NaN) below it
+        let is_below = option_value.cmp_lt(price);
 
-        low = is_positive_diff * middle + is_negative_diff * low;
-        high = is_negative_diff * middle + is_positive_diff * high;
+        low = converged.blend(low, is_below.blend(middle, low));
+        high = converged.blend(high, is_below.blend(high, middle));
 
         if count > max_iterations {
             break;
         } else {
-            count = count + 1;
+            count += 1;
         }
     }
 
-    (high + low) / two_f32x8
+    (high + low) / two
+}
+
+/*
+    Closed-form implied volatility approximations. Each works on the call price, so puts are
+    converted with put call parity first, and on the dividend discounted spot s = S e^(-qT) and
+    the discounted strike x = K e^(-rT).
+
+    Brenner-Subrahmanyam (1988), exact in the limit at the money forward:
+        sigma = sqrt(2 pi / T) C / s
+
+    Corrado-Miller (1996), which adds a moneyness correction:
+        sigma = sqrt(2 pi / T) / (s + x) * (C - (s - x) / 2 + sqrt((C - (s - x) / 2)^2 - (s - x)^2 / pi))
+
+    Li (2005), "A new formula for computing implied volatility". With eta = x / s, c = C / s,
+    rho = |eta - 1| / c^2 and alpha = sqrt(2 pi) / (1 + eta) * (2 c + eta - 1):
+        rho <= 1.4: sigma sqrt(T) = 2 sqrt(2) z - sqrt(8 z^2 - 6 alpha / (sqrt(2) z))
+                    where z = cos(acos(3 alpha / sqrt(32)) / 3)
+        rho > 1.4:  sigma sqrt(T) = (alpha + sqrt(alpha^2 - 4 (eta - 1)^2 / (1 + eta))) / 2
+
+    Negative square roots, which happen for prices far from the money, are floored at zero and
+    the result is clamped to the bisection bracket.
+*/
+#[cfg_attr(not(debug_assertions), inline(always))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn approx_vol<V: Simd>(
+    option_dir: OptionDir,
+    method: VolMethod,
+    price: V,
+    spot: V,
+    strike: V,
+    risk_free_rate: V,
+    dividend_yield: V,
+    years_to_expiry: V
+) -> V {
+    let s = spot * (-dividend_yield * years_to_expiry).exp();
+    let x = strike * (-risk_free_rate * years_to_expiry).exp();
+
+    let call = match option_dir {
+        OptionDir::CALL => price,
+        OptionDir::PUT => price + s - x,
+    };
+
+    let sqrt_two_pi = V::splat((2.0 * PI).sqrt());
+
+    let total_vol = match method {
+        VolMethod::BrennerSubrahmanyam => sqrt_two_pi * call / s,
+        VolMethod::CorradoMiller => {
+            let half_diff = (s - x) * V::splat(0.5);
+            let a = call - half_diff;
+            let root = (a * a - (s - x) * (s - x) / V::splat(PI)).max(V::splat(0.0)).sqrt();
+            sqrt_two_pi / (s + x) * (a + root)
+        }
+        VolMethod::Li => li_total_vol(call / s, x / s),
+    };
+
+    (total_vol / years_to_expiry.sqrt())
+        .max(V::splat(MIN_BRACKET))
+        .min(V::splat(MAX_BRACKET))
+}
+
+// Li's sigma sqrt(T) from the normalized call price c = C / s and strike eta = x / s
+#[cfg_attr(not(debug_assertions), inline(always))]
+fn li_total_vol<V: Simd>(c: V, eta: V) -> V {
+    let sqrt_two = V::splat(SQRT_2);
+    let moneyness = eta - V::splat(1.0);
+    let rho = moneyness.abs() / (c * c);
+    let alpha = V::splat((2.0 * PI).sqrt()) / (V::splat(1.0) + eta) * (c + c + moneyness);
+
+    // Near the money, the root of the cubic expansion of the call price in sigma sqrt(T)
+    let cos_arg = (V::splat(3.0) * alpha / V::splat(32f32.sqrt()))
+        .max(V::splat(-1.0))
+        .min(V::splat(1.0));
+    let z = (cos_arg.acos() / V::splat(3.0)).cos();
+    let near =
+        V::splat(2.0) * sqrt_two * z -
+        (V::splat(8.0) * z * z - V::splat(6.0) * alpha / (sqrt_two * z)).max(V::splat(0.0)).sqrt();
+
+    // Away from the money, a refinement of Corrado-Miller
+    let far_root = (alpha * alpha - V::splat(4.0) * moneyness * moneyness / (V::splat(1.0) + eta))
+        .max(V::splat(0.0))
+        .sqrt();
+    let far = (alpha + far_root) * V::splat(0.5);
+
+    rho.cmp_le(V::splat(1.4)).blend(near, far)
 }
 
 // Apply put call parity to determine interest rate
-pub(crate) fn parity_interest_rate_f32x8(
-    call_price: f32x8,
-    put_price: f32x8,
-    spot: f32x8,
-    strike: f32x8,
-    years_to_expiry: f32x8
-) -> f32x8 {
-    return (strike / (spot - call_price + put_price)).ln() / years_to_expiry;
+#[cfg_attr(not(debug_assertions), inline(always))]
+pub(crate) fn parity_interest_rate<V: Simd>(
+    call_price: V,
+    put_price: V,
+    spot: V,
+    strike: V,
+    years_to_expiry: V
+) -> V {
+    (strike / (spot - call_price + put_price)).ln() / years_to_expiry
+}
+
+/*
+    Kernels running the functions above over slices with simd::map and simd::map_into. The
+    inputs are in the same order as the arguments of the functions they call.
+
+    Lanes past the end of the slices are padded with an at the money option with a year to
+    expiry, priced at a vol of 0.2 where a price is an input.
+*/
+
+const PADDING_PRICE: f32 = 0.0797;
+const PADDING_VOL: f32 = 0.2;
+
+// [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry] -> [implied_vol]
+pub(crate) struct ImpliedVolKernel {
+    pub(crate) option_dir: OptionDir,
+    pub(crate) max_iterations: i32,
+    pub(crate) threshold: f32,
+}
+
+impl Kernel<6, 1> for ImpliedVolKernel {
+    const PADDING: [f32; 6] = [PADDING_PRICE, 1.0, 1.0, 0.0, 0.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry]: [V; 6], outputs: &mut [V; 1]) {
+        outputs[0] = implied_vol(
+            self.option_dir,
+            price,
+            spot,
+            strike,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry,
+            self.threshold,
+            self.max_iterations
+        );
+    }
+}
+
+// Same inputs as ImpliedVolKernel
+pub(crate) struct ApproxVolKernel {
+    pub(crate) option_dir: OptionDir,
+    pub(crate) method: VolMethod,
+}
+
+impl Kernel<6, 1> for ApproxVolKernel {
+    const PADDING: [f32; 6] = [PADDING_PRICE, 1.0, 1.0, 0.0, 0.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry]: [V; 6], outputs: &mut [V; 1]) {
+        outputs[0] = approx_vol(
+            self.option_dir,
+            self.method,
+            price,
+            spot,
+            strike,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry
+        );
+    }
+}
+
+// Same inputs as ImpliedVolKernel, with the guess in the output on entry
+pub(crate) struct ImpliedVolGuessKernel {
+    pub(crate) option_dir: OptionDir,
+    pub(crate) half_width: f32,
+    pub(crate) max_iterations: i32,
+    pub(crate) threshold: f32,
+}
+
+impl Kernel<6, 1> for ImpliedVolGuessKernel {
+    const PADDING: [f32; 6] = [PADDING_PRICE, 1.0, 1.0, 0.0, 0.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, active: V, [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry]: [V; 6], outputs: &mut [V; 1]) {
+        // The guess of inactive lanes is zero rather than padding
+        let guess = active.blend(outputs[0], V::splat(PADDING_VOL));
+
+        outputs[0] = implied_vol_guess(
+            self.option_dir,
+            price,
+            spot,
+            strike,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry,
+            guess,
+            self.half_width,
+            self.threshold,
+            self.max_iterations
+        );
+    }
+}
+
+// [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry] -> [delta]
+pub(crate) struct DeltaKernel {
+    pub(crate) option_dir: OptionDir,
+}
+
+impl Kernel<6, 1> for DeltaKernel {
+    const PADDING: [f32; 6] = [1.0, 1.0, PADDING_VOL, 0.0, 0.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry]: [V; 6], outputs: &mut [V; 1]) {
+        outputs[0] = delta(self.option_dir, spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
+    }
+}
+
+// Same inputs as DeltaKernel -> [price]
+pub(crate) struct PriceKernel {
+    pub(crate) option_dir: OptionDir,
+}
+
+impl Kernel<6, 1> for PriceKernel {
+    const PADDING: [f32; 6] = [1.0, 1.0, PADDING_VOL, 0.0, 0.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry]: [V; 6], outputs: &mut [V; 1]) {
+        outputs[0] = price(self.option_dir, spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry);
+    }
+}
+
+// Same inputs as DeltaKernel -> [delta, gamma, vega, theta, rho]
+pub(crate) struct GreeksKernel {
+    pub(crate) option_dir: OptionDir,
+}
+
+impl Kernel<6, 5> for GreeksKernel {
+    const PADDING: [f32; 6] = [1.0, 1.0, PADDING_VOL, 0.0, 0.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry]: [V; 6], outputs: &mut [V; 5]) {
+        let (delta, gamma, vega, theta, rho) = greeks(
+            self.option_dir,
+            spot,
+            strike,
+            volatility,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry
+        );
+
+        *outputs = [delta, gamma, vega, theta, rho];
+    }
+}
+
+// [call_price, put_price, spot, strike, years_to_expiry] -> [risk_free_rate]
+pub(crate) struct ParityInterestRateKernel;
+
+impl Kernel<5, 1> for ParityInterestRateKernel {
+    const PADDING: [f32; 5] = [PADDING_PRICE, PADDING_PRICE, 1.0, 1.0, 1.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [call_price, put_price, spot, strike, years_to_expiry]: [V; 5], outputs: &mut [V; 1]) {
+        outputs[0] = parity_interest_rate(call_price, put_price, spot, strike, years_to_expiry);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::bs;
-    use bytemuck::cast;
+    use crate::read_hist;
+    use crate::simd::{ self, Backend };
+    use proptest::array::uniform8;
+    use proptest::prelude::*;
+
+    // The scalar backend with 8 lanes
+    type Lanes = Portable<8>;
+
+    fn lanes(v: Lanes) -> [f32; 8] {
+        v.0
+    }
+
+    /*
+        Max absolute and relative error of phi against an f64 reference on a grid of step
+        1/1024 over [lo, hi). The relative error is only taken where the reference is a normal
+        f32, as below it phi flushes to 0.
+    */
+    fn phi_error(lo: f32, hi: f32) -> (f64, f64) {
+        let steps = ((hi - lo) * 1024.0) as usize;
+        let xs: Vec<f32> = (0..steps).map(|idx| lo + idx as f32 / 1024.0).collect();
+
+        let mut max_abs: f64 = 0.0;
+        let mut max_rel: f64 = 0.0;
+
+        for chunk in xs.chunks(8) {
+            let mut x = [0.0; 8];
+            x[..chunk.len()].copy_from_slice(chunk);
+            let cdf = lanes(phi(Portable(x)));
+
+            for (x, cdf) in chunk.iter().zip(cdf.iter()) {
+                let expected = ::implied_vol::norm_cdf(*x as f64);
+                let error = (*cdf as f64 - expected).abs();
+
+                max_abs = max_abs.max(error);
+                if expected >= f32::MIN_POSITIVE as f64 {
+                    max_rel = max_rel.max(error / expected);
+                }
+            }
+        }
+
+        (max_abs, max_rel)
+    }
+
+    #[test]
+    fn phi_accuracy() {
+        // Central, wings and deep tails, with the bound on the relative error for each. Only
+        // just below x = -sqrt(2), where 0.5 - 0.5 erf(z) cancels, is it more than a few ulps.
+        let ranges = [(-3.0, 3.0, 1e-6), (-8.0, -3.0, 4e-7), (-14.0, -8.0, 4e-7), (3.0, 14.0, 6e-8)];
+
+        for (lo, hi, max_rel) in ranges {
+            let (abs_error, rel_error) = phi_error(lo, hi);
+
+            assert!(abs_error < 1e-7, "phi on [{}, {}): max abs error {:e}", lo, hi, abs_error);
+            assert!(rel_error < max_rel, "phi on [{}, {}): max rel error {:e} above {:e}", lo, hi, rel_error, max_rel);
+        }
+    }
+
+    #[test]
+    fn phi_special_values() {
+        let x = Portable([0.0, -0.0, f32::INFINITY, f32::NEG_INFINITY, 1e30, -1e30, f32::NAN, -40.0]);
+        let cdf = lanes(phi(x));
+
+        assert_eq!(&cdf[..6], &[0.5, 0.5, 1.0, 0.0, 1.0, 0.0]);
+        assert!(cdf[6].is_nan());
+        assert_eq!(cdf[7], 0.0);
+    }
+
+    #[test]
+    fn implied_vol_far_wings() {
+        let (spot, volatility, years_to_expiry): (f64, f64, f64) = (100.0, 0.4, 0.25);
+        let strikes = [150.0, 170.0, 190.0, 210.0, 230.0, 250.0, 270.0, 290.0];
+
+        // f64 prices of calls priced at 1e-2 down to about 1e-9
+        let prices = strikes.map(|strike: f32| {
+            let rd = volatility * years_to_expiry.sqrt();
+            let d1 = ((spot / strike as f64).ln() + 0.5 * rd * rd) / rd;
+            let cdf = ::implied_vol::norm_cdf;
+            (spot * cdf(d1) - strike as f64 * cdf(d1 - rd)) as f32
+        });
+
+        let vol = lanes(
+            implied_vol::<Lanes>(
+                OptionDir::CALL,
+                Portable(prices),
+                (spot as f32).into(),
+                Portable(strikes),
+                (0.0).into(),
+                (0.0).into(),
+                (years_to_expiry as f32).into(),
+                1e-6,
+                40
+            )
+        );
+
+        for (strike, vol) in strikes.iter().zip(vol.iter()) {
+            assert!((vol - volatility as f32).abs() < 1e-3, "Strike: {} Vol: {}", strike, vol);
+        }
+    }
 
     #[test]
     fn interest_rate_check_small() {
-        let interest_rate: [f32; 8] = cast(
-            parity_interest_rate_f32x8(
+        let interest_rate: [f32; 8] = lanes(
+            parity_interest_rate::<Lanes>(
                 (8.247).into(),
                 (5.785).into(),
                 (100.0).into(),
@@ -243,7 +817,7 @@ mod tests {
             let volatility = 0.2;
             let dividend_yield = 0.0;
 
-            let put_price = price_f32x8(
+            let put_price = price::<Lanes>(
                 OptionDir::PUT,
                 spot.into(),
                 strike.into(),
@@ -253,7 +827,7 @@ mod tests {
                 years_to_expiry.into()
             );
 
-            let call_price = price_f32x8(
+            let call_price = price::<Lanes>(
                 OptionDir::CALL,
                 spot.into(),
                 strike.into(),
@@ -263,8 +837,8 @@ mod tests {
                 years_to_expiry.into()
             );
 
-            let interest_rate: [f32; 8] = cast(
-                parity_interest_rate_f32x8(
+            let interest_rate: [f32; 8] = lanes(
+                parity_interest_rate::<Lanes>(
                     call_price,
                     put_price,
                     spot.into(),
@@ -297,8 +871,8 @@ mod tests {
                 years_to_expiry
             );
 
-            let actual: [f32; 8] = cast(
-                price_f32x8(
+            let actual: [f32; 8] = lanes(
+                price::<Lanes>(
                     OptionDir::PUT,
                     spot.into(),
                     strike.into(),
@@ -333,8 +907,8 @@ mod tests {
                 years_to_expiry
             );
 
-            let actual: [f32; 8] = cast(
-                price_f32x8(
+            let actual: [f32; 8] = lanes(
+                price::<Lanes>(
                     OptionDir::CALL,
                     spot.into(),
                     strike.into(),
@@ -369,8 +943,8 @@ mod tests {
                 years_to_expiry
             );
 
-            let actual: [f32; 8] = cast(
-                vega_f32x8(
+            let actual: [f32; 8] = lanes(
+                vega::<Lanes>(
                     spot.into(),
                     strike.into(),
                     volatility.into(),
@@ -383,6 +957,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn delta_check() {
+        for i in (50..90).step_by(1) {
+            let spot = 50.0;
+            let strike = i as f32;
+            let years_to_expiry = 1.0;
+            let risk_free_rate = 0.02;
+            let volatility = 0.2;
+            let dividend_yield = 0.01;
+
+            for dir in [OptionDir::CALL, OptionDir::PUT] {
+                let expected = bs::delta(
+                    dir,
+                    spot,
+                    strike,
+                    volatility,
+                    risk_free_rate,
+                    dividend_yield,
+                    years_to_expiry
+                );
+
+                let actual: [f32; 8] = lanes(
+                    delta::<Lanes>(
+                        dir,
+                        spot.into(),
+                        strike.into(),
+                        volatility.into(),
+                        risk_free_rate.into(),
+                        dividend_yield.into(),
+                        years_to_expiry.into()
+                    )
+                );
+                assert!((actual[0] - expected).abs() < 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn greeks_check() {
+        let spot: f32 = 100.0;
+        let volatility = 0.25;
+        let risk_free_rate = 0.03;
+        let dividend_yield = 0.01;
+        let years_to_expiry = 0.5;
+        let h = 0.01;
+
+        let price = |dir, spot: f32, volatility: f32, risk_free_rate: f32, years_to_expiry: f32| {
+            bs::price(dir, spot, 105.0, volatility, risk_free_rate, dividend_yield, years_to_expiry)
+        };
+
+        for dir in [OptionDir::CALL, OptionDir::PUT] {
+            let (delta, gamma, vega, theta, rho) = greeks::<Lanes>(
+                dir,
+                spot.into(),
+                (105.0).into(),
+                volatility.into(),
+                risk_free_rate.into(),
+                dividend_yield.into(),
+                years_to_expiry.into()
+            );
+            let [delta, gamma, vega, theta, rho]: [[f32; 8]; 5] = [
+                lanes(delta),
+                lanes(gamma),
+                lanes(vega),
+                lanes(theta),
+                lanes(rho),
+            ];
+
+            // Central differences of the scalar pricer
+            let mid = price(dir, spot, volatility, risk_free_rate, years_to_expiry);
+            let expected_delta =
+                (price(dir, spot + h, volatility, risk_free_rate, years_to_expiry) -
+                    price(dir, spot - h, volatility, risk_free_rate, years_to_expiry)) /
+                (2.0 * h);
+            // Second difference needs a wider step to stay above f32 rounding
+            let hs = 1.0;
+            let expected_gamma =
+                (price(dir, spot + hs, volatility, risk_free_rate, years_to_expiry) -
+                    2.0 * mid +
+                    price(dir, spot - hs, volatility, risk_free_rate, years_to_expiry)) /
+                (hs * hs);
+            let expected_vega =
+                (price(dir, spot, volatility + h, risk_free_rate, years_to_expiry) -
+                    price(dir, spot, volatility - h, risk_free_rate, years_to_expiry)) /
+                (2.0 * h);
+            let expected_theta =
+                -(price(dir, spot, volatility, risk_free_rate, years_to_expiry + h) -
+                    price(dir, spot, volatility, risk_free_rate, years_to_expiry - h)) /
+                (2.0 * h);
+            let expected_rho =
+                (price(dir, spot, volatility, risk_free_rate + h, years_to_expiry) -
+                    price(dir, spot, volatility, risk_free_rate - h, years_to_expiry)) /
+                (2.0 * h);
+
+            assert!((delta[0] - expected_delta).abs() < 0.001, "Delta {} {}", delta[0], expected_delta);
+            assert!((gamma[0] - expected_gamma).abs() < 0.001, "Gamma {} {}", gamma[0], expected_gamma);
+            assert!((vega[0] - expected_vega).abs() < 0.05, "Vega {} {}", vega[0], expected_vega);
+            assert!((theta[0] - expected_theta).abs() < 0.05, "Theta {} {}", theta[0], expected_theta);
+            assert!((rho[0] - expected_rho).abs() < 0.05, "Rho {} {}", rho[0], expected_rho);
+        }
+    }
+
     #[test]
     fn check_put_iv_from_price() {
         let spot = 131.0;
@@ -401,8 +1077,8 @@ mod tests {
             dividend_yield,
             years_to_expiry
         );
-        let v: [f32; 8] = cast(
-            implied_vol_f32x8(
+        let v: [f32; 8] = lanes(
+            implied_vol::<Lanes>(
                 OptionDir::PUT,
                 put_price.into(),
                 spot.into(),
@@ -436,8 +1112,8 @@ mod tests {
             dividend_yield,
             years_to_expiry
         );
-        let v: [f32; 8] = cast(
-            implied_vol_f32x8(
+        let v: [f32; 8] = lanes(
+            implied_vol::<Lanes>(
                 OptionDir::CALL,
                 call_price.into(),
                 spot.into(),
@@ -461,8 +1137,8 @@ mod tests {
         let risk_free_rate = 0.01;
         let dividend_yield = 0.0;
 
-        let v: [f32; 8] = cast(
-            implied_vol_f32x8(
+        let v: [f32; 8] = lanes(
+            implied_vol::<Lanes>(
                 OptionDir::CALL,
                 (55.45).into(),
                 spot.into(),
@@ -476,4 +1152,421 @@ mod tests {
         );
         assert!((v[0] - 0.0).abs() < 0.00001);
     }
+
+    #[test]
+    fn guess_check() {
+        let spot = Lanes::splat(100.0);
+        let strike = Portable([80.0, 90.0, 95.0, 100.0, 105.0, 110.0, 120.0, 130.0]);
+        let vol = Portable([0.35, 0.3, 0.25, 0.2, 0.18, 0.17, 0.19, 0.22]);
+        let (rate, div, years) = (Lanes::splat(0.03), Lanes::splat(0.01), Lanes::splat(0.5));
+        let price = price::<Lanes>(OptionDir::CALL, spot, strike, vol, rate, div, years);
+
+        // Close guesses, guesses far from the root and invalid guesses all converge
+        let guesses = [
+            vol + Lanes::splat(0.01),
+            Portable([2.0, 0.01, 4.0, 0.3, 0.1, 0.5, 0.19, 1.0]),
+            Portable([f32::NAN, 0.0, -1.0, f32::NAN, 0.0, -1.0, f32::NAN, 0.0]),
+        ];
+
+        for guess in guesses {
+            let actual: [f32; 8] = lanes(
+                implied_vol_guess::<Lanes>(OptionDir::CALL, price, spot, strike, rate, div, years, guess, 0.02, 0.00001, 40)
+            );
+            let expected: [f32; 8] = lanes(vol);
+
+            for i in 0..8 {
+                assert!((actual[i] - expected[i]).abs() < 0.001, "Got: {:?}, Expected: {:?}", actual, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn approx_vol_check() {
+        let spot = 100.0;
+        let risk_free_rate = 0.03;
+        let dividend_yield = 0.01;
+        let years_to_expiry: f32 = 0.5;
+        let forward = spot * ((risk_free_rate - dividend_yield) * years_to_expiry).exp();
+
+        for volatility in [0.2, 0.3, 0.5] {
+            for i in 95..=105 {
+                let strike = i as f32;
+
+                for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+                    let price = price::<Lanes>(
+                        option_dir,
+                        spot.into(),
+                        strike.into(),
+                        volatility.into(),
+                        risk_free_rate.into(),
+                        dividend_yield.into(),
+                        years_to_expiry.into()
+                    );
+
+                    for method in [VolMethod::CorradoMiller, VolMethod::Li] {
+                        let vol: [f32; 8] = lanes(
+                            approx_vol::<Lanes>(
+                                option_dir,
+                                method,
+                                price,
+                                spot.into(),
+                                strike.into(),
+                                risk_free_rate.into(),
+                                dividend_yield.into(),
+                                years_to_expiry.into()
+                            )
+                        );
+
+                        assert!(
+                            (vol[0] - volatility).abs() < 0.01,
+                            "{:?} Strike: {} Got: {}, Expected: {}",
+                            method,
+                            strike,
+                            vol[0],
+                            volatility
+                        );
+                    }
+                }
+            }
+
+            // Brenner-Subrahmanyam only holds at the money forward
+            let price = price::<Lanes>(
+                OptionDir::CALL,
+                spot.into(),
+                forward.into(),
+                volatility.into(),
+                risk_free_rate.into(),
+                dividend_yield.into(),
+                years_to_expiry.into()
+            );
+            let vol: [f32; 8] = lanes(
+                approx_vol::<Lanes>(
+                    OptionDir::CALL,
+                    VolMethod::BrennerSubrahmanyam,
+                    price,
+                    spot.into(),
+                    forward.into(),
+                    risk_free_rate.into(),
+                    dividend_yield.into(),
+                    years_to_expiry.into()
+                )
+            );
+
+            assert!((vol[0] - volatility).abs() < 0.01, "Got: {}, Expected: {}", vol[0], volatility);
+        }
+    }
+
+    #[test]
+    fn backends_match_scalar() {
+        let (spot, call_prices, call_strikes, _, _, years_to_expiry, _) = read_hist::get_appl_data();
+
+        // Not a multiple of any lane width, so the last vector is partial
+        let n = call_prices.len().min(2003);
+        let (call_prices, call_strikes) = (&call_prices[..n], &call_strikes[..n]);
+        let years_to_expiry = &years_to_expiry[..n];
+        let spot = vec![spot; n];
+        let risk_free_rate = vec![0.01; n];
+        let dividend_yield = vec![0.0; n];
+
+        let run = |backend: Backend| {
+            let mut vol = vec![0.0; n];
+            let kernel = ImpliedVolKernel {
+                option_dir: OptionDir::CALL,
+                max_iterations: 20,
+                threshold: 0.0001,
+            };
+            simd::map_into_with(
+                backend,
+                &kernel,
+                [call_prices, &spot, call_strikes, &risk_free_rate, &dividend_yield, years_to_expiry],
+                [&mut vol]
+            );
+
+            let mut greeks: [Vec<f32>; 5] = std::array::from_fn(|_| vec![0.0; n]);
+            simd::map_into_with(
+                backend,
+                &GreeksKernel { option_dir: OptionDir::CALL },
+                [&spot, call_strikes, &vol, &risk_free_rate, &dividend_yield, years_to_expiry],
+                greeks.each_mut().map(|column| column.as_mut_slice())
+            );
+
+            let mut guess = vol.iter().map(|vol| vol + 0.01).collect::<Vec<f32>>();
+            let kernel = ImpliedVolGuessKernel {
+                option_dir: OptionDir::CALL,
+                half_width: 0.005,
+                max_iterations: 20,
+                threshold: 0.0001,
+            };
+            simd::map_into_with(
+                backend,
+                &kernel,
+                [call_prices, &spot, call_strikes, &risk_free_rate, &dividend_yield, years_to_expiry],
+                [&mut guess]
+            );
+
+            let [delta, gamma, vega, theta, rho] = greeks;
+            [vol, delta, gamma, vega, theta, rho, guess]
+        };
+
+        let expected = run(Backend::Scalar);
+
+        for backend in [Backend::Sse, Backend::Avx2, Backend::Avx512, Backend::Neon, Backend::Simd128] {
+            if !backend.is_supported() {
+                continue;
+            }
+
+            for (actual, expected) in run(backend).iter().zip(expected.iter()) {
+                for (a, e) in actual.iter().zip(expected.iter()) {
+                    assert!(a.to_bits() == e.to_bits() || (a.is_nan() && e.is_nan()), "{:?}: {} != {}", backend, a, e);
+                }
+            }
+        }
+    }
+
+    /*
+        Property tests over random parameters, eight options per case with one in each lane.
+        Tolerances are in units of spot where f32 rounding of the prices dominates.
+    */
+
+    #[derive(Debug, Clone)]
+    struct Options {
+        spot: [f32; 8],
+        strike: [f32; 8],
+        volatility: [f32; 8],
+        risk_free_rate: [f32; 8],
+        dividend_yield: [f32; 8],
+        years_to_expiry: [f32; 8],
+    }
+
+    fn options() -> impl Strategy<Value = Options> {
+        (
+            uniform8(50.0f32..150.0),
+            uniform8(0.7f32..1.3),
+            uniform8(0.05f32..1.0),
+            uniform8(0.0f32..0.08),
+            uniform8(0.0f32..0.04),
+            uniform8(0.05f32..2.0),
+        ).prop_map(|(spot, moneyness, volatility, risk_free_rate, dividend_yield, years_to_expiry)| {
+            Options {
+                spot,
+                strike: std::array::from_fn(|idx| spot[idx] * moneyness[idx]),
+                volatility,
+                risk_free_rate,
+                dividend_yield,
+                years_to_expiry,
+            }
+        })
+    }
+
+    fn option_dir() -> impl Strategy<Value = OptionDir> {
+        prop_oneof![Just(OptionDir::CALL), Just(OptionDir::PUT)]
+    }
+
+    fn price_of(dir: OptionDir, options: &Options, strike: [f32; 8], volatility: [f32; 8]) -> [f32; 8] {
+        lanes(
+            price::<Lanes>(
+                dir,
+                Portable(options.spot),
+                Portable(strike),
+                Portable(volatility),
+                Portable(options.risk_free_rate),
+                Portable(options.dividend_yield),
+                Portable(options.years_to_expiry)
+            )
+        )
+    }
+
+    fn vega_of(options: &Options) -> [f32; 8] {
+        lanes(
+            vega::<Lanes>(
+                Portable(options.spot),
+                Portable(options.strike),
+                Portable(options.volatility),
+                Portable(options.risk_free_rate),
+                Portable(options.dividend_yield),
+                Portable(options.years_to_expiry)
+            )
+        )
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn price_implied_vol_round_trip(dir in option_dir(), options in options()) {
+            let price = price_of(dir, &options, options.strike, options.volatility);
+            let vol = lanes(
+                implied_vol::<Lanes>(
+                    dir,
+                    Portable(price),
+                    Portable(options.spot),
+                    Portable(options.strike),
+                    Portable(options.risk_free_rate),
+                    Portable(options.dividend_yield),
+                    Portable(options.years_to_expiry),
+                    1e-6,
+                    40
+                )
+            );
+            let reprice = price_of(dir, &options, options.strike, vol);
+            let vega = vega_of(&options);
+
+            for idx in 0..8 {
+                prop_assert!(
+                    (reprice[idx] - price[idx]).abs() < 1e-5 * options.spot[idx],
+                    "Lane {}: price {} repriced {}", idx, price[idx], reprice[idx]
+                );
+
+                // Deep in the money the price hardly moves with vol, so only the price comes back
+                if vega[idx] > 0.01 * options.spot[idx] {
+                    prop_assert!(
+                        (vol[idx] - options.volatility[idx]).abs() < 1e-3,
+                        "Lane {}: vol {} implied {}", idx, options.volatility[idx], vol[idx]
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn put_call_parity(options in options()) {
+            let call = price_of(OptionDir::CALL, &options, options.strike, options.volatility);
+            let put = price_of(OptionDir::PUT, &options, options.strike, options.volatility);
+
+            for idx in 0..8 {
+                let years_to_expiry = options.years_to_expiry[idx];
+                let forward_value =
+                    options.spot[idx] * (-options.dividend_yield[idx] * years_to_expiry).exp() -
+                    options.strike[idx] * (-options.risk_free_rate[idx] * years_to_expiry).exp();
+
+                prop_assert!(
+                    (call[idx] - put[idx] - forward_value).abs() < 1e-5 * options.spot[idx],
+                    "Lane {}: call {} put {} forward value {}", idx, call[idx], put[idx], forward_value
+                );
+            }
+        }
+
+        #[test]
+        fn monotone_in_vol_and_strike(
+            dir in option_dir(),
+            options in options(),
+            vol_step in 0.001f32..0.5,
+            strike_step in 0.001f32..0.2
+        ) {
+            let price = price_of(dir, &options, options.strike, options.volatility);
+            let higher_vol = price_of(dir, &options, options.strike, options.volatility.map(|vol| vol + vol_step));
+            let higher_strike = price_of(dir, &options, options.strike.map(|strike| strike * (1.0 + strike_step)), options.volatility);
+
+            for idx in 0..8 {
+                let tolerance = 1e-6 * options.spot[idx];
+
+                prop_assert!(higher_vol[idx] >= price[idx] - tolerance, "Lane {}: {} then {}", idx, price[idx], higher_vol[idx]);
+
+                match dir {
+                    OptionDir::CALL => prop_assert!(higher_strike[idx] <= price[idx] + tolerance, "Lane {}: {} then {}", idx, price[idx], higher_strike[idx]),
+                    OptionDir::PUT => prop_assert!(higher_strike[idx] >= price[idx] - tolerance, "Lane {}: {} then {}", idx, price[idx], higher_strike[idx]),
+                }
+            }
+        }
+
+        #[test]
+        fn matches_scalar(dir in option_dir(), options in options()) {
+            let price = price_of(dir, &options, options.strike, options.volatility);
+            let vega = vega_of(&options);
+            let delta = lanes(
+                delta::<Lanes>(
+                    dir,
+                    Portable(options.spot),
+                    Portable(options.strike),
+                    Portable(options.volatility),
+                    Portable(options.risk_free_rate),
+                    Portable(options.dividend_yield),
+                    Portable(options.years_to_expiry)
+                )
+            );
+
+            for idx in 0..8 {
+                let args = (
+                    options.spot[idx],
+                    options.strike[idx],
+                    options.volatility[idx],
+                    options.risk_free_rate[idx],
+                    options.dividend_yield[idx],
+                    options.years_to_expiry[idx],
+                );
+                let expected_price = bs::price(dir, args.0, args.1, args.2, args.3, args.4, args.5);
+                let expected_delta = bs::delta(dir, args.0, args.1, args.2, args.3, args.4, args.5);
+                let expected_vega = bs::vega(args.0, args.1, args.2, args.3, args.4, args.5);
+
+                // bs still uses the Abramowitz-Stegun erf, good to about 1e-7
+                prop_assert!((price[idx] - expected_price).abs() < 1e-6 * (args.0 + args.1), "Lane {}: price {} scalar {}", idx, price[idx], expected_price);
+                prop_assert!((delta[idx] - expected_delta).abs() < 1e-5, "Lane {}: delta {} scalar {}", idx, delta[idx], expected_delta);
+                prop_assert!((vega[idx] - expected_vega).abs() < 1e-4 * args.0, "Lane {}: vega {} scalar {}", idx, vega[idx], expected_vega);
+            }
+
+            // Both bisections on the slice API, where the scalar one stops after a fixed count
+            let slices = |values: [f32; 8]| values.to_vec();
+            let vol = crate::vol32x8::implied_vol(
+                dir,
+                &price,
+                &slices(options.spot),
+                &slices(options.strike),
+                &slices(options.risk_free_rate),
+                &slices(options.dividend_yield),
+                &slices(options.years_to_expiry),
+                30,
+                1e-6
+            );
+            let expected_vol = bs::implied_vol(
+                dir,
+                &price,
+                &slices(options.spot),
+                &slices(options.strike),
+                &slices(options.risk_free_rate),
+                &slices(options.dividend_yield),
+                &slices(options.years_to_expiry),
+                30,
+                1e-6
+            );
+
+            for idx in 0..8 {
+                if vega[idx] > 0.01 * options.spot[idx] {
+                    prop_assert!((vol[idx] - expected_vol[idx]).abs() < 1e-3, "Lane {}: vol {} scalar {}", idx, vol[idx], expected_vol[idx]);
+                }
+            }
+        }
+    }
+
+    /*
+        Published reference prices, quoted to the given precision: Hull, Options, Futures and
+        Other Derivatives (the first three), and Haug, The Complete Guide to Option Pricing
+        Formulas. Each is (dir, [spot, strike, vol, rate, dividend, years], price, precision).
+    */
+    const GOLDEN: [(OptionDir, [f32; 6], f32, f32); 6] = [
+        (OptionDir::CALL, [42.0, 40.0, 0.2, 0.1, 0.0, 0.5], 4.76, 0.005),
+        (OptionDir::PUT, [42.0, 40.0, 0.2, 0.1, 0.0, 0.5], 0.81, 0.005),
+        (OptionDir::CALL, [930.0, 900.0, 0.2, 0.08, 0.03, 2.0 / 12.0], 51.83, 0.005),
+        (OptionDir::CALL, [60.0, 65.0, 0.3, 0.08, 0.0, 0.25], 2.1334, 0.0001),
+        (OptionDir::PUT, [100.0, 95.0, 0.2, 0.1, 0.05, 0.5], 2.4648, 0.0001),
+        (OptionDir::PUT, [75.0, 70.0, 0.35, 0.1, 0.05, 0.5], 4.087, 0.0001),
+    ];
+
+    #[test]
+    fn golden_prices() {
+        for (dir, [spot, strike, vol, rate, dividend, years], expected, precision) in GOLDEN {
+            let actual = lanes(price::<Lanes>(dir, spot.into(), strike.into(), vol.into(), rate.into(), dividend.into(), years.into()));
+
+            assert!((actual[0] - expected).abs() <= precision, "{:?} {} {}: {} != {}", dir, spot, strike, actual[0], expected);
+        }
+    }
+
+    #[test]
+    fn golden_implied_vols() {
+        for (dir, [spot, strike, vol, rate, dividend, years], price, _) in GOLDEN {
+            let actual = lanes(
+                implied_vol::<Lanes>(dir, price.into(), spot.into(), strike.into(), rate.into(), dividend.into(), years.into(), 1e-6, 40)
+            );
+
+            assert!((actual[0] - vol).abs() < 1e-3, "{:?} {} {}: {} != {}", dir, spot, strike, actual[0], vol);
+        }
+    }
 }
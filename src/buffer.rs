@@ -0,0 +1,366 @@
+use js_sys::Float32Array;
+use wasm_bindgen::prelude::*;
+use crate::bs32x8;
+use crate::consts::OptionDir;
+use crate::simd;
+
+/*
+    Struct-of-arrays chain that lives in wasm linear memory.
+
+    Calling vol32x8::implied_vol from JS copies each of the six input arrays into wasm memory
+    and the result back out. A ChainBuffer instead owns its columns, and JS reads and writes them
+    in place through Float32Array views:
+
+        const buffer = new ChainBuffer(n);
+        buffer.price().set(prices);
+        buffer.spot().fill(spot);
+        ...
+        buffer.compute_implied_vol(OptionDir.CALL, 20, 0.0001);
+        buffer.compute_greeks(OptionDir.CALL);
+        const vols = buffer.implied_vol();
+
+    The views alias wasm memory, so they are invalidated whenever the memory grows (any
+    allocation on the Rust side, including creating another buffer). Get fresh views after such
+    calls instead of holding on to them. The compute methods split the columns in place and do
+    not allocate themselves, but with the wasm-threads pool rayon may still allocate while
+    scheduling the blocks, so get fresh views after computing as well.
+*/
+
+#[wasm_bindgen]
+pub struct ChainBuffer {
+    len: usize,
+
+    // Inputs
+    price: Vec<f32>,
+    spot: Vec<f32>,
+    strike: Vec<f32>,
+    risk_free_rate: Vec<f32>,
+    dividend_yield: Vec<f32>,
+    years_to_expiry: Vec<f32>,
+
+    // Outputs
+    implied_vol: Vec<f32>,
+    delta: Vec<f32>,
+    gamma: Vec<f32>,
+    vega: Vec<f32>,
+    theta: Vec<f32>,
+    rho: Vec<f32>,
+}
+
+/*
+    Views over a column. Both are only valid until the next allocation in wasm memory, which
+    moves the memory's buffer and detaches the view: JS then reads a length of 0 and its writes
+    are silently dropped, so a view must not be held across any call that may allocate.
+*/
+
+// View that JS only reads from, for the output columns
+fn view(column: &[f32]) -> Float32Array {
+    unsafe { Float32Array::view(column) }
+}
+
+// View that JS writes the column through, for the input columns
+fn view_mut(column: &mut [f32]) -> Float32Array {
+    unsafe { Float32Array::view_mut_raw(column.as_mut_ptr(), column.len()) }
+}
+
+#[wasm_bindgen]
+impl ChainBuffer {
+    // Buffer of len options with every column zeroed
+    #[wasm_bindgen(constructor)]
+    pub fn new(len: usize) -> ChainBuffer {
+        // Start the global thread pool now rather than on the first compute call
+        rayon::current_num_threads();
+
+        ChainBuffer {
+            len,
+            price: vec![0.0; len],
+            spot: vec![0.0; len],
+            strike: vec![0.0; len],
+            risk_free_rate: vec![0.0; len],
+            dividend_yield: vec![0.0; len],
+            years_to_expiry: vec![0.0; len],
+            implied_vol: vec![0.0; len],
+            delta: vec![0.0; len],
+            gamma: vec![0.0; len],
+            vega: vec![0.0; len],
+            theta: vec![0.0; len],
+            rho: vec![0.0; len],
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The input columns are written from JS through these views, so they borrow mutably
+    pub fn price(&mut self) -> Float32Array {
+        view_mut(&mut self.price)
+    }
+
+    pub fn spot(&mut self) -> Float32Array {
+        view_mut(&mut self.spot)
+    }
+
+    pub fn strike(&mut self) -> Float32Array {
+        view_mut(&mut self.strike)
+    }
+
+    pub fn risk_free_rate(&mut self) -> Float32Array {
+        view_mut(&mut self.risk_free_rate)
+    }
+
+    pub fn dividend_yield(&mut self) -> Float32Array {
+        view_mut(&mut self.dividend_yield)
+    }
+
+    pub fn years_to_expiry(&mut self) -> Float32Array {
+        view_mut(&mut self.years_to_expiry)
+    }
+
+    // Also an input, holding the guesses of compute_implied_vol_guess
+    pub fn implied_vol(&mut self) -> Float32Array {
+        view_mut(&mut self.implied_vol)
+    }
+
+    // The Greeks columns are outputs only and read from JS
+    pub fn delta(&self) -> Float32Array {
+        view(&self.delta)
+    }
+
+    pub fn gamma(&self) -> Float32Array {
+        view(&self.gamma)
+    }
+
+    pub fn vega(&self) -> Float32Array {
+        view(&self.vega)
+    }
+
+    pub fn theta(&self) -> Float32Array {
+        view(&self.theta)
+    }
+
+    pub fn rho(&self) -> Float32Array {
+        view(&self.rho)
+    }
+
+    /*
+        Implied volatility of every option into the implied_vol column, with the same bisection
+        as vol32x8::implied_vol. Returns false and leaves the column untouched for invalid
+        max_iterations or threshold.
+    */
+    pub fn compute_implied_vol(
+        &mut self,
+        option_dir: OptionDir,
+        max_iterations: i32,
+        threshold: f32
+    ) -> bool {
+        if !(1 < max_iterations && 0.0 < threshold) {
+            return false;
+        }
+
+        let ChainBuffer {
+            price,
+            spot,
+            strike,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry,
+            implied_vol,
+            ..
+        } = self;
+
+        let kernel = bs32x8::ImpliedVolKernel {
+            option_dir,
+            max_iterations,
+            threshold,
+        };
+        simd::map_into(
+            &kernel,
+            [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry],
+            [implied_vol]
+        );
+
+        true
+    }
+
+    /*
+        Same as compute_implied_vol, but warm started from the values already in the implied_vol
+        column (see vol32x8::implied_vol_guess). Meant for recomputing the buffer after the
+        prices change, as the column still holds the previous snapshot's vols.
+    */
+    pub fn compute_implied_vol_guess(
+        &mut self,
+        option_dir: OptionDir,
+        half_width: f32,
+        max_iterations: i32,
+        threshold: f32
+    ) -> bool {
+        if !(1 < max_iterations && 0.0 < threshold) {
+            return false;
+        }
+
+        let ChainBuffer {
+            price,
+            spot,
+            strike,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry,
+            implied_vol,
+            ..
+        } = self;
+
+        let kernel = bs32x8::ImpliedVolGuessKernel { option_dir, half_width, max_iterations, threshold };
+        simd::map_into(
+            &kernel,
+            [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry],
+            [implied_vol]
+        );
+
+        true
+    }
+
+    // Greeks of every option into their columns, priced at the implied_vol column
+    pub fn compute_greeks(&mut self, option_dir: OptionDir) {
+        let ChainBuffer {
+            spot,
+            strike,
+            risk_free_rate,
+            dividend_yield,
+            years_to_expiry,
+            implied_vol,
+            delta,
+            gamma,
+            vega,
+            theta,
+            rho,
+            ..
+        } = self;
+
+        simd::map_into(
+            &bs32x8::GreeksKernel { option_dir },
+            [spot, strike, implied_vol, risk_free_rate, dividend_yield, years_to_expiry],
+            [delta, gamma, vega, theta, rho]
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::read_hist;
+    use crate::vol32x8;
+
+    #[test]
+    fn matches_vol32x8() {
+        let (spot, call_prices, call_strikes, _, _, years_to_expiry, _) =
+            read_hist::get_appl_data();
+
+        // Not a multiple of 8, so the last chunk is partial
+        let n = 1003.min(call_prices.len());
+        let mut buffer = ChainBuffer::new(n);
+
+        buffer.price.copy_from_slice(&call_prices[..n]);
+        buffer.spot.fill(spot);
+        buffer.strike.copy_from_slice(&call_strikes[..n]);
+        buffer.risk_free_rate.fill(0.05);
+        buffer.years_to_expiry.copy_from_slice(&years_to_expiry[..n]);
+
+        assert!(buffer.compute_implied_vol(OptionDir::CALL, 20, 0.0001));
+        buffer.compute_greeks(OptionDir::CALL);
+
+        let expected_vol = vol32x8::implied_vol(
+            OptionDir::CALL,
+            &buffer.price,
+            &buffer.spot,
+            &buffer.strike,
+            &buffer.risk_free_rate,
+            &buffer.dividend_yield,
+            &buffer.years_to_expiry,
+            20,
+            0.0001
+        );
+        let expected_greeks = vol32x8::greeks(
+            OptionDir::CALL,
+            &buffer.spot,
+            &buffer.strike,
+            &expected_vol,
+            &buffer.risk_free_rate,
+            &buffer.dividend_yield,
+            &buffer.years_to_expiry
+        );
+
+        assert_eq!(buffer.implied_vol, expected_vol);
+        assert_eq!(buffer.delta, expected_greeks.delta);
+        assert_eq!(buffer.gamma, expected_greeks.gamma);
+        assert_eq!(buffer.vega, expected_greeks.vega);
+        assert_eq!(buffer.theta, expected_greeks.theta);
+        assert_eq!(buffer.rho, expected_greeks.rho);
+    }
+
+    #[test]
+    fn warm_start_after_price_move() {
+        let (spot, call_prices, call_strikes, _, _, years_to_expiry, _) =
+            read_hist::get_appl_data();
+
+        let n = call_prices.len();
+        let mut buffer = ChainBuffer::new(n);
+
+        buffer.price.copy_from_slice(&call_prices);
+        buffer.spot.fill(spot);
+        buffer.strike.copy_from_slice(&call_strikes);
+        buffer.risk_free_rate.fill(0.05);
+        buffer.years_to_expiry.copy_from_slice(&years_to_expiry);
+
+        buffer.compute_implied_vol(OptionDir::CALL, 30, 0.00001);
+
+        for price in buffer.price.iter_mut() {
+            *price *= 1.01;
+        }
+
+        let mut cold = ChainBuffer::new(n);
+        cold.price.copy_from_slice(&buffer.price);
+        cold.spot.copy_from_slice(&buffer.spot);
+        cold.strike.copy_from_slice(&buffer.strike);
+        cold.risk_free_rate.copy_from_slice(&buffer.risk_free_rate);
+        cold.years_to_expiry.copy_from_slice(&buffer.years_to_expiry);
+        cold.compute_implied_vol(OptionDir::CALL, 30, 0.00001);
+
+        assert!(buffer.compute_implied_vol_guess(OptionDir::CALL, 0.02, 30, 0.00001));
+
+        // Deep in the money the price is flat in vol, so the root is only unique up to the
+        // price; there both vols must reprice the option
+        let reprice = |i: usize, vol: f32| {
+            bs::call_price(spot, call_strikes[i], vol, 0.05, 0.0, years_to_expiry[i])
+        };
+
+        for i in 0..n {
+            let (warm, cold) = (buffer.implied_vol[i], cold.implied_vol[i]);
+
+            assert!(
+                (warm - cold).abs() < 0.001 || (reprice(i, warm) - reprice(i, cold)).abs() < 0.001,
+                "Option: {} Warm: {} Cold: {}",
+                i,
+                warm,
+                cold
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        let mut buffer = ChainBuffer::new(3);
+
+        assert!(!buffer.compute_implied_vol(OptionDir::PUT, 0, 0.0001));
+        assert!(!buffer.compute_implied_vol(OptionDir::PUT, 20, 0.0));
+        assert!(!buffer.compute_implied_vol_guess(OptionDir::PUT, 0.02, 0, 0.0001));
+        assert_eq!(buffer.implied_vol, vec![0.0; 3]);
+        assert!(ChainBuffer::new(0).is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use crate::consts::OptionDir;
+use crate::vol32x8;
+use serde::Serialize;
+
+// Per-contract view of an option chain, keeping the quote and activity fields that the
+// mid-price arrays in read_hist throw away.
+
+/// A single listed contract with its quote and trading activity
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionQuote {
+    pub option_dir: OptionDir,
+    pub name: String,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub bid: f32,
+    pub ask: f32,
+    pub volume: u32,
+    pub open_interest: u32,
+}
+
+impl OptionQuote {
+    pub fn mid(&self) -> f32 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    // Bid-ask spread as a fraction of the mid. Infinite when there is no two-sided market.
+    pub fn relative_spread(&self) -> f32 {
+        let mid = self.mid();
+
+        if mid <= 0.0 || self.ask < self.bid {
+            return f32::INFINITY;
+        }
+
+        (self.ask - self.bid) / mid
+    }
+
+    // Strike over spot, so 1.0 is at-the-money
+    pub fn moneyness(&self, spot: f32) -> f32 {
+        self.strike / spot
+    }
+
+    pub fn is_otm(&self, spot: f32) -> bool {
+        match self.option_dir {
+            OptionDir::CALL => self.strike >= spot,
+            OptionDir::PUT => self.strike <= spot,
+        }
+    }
+}
+
+/// Implied volatility and Greeks of one contract, as output by the simd-vol binary and server
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuoteAnalytics {
+    pub name: String,
+    pub option_type: &'static str,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub bid: f32,
+    pub ask: f32,
+    pub mid: f32,
+    pub implied_vol: f32,
+    pub delta: f32,
+    pub gamma: f32,
+    pub vega: f32,
+    pub theta: f32,
+    pub rho: f32,
+}
+
+/// Calls and puts of one underlying, quoted against a single spot
+#[derive(Debug, Clone, Default)]
+pub struct OptionChain {
+    pub spot: f32,
+    pub quotes: Vec<OptionQuote>,
+}
+
+impl OptionChain {
+    pub fn new(spot: f32, quotes: Vec<OptionQuote>) -> OptionChain {
+        OptionChain { spot, quotes }
+    }
+
+    /*
+        Builds a chain from the mid-price arrays used by the frontend and read_hist, where row i
+        holds the call and put quoted at strike[i] and years_to_expiry[i]. Bid and ask are both
+        set to the mid and no activity is recorded. Returns a chain without quotes if the array
+        lengths do not match.
+    */
+    pub fn from_mids(
+        spot: f32,
+        call_prices: &[f32],
+        put_prices: &[f32],
+        strike: &[f32],
+        years_to_expiry: &[f32]
+    ) -> OptionChain {
+        if
+            !(
+                call_prices.len() == put_prices.len() &&
+                put_prices.len() == strike.len() &&
+                strike.len() == years_to_expiry.len()
+            )
+        {
+            return OptionChain::new(spot, Vec::new());
+        }
+
+        let mut quotes = Vec::with_capacity(2 * strike.len());
+
+        for i in 0..strike.len() {
+            for (option_dir, price) in [(OptionDir::CALL, call_prices[i]), (OptionDir::PUT, put_prices[i])] {
+                quotes.push(OptionQuote {
+                    option_dir,
+                    name: String::new(),
+                    strike: strike[i],
+                    years_to_expiry: years_to_expiry[i],
+                    bid: price,
+                    ask: price,
+                    volume: 0,
+                    open_interest: 0,
+                });
+            }
+        }
+
+        OptionChain::new(spot, quotes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.quotes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quotes.is_empty()
+    }
+
+    // Distinct expiries in ascending order
+    pub fn expiries(&self) -> Vec<f32> {
+        let mut expiries: Vec<f32> = self.quotes
+            .iter()
+            .map(|quote| quote.years_to_expiry)
+            .collect();
+        expiries.sort_by(|a, b| a.total_cmp(b));
+        expiries.dedup();
+        expiries
+    }
+
+    // Contracts expiring at exactly years_to_expiry
+    pub fn expiry(&self, years_to_expiry: f32) -> OptionChain {
+        let quotes = self.quotes
+            .iter()
+            .filter(|quote| quote.years_to_expiry == years_to_expiry)
+            .cloned()
+            .collect();
+
+        OptionChain::new(self.spot, quotes)
+    }
+
+    /*
+        Forward price of one expiry from put-call parity, C - P = e^(-rT) (F - K), evaluated at
+        the strike where the call and put mids are closest. This is the strike least affected by
+        the bid-ask spread. Returns None if no strike has both a call and a put quoted.
+    */
+    pub fn parity_forward(&self, years_to_expiry: f32, risk_free_rate: f32) -> Option<f32> {
+        let expiry = self.expiry(years_to_expiry);
+        let mut best: Option<(f32, f32)> = None;
+
+        for call in expiry.quotes.iter().filter(|quote| quote.option_dir == OptionDir::CALL) {
+            let put = expiry.quotes
+                .iter()
+                .find(|quote| quote.option_dir == OptionDir::PUT && quote.strike == call.strike);
+
+            if let Some(put) = put {
+                if call.mid() <= 0.0 || put.mid() <= 0.0 {
+                    continue;
+                }
+
+                let diff = call.mid() - put.mid();
+
+                if best.is_none_or(|(_, best_diff)| diff.abs() < best_diff.abs()) {
+                    best = Some((call.strike, diff));
+                }
+            }
+        }
+
+        best.map(|(strike, diff)| strike + (risk_free_rate * years_to_expiry).exp() * diff)
+    }
+
+    /*
+        Average interest rate implied by put-call parity over every strike with both a call and
+        a put quoted, as done by the frontend before calculating implied volatility.
+        Returns 0 if no strike can be used.
+    */
+    pub fn parity_interest_rate(&self) -> f32 {
+        let mut call_price = Vec::new();
+        let mut put_price = Vec::new();
+        let mut strike = Vec::new();
+        let mut years_to_expiry = Vec::new();
+
+        // Put mids keyed by the bits of (strike, years_to_expiry)
+        let puts: HashMap<(u32, u32), f32> = self.quotes
+            .iter()
+            .filter(|quote| quote.option_dir == OptionDir::PUT)
+            .map(|quote| ((quote.strike.to_bits(), quote.years_to_expiry.to_bits()), quote.mid()))
+            .collect();
+
+        for call in self.quotes.iter().filter(|quote| quote.option_dir == OptionDir::CALL) {
+            if call.mid() <= 0.0 || call.years_to_expiry <= 0.0 {
+                continue;
+            }
+
+            let put = puts.get(&(call.strike.to_bits(), call.years_to_expiry.to_bits()));
+
+            if let Some(put) = put.filter(|put| **put > 0.0) {
+                call_price.push(call.mid());
+                put_price.push(*put);
+                strike.push(call.strike);
+                years_to_expiry.push(call.years_to_expiry);
+            }
+        }
+
+        vol32x8::parity_interest_rate(
+            &call_price,
+            &put_price,
+            &vec![self.spot; strike.len()],
+            &strike,
+            &years_to_expiry
+        )
+    }
+
+    /*
+        Splits one side of the chain into the (price, strike, years_to_expiry) columns
+        expected by vol32x8::implied_vol, pricing each contract at its mid.
+    */
+    pub fn columns(&self, option_dir: OptionDir) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let mut prices = Vec::new();
+        let mut strikes = Vec::new();
+        let mut years_to_expiry = Vec::new();
+
+        for quote in self.quotes.iter().filter(|quote| quote.option_dir == option_dir) {
+            prices.push(quote.mid());
+            strikes.push(quote.strike);
+            years_to_expiry.push(quote.years_to_expiry);
+        }
+
+        (prices, strikes, years_to_expiry)
+    }
+
+    // Implied volatility of every contract on one side of the chain, in the order of columns()
+    pub fn implied_vol(
+        &self,
+        option_dir: OptionDir,
+        risk_free_rate: f32,
+        dividend_yield: f32,
+        max_iterations: i32,
+        threshold: f32
+    ) -> Vec<f32> {
+        let (prices, strikes, years_to_expiry) = self.columns(option_dir);
+
+        let n = prices.len();
+        let spot = vec![self.spot; n];
+        let risk_free_rate = vec![risk_free_rate; n];
+        let dividend_yield = vec![dividend_yield; n];
+
+        vol32x8::implied_vol(
+            option_dir,
+            &prices,
+            &spot,
+            &strikes,
+            &risk_free_rate,
+            &dividend_yield,
+            &years_to_expiry,
+            max_iterations,
+            threshold
+        )
+    }
+
+    // Implied volatility and Greeks of every contract, calls first then puts
+    pub fn analytics(
+        &self,
+        risk_free_rate: f32,
+        dividend_yield: f32,
+        max_iterations: i32,
+        threshold: f32
+    ) -> Vec<QuoteAnalytics> {
+        let mut rows = Vec::with_capacity(self.len());
+
+        for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+            let quotes: Vec<_> = self.quotes
+                .iter()
+                .filter(|quote| quote.option_dir == option_dir)
+                .collect();
+            let n = quotes.len();
+
+            let (_, strike, years_to_expiry) = self.columns(option_dir);
+            let implied_vol = self.implied_vol(
+                option_dir,
+                risk_free_rate,
+                dividend_yield,
+                max_iterations,
+                threshold
+            );
+            let greeks = vol32x8::greeks(
+                option_dir,
+                &vec![self.spot; n],
+                &strike,
+                &implied_vol,
+                &vec![risk_free_rate; n],
+                &vec![dividend_yield; n],
+                &years_to_expiry
+            );
+
+            for (i, quote) in quotes.into_iter().enumerate() {
+                rows.push(QuoteAnalytics {
+                    name: quote.name.clone(),
+                    option_type: match option_dir {
+                        OptionDir::CALL => "call",
+                        OptionDir::PUT => "put",
+                    },
+                    strike: quote.strike,
+                    years_to_expiry: quote.years_to_expiry,
+                    bid: quote.bid,
+                    ask: quote.ask,
+                    mid: quote.mid(),
+                    implied_vol: implied_vol[i],
+                    delta: greeks.delta[i],
+                    gamma: greeks.gamma[i],
+                    vega: greeks.vega[i],
+                    theta: greeks.theta[i],
+                    rho: greeks.rho[i],
+                });
+            }
+        }
+
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_mids() {
+        let chain = OptionChain::from_mids(100.0, &[5.0, 2.0], &[1.0], &[95.0, 105.0], &[0.5, 0.5]);
+
+        assert_eq!(chain.spot, 100.0);
+        assert!(chain.is_empty());
+    }
+}
@@ -2,8 +2,54 @@ use wasm_bindgen::prelude::*;
 
 /// Specify whether an option is put or call
 #[wasm_bindgen]
-#[derive(PartialEq, Debug, Copy, Clone, PartialOrd)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone, PartialOrd)]
 pub enum OptionDir {
     CALL = 2,
     PUT = 1,
 }
+
+/// Closed-form approximation used by vol32x8::implied_vol_approx. These skip the iterative
+/// solver of vol32x8::implied_vol and are meant for previews or as initial guesses.
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum VolMethod {
+    BrennerSubrahmanyam = 0,
+    CorradoMiller = 1,
+    Li = 2,
+}
+
+/// Option strategies built by strategy::strategy_legs. Each takes its strikes in ascending
+/// order, see there for the legs.
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum StrategyKind {
+    CallVertical = 0,
+    PutVertical = 1,
+    Straddle = 2,
+    Strangle = 3,
+    IronCondor = 4,
+    Calendar = 5,
+    Butterfly = 6,
+}
+
+/// Payoffs priced by montecarlo::monte_carlo_price. The barriers are monitored at the end of
+/// every time step and the Asian option pays off on the average of those spots.
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum ExoticKind {
+    European = 0,
+    UpAndOut = 1,
+    UpAndIn = 2,
+    DownAndOut = 3,
+    DownAndIn = 4,
+    Asian = 5,
+}
+
+/// Dynamics of the spot in the Monte Carlo pricer
+#[wasm_bindgen]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum MonteCarloModel {
+    Gbm = 0,
+    LocalVol = 1,
+    Heston = 2,
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::chain::{ OptionChain, OptionQuote };
+use crate::consts::OptionDir;
+use crate::surface::{ self, FittedSmile, VolSurface };
+use crate::vol32x8;
+
+/*
+    Stateful surface for streaming quotes.
+
+    Holds the last chain with the implied volatility of every contract and the fitted surface.
+    Quote updates only re-invert the contracts that changed, starting the bisection from their
+    previous vol (vol32x8::implied_vol_guess), and only refit the expiries those contracts belong
+    to. Every update returns the resulting diff: the contracts whose vol moved and the smiles
+    whose fit changed.
+
+    A spot move changes every contract, so set_spot re-inverts the whole chain, still warm
+    started from the previous vols.
+*/
+
+// Half width of the bracket around the previous vol when re-inverting a contract
+const WARM_START_HALF_WIDTH: f32 = 0.05;
+
+/// New quote for one contract, matched on direction, strike and expiry
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuoteUpdate {
+    pub option_dir: OptionDir,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub bid: f32,
+    pub ask: f32,
+}
+
+/// Implied volatility of one contract before and after an update. old_vol is NaN for new contracts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IvChange {
+    // Position of the contract in the engine's chain
+    pub index: usize,
+    pub name: String,
+    pub option_type: &'static str,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub old_vol: f32,
+    pub new_vol: f32,
+}
+
+/// Fitted smile of one expiry before and after an update. None if the expiry could not be fitted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SmileChange {
+    pub years_to_expiry: f32,
+    pub old: Option<FittedSmile>,
+    pub new: Option<FittedSmile>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SurfaceDiff {
+    pub implied_vols: Vec<IvChange>,
+    pub smiles: Vec<SmileChange>,
+}
+
+impl SurfaceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.implied_vols.is_empty() && self.smiles.is_empty()
+    }
+}
+
+type ContractKey = (OptionDir, u32, u32);
+
+fn contract_key(option_dir: OptionDir, strike: f32, years_to_expiry: f32) -> ContractKey {
+    (option_dir, strike.to_bits(), years_to_expiry.to_bits())
+}
+
+fn option_type(option_dir: OptionDir) -> &'static str {
+    match option_dir {
+        OptionDir::CALL => "call",
+        OptionDir::PUT => "put",
+    }
+}
+
+pub struct SurfaceEngine {
+    chain: OptionChain,
+    // Parallel to chain.quotes
+    implied_vols: Vec<f32>,
+    index: HashMap<ContractKey, usize>,
+    surface: VolSurface,
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    max_iterations: i32,
+    threshold: f32,
+}
+
+impl SurfaceEngine {
+    /*
+        Inverts and fits the whole chain from scratch. Returns None for a max_iterations or
+        threshold the bisection rejects, as every later update would be left without vols.
+    */
+    pub fn new(
+        chain: OptionChain,
+        risk_free_rate: f32,
+        dividend_yield: f32,
+        max_iterations: i32,
+        threshold: f32
+    ) -> Option<SurfaceEngine> {
+        if !(1 < max_iterations && 0.0 < threshold) {
+            return None;
+        }
+
+        let mut implied_vols = vec![f32::NAN; chain.len()];
+
+        for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+            let vols = chain.implied_vol(
+                option_dir,
+                risk_free_rate,
+                dividend_yield,
+                max_iterations,
+                threshold
+            );
+            let indices = chain.quotes
+                .iter()
+                .enumerate()
+                .filter(|(_, quote)| quote.option_dir == option_dir)
+                .map(|(idx, _)| idx);
+
+            for (idx, vol) in indices.zip(vols) {
+                implied_vols[idx] = vol;
+            }
+        }
+
+        let index = chain.quotes
+            .iter()
+            .enumerate()
+            .map(|(idx, quote)| {
+                (contract_key(quote.option_dir, quote.strike, quote.years_to_expiry), idx)
+            })
+            .collect();
+        let surface = surface::fit_surface(&chain, risk_free_rate, max_iterations, threshold);
+
+        Some(SurfaceEngine {
+            chain,
+            implied_vols,
+            index,
+            surface,
+            risk_free_rate,
+            dividend_yield,
+            max_iterations,
+            threshold,
+        })
+    }
+
+    pub fn chain(&self) -> &OptionChain {
+        &self.chain
+    }
+
+    // Implied volatility of every contract, in the order of chain().quotes
+    pub fn implied_vols(&self) -> &[f32] {
+        &self.implied_vols
+    }
+
+    pub fn surface(&self) -> &VolSurface {
+        &self.surface
+    }
+
+    /*
+        Applies new quotes. Unknown contracts are added to the chain and solved from the full
+        bracket. Updates that leave the bid and ask unchanged are ignored.
+    */
+    pub fn update(&mut self, updates: &[QuoteUpdate]) -> SurfaceDiff {
+        let mut changed = Vec::new();
+
+        for update in updates {
+            let key = contract_key(update.option_dir, update.strike, update.years_to_expiry);
+
+            match self.index.get(&key) {
+                Some(&idx) => {
+                    let quote = &mut self.chain.quotes[idx];
+
+                    if quote.bid == update.bid && quote.ask == update.ask {
+                        continue;
+                    }
+
+                    quote.bid = update.bid;
+                    quote.ask = update.ask;
+                    changed.push(idx);
+                }
+                None => {
+                    self.chain.quotes.push(OptionQuote {
+                        option_dir: update.option_dir,
+                        name: String::new(),
+                        strike: update.strike,
+                        years_to_expiry: update.years_to_expiry,
+                        bid: update.bid,
+                        ask: update.ask,
+                        volume: 0,
+                        open_interest: 0,
+                    });
+                    self.implied_vols.push(f32::NAN);
+                    self.index.insert(key, self.chain.len() - 1);
+                    changed.push(self.chain.len() - 1);
+                }
+            }
+        }
+
+        changed.sort_unstable();
+        changed.dedup();
+
+        self.apply(&changed)
+    }
+
+    // Moves the spot, which re-inverts every contract and refits every expiry
+    pub fn set_spot(&mut self, spot: f32) -> SurfaceDiff {
+        if spot == self.chain.spot {
+            return SurfaceDiff::default();
+        }
+
+        self.chain.spot = spot;
+        let all: Vec<usize> = (0..self.chain.len()).collect();
+
+        self.apply(&all)
+    }
+
+    fn apply(&mut self, changed: &[usize]) -> SurfaceDiff {
+        let implied_vols = self.reinvert(changed);
+
+        let mut expiries: Vec<f32> = changed
+            .iter()
+            .map(|idx| self.chain.quotes[*idx].years_to_expiry)
+            .collect();
+        expiries.sort_by(|a, b| a.total_cmp(b));
+        expiries.dedup();
+
+        SurfaceDiff {
+            implied_vols,
+            smiles: self.refit(&expiries),
+        }
+    }
+
+    // Warm started inversion of the given contracts, returning the ones whose vol moved
+    fn reinvert(&mut self, indices: &[usize]) -> Vec<IvChange> {
+        let mut changes = Vec::new();
+
+        for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+            let indices: Vec<usize> = indices
+                .iter()
+                .copied()
+                .filter(|idx| self.chain.quotes[*idx].option_dir == option_dir)
+                .collect();
+            let n = indices.len();
+
+            if n == 0 {
+                continue;
+            }
+
+            let quotes: Vec<&OptionQuote> = indices
+                .iter()
+                .map(|idx| &self.chain.quotes[*idx])
+                .collect();
+            let price: Vec<f32> = quotes
+                .iter()
+                .map(|quote| quote.mid())
+                .collect();
+            let strike: Vec<f32> = quotes
+                .iter()
+                .map(|quote| quote.strike)
+                .collect();
+            let years_to_expiry: Vec<f32> = quotes
+                .iter()
+                .map(|quote| quote.years_to_expiry)
+                .collect();
+            let guess: Vec<f32> = indices
+                .iter()
+                .map(|idx| self.implied_vols[*idx])
+                .collect();
+
+            let vols = vol32x8::implied_vol_guess(
+                option_dir,
+                &price,
+                &vec![self.chain.spot; n],
+                &strike,
+                &vec![self.risk_free_rate; n],
+                &vec![self.dividend_yield; n],
+                &years_to_expiry,
+                &guess,
+                WARM_START_HALF_WIDTH,
+                self.max_iterations,
+                self.threshold
+            );
+
+            // Leave the previous vols in place if the solver rejected the inputs
+            if vols.len() != n {
+                continue;
+            }
+
+            for (i, idx) in indices.into_iter().enumerate() {
+                let old_vol = self.implied_vols[idx];
+
+                if old_vol.to_bits() == vols[i].to_bits() {
+                    continue;
+                }
+
+                let quote = &self.chain.quotes[idx];
+                changes.push(IvChange {
+                    index: idx,
+                    name: quote.name.clone(),
+                    option_type: option_type(option_dir),
+                    strike: quote.strike,
+                    years_to_expiry: quote.years_to_expiry,
+                    old_vol,
+                    new_vol: vols[i],
+                });
+                self.implied_vols[idx] = vols[i];
+            }
+        }
+
+        changes.sort_by_key(|change| change.index);
+        changes
+    }
+
+    // Refits the given expiries, returning the smiles whose fit changed
+    fn refit(&mut self, expiries: &[f32]) -> Vec<SmileChange> {
+        let mut changes = Vec::new();
+
+        self.surface.spot = self.chain.spot;
+
+        for &years_to_expiry in expiries {
+            let new = surface::fit_expiry(
+                &self.chain,
+                years_to_expiry,
+                self.risk_free_rate,
+                self.max_iterations,
+                self.threshold
+            );
+            let smiles = &mut self.surface.smiles;
+            let position = smiles.iter().position(|smile| smile.years_to_expiry == years_to_expiry);
+
+            let old = match (position, new.clone()) {
+                (Some(idx), Some(new)) => Some(std::mem::replace(&mut smiles[idx], new)),
+                (Some(idx), None) => Some(smiles.remove(idx)),
+                (None, Some(new)) => {
+                    let idx = smiles.partition_point(|smile| smile.years_to_expiry < years_to_expiry);
+                    smiles.insert(idx, new);
+                    None
+                }
+                (None, None) => None,
+            };
+
+            if old != new {
+                changes.push(SmileChange { years_to_expiry, old, new });
+            }
+        }
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_hist;
+
+    const RATE: f32 = 0.05;
+
+    fn spy_engine() -> SurfaceEngine {
+        SurfaceEngine::new(read_hist::get_spy_chain(), RATE, 0.0, 30, 0.00001).unwrap()
+    }
+
+    // Quote of the first call with a two-sided market in the given expiry
+    fn liquid_call(engine: &SurfaceEngine, years_to_expiry: f32) -> OptionQuote {
+        engine
+            .chain()
+            .quotes.iter()
+            .find(|quote| {
+                quote.option_dir == OptionDir::CALL &&
+                    quote.years_to_expiry == years_to_expiry &&
+                    quote.bid > 1.0 &&
+                    quote.strike > engine.chain().spot
+            })
+            .unwrap()
+            .clone()
+    }
+
+    fn cold_vols(engine: &SurfaceEngine) -> Vec<f32> {
+        SurfaceEngine::new(engine.chain().clone(), RATE, 0.0, 30, 0.00001).unwrap().implied_vols().to_vec()
+    }
+
+    #[test]
+    fn update_reinverts_changed_contract() {
+        let mut engine = spy_engine();
+        let years_to_expiry = engine.chain().expiries()[5];
+        let quote = liquid_call(&engine, years_to_expiry);
+
+        let diff = engine.update(
+            &[
+                QuoteUpdate {
+                    option_dir: quote.option_dir,
+                    strike: quote.strike,
+                    years_to_expiry,
+                    bid: quote.bid + 0.1,
+                    ask: quote.ask + 0.1,
+                },
+            ]
+        );
+
+        assert_eq!(diff.implied_vols.len(), 1);
+        let change = &diff.implied_vols[0];
+        assert!(change.new_vol > change.old_vol);
+        assert_eq!(change.name, quote.name);
+
+        // Warm start agrees with solving from scratch
+        let cold = cold_vols(&engine);
+        assert!((cold[change.index] - change.new_vol).abs() < 0.0001);
+
+        // Only the updated expiry is refitted
+        assert!(diff.smiles.iter().all(|smile| smile.years_to_expiry == years_to_expiry));
+    }
+
+    #[test]
+    fn unchanged_and_new_quotes() {
+        let mut engine = spy_engine();
+        let years_to_expiry = engine.chain().expiries()[5];
+        let quote = liquid_call(&engine, years_to_expiry);
+        let n = engine.chain().len();
+
+        let same = QuoteUpdate {
+            option_dir: quote.option_dir,
+            strike: quote.strike,
+            years_to_expiry,
+            bid: quote.bid,
+            ask: quote.ask,
+        };
+        assert!(engine.update(std::slice::from_ref(&same)).is_empty());
+
+        let new = QuoteUpdate { strike: 10000.0, bid: 0.01, ask: 0.02, ..same };
+        let diff = engine.update(&[new]);
+
+        assert_eq!(engine.chain().len(), n + 1);
+        assert_eq!(diff.implied_vols.len(), 1);
+        assert!(diff.implied_vols[0].old_vol.is_nan());
+        assert_eq!(diff.implied_vols[0].index, n);
+    }
+
+    #[test]
+    fn spot_move_matches_cold_start() {
+        let mut engine = spy_engine();
+        let spot = engine.chain().spot * 1.005;
+
+        let diff = engine.set_spot(spot);
+        let cold = cold_vols(&engine);
+
+        assert!(!diff.implied_vols.is_empty());
+        assert_eq!(engine.surface().spot, spot);
+
+        // Deep contracts have little vega, so both only agree to the precision of an f32 price
+        for (warm, cold) in engine.implied_vols().iter().zip(cold.iter()) {
+            assert!((warm - cold).abs() < 0.001 || (warm.is_nan() && cold.is_nan()), "Warm: {} Cold: {}", warm, cold);
+        }
+    }
+
+    #[test]
+    fn invalid_solver_settings() {
+        let chain = read_hist::get_spy_chain();
+
+        assert!(SurfaceEngine::new(chain.clone(), RATE, 0.0, 0, 0.00001).is_none());
+        assert!(SurfaceEngine::new(chain.clone(), RATE, 0.0, 1, 0.00001).is_none());
+        assert!(SurfaceEngine::new(chain, RATE, 0.0, 30, 0.0).is_none());
+    }
+}
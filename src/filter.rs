@@ -0,0 +1,193 @@
+use crate::chain::{ OptionChain, OptionQuote };
+
+/*
+    Liquidity and quote-quality filtering for a parsed chain.
+
+    Raw CBOE chains contain many contracts with a zero bid, no volume and no open interest.
+    Their mids are not tradeable prices and invert to meaningless implied volatilities, so
+    the chain should be passed through filter_chain before vol32x8::implied_vol.
+
+    Checks run in the order of DropReason and a contract is reported against the first
+    check it fails.
+*/
+
+/// Thresholds applied by filter_chain. The default keeps every contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterConfig {
+    pub min_bid: f32,
+    // (ask - bid) / mid
+    pub max_relative_spread: f32,
+    pub min_volume: u32,
+    pub min_open_interest: u32,
+    // Moneyness window expressed as strike / spot
+    pub min_moneyness: f32,
+    pub max_moneyness: f32,
+    pub min_days_to_expiry: f32,
+    // Keep only out-of-the-money contracts (calls at or above spot, puts at or below)
+    pub otm_only: bool,
+}
+
+impl Default for FilterConfig {
+    fn default() -> FilterConfig {
+        FilterConfig {
+            min_bid: 0.0,
+            max_relative_spread: f32::INFINITY,
+            min_volume: 0,
+            min_open_interest: 0,
+            min_moneyness: 0.0,
+            max_moneyness: f32::INFINITY,
+            min_days_to_expiry: 0.0,
+            otm_only: false,
+        }
+    }
+}
+
+/// Why a contract was removed from the chain
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DropReason {
+    Expiry,
+    Moneyness,
+    InTheMoney,
+    Bid,
+    Spread,
+    Volume,
+    OpenInterest,
+}
+
+/// Outcome of filter_chain, indexed against the quotes of the input chain
+#[derive(Debug, Clone, Default)]
+pub struct FilterReport {
+    pub kept: Vec<usize>,
+    pub dropped: Vec<(usize, DropReason)>,
+}
+
+impl FilterReport {
+    pub fn num_dropped(&self, reason: DropReason) -> usize {
+        self.dropped
+            .iter()
+            .filter(|(_, r)| *r == reason)
+            .count()
+    }
+}
+
+fn check_quote(quote: &OptionQuote, spot: f32, config: &FilterConfig) -> Option<DropReason> {
+    let moneyness = quote.moneyness(spot);
+
+    if quote.years_to_expiry * 365.0 < config.min_days_to_expiry {
+        Some(DropReason::Expiry)
+    } else if !(config.min_moneyness..=config.max_moneyness).contains(&moneyness) {
+        Some(DropReason::Moneyness)
+    } else if config.otm_only && !quote.is_otm(spot) {
+        Some(DropReason::InTheMoney)
+    } else if quote.bid < config.min_bid {
+        Some(DropReason::Bid)
+    } else if quote.relative_spread() > config.max_relative_spread {
+        Some(DropReason::Spread)
+    } else if quote.volume < config.min_volume {
+        Some(DropReason::Volume)
+    } else if quote.open_interest < config.min_open_interest {
+        Some(DropReason::OpenInterest)
+    } else {
+        None
+    }
+}
+
+pub fn filter_chain(chain: &OptionChain, config: &FilterConfig) -> (OptionChain, FilterReport) {
+    let mut quotes = Vec::new();
+    let mut report = FilterReport::default();
+
+    for (idx, quote) in chain.quotes.iter().enumerate() {
+        match check_quote(quote, chain.spot, config) {
+            Some(reason) => report.dropped.push((idx, reason)),
+            None => {
+                report.kept.push(idx);
+                quotes.push(quote.clone());
+            }
+        }
+    }
+
+    (OptionChain::new(chain.spot, quotes), report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::OptionDir;
+    use crate::read_hist;
+
+    fn quote(option_dir: OptionDir, strike: f32, bid: f32, ask: f32) -> OptionQuote {
+        OptionQuote {
+            option_dir,
+            name: String::from(""),
+            strike,
+            years_to_expiry: 0.1,
+            bid,
+            ask,
+            volume: 10,
+            open_interest: 100,
+        }
+    }
+
+    #[test]
+    fn default_keeps_everything() {
+        let chain = OptionChain::new(
+            100.0,
+            vec![quote(OptionDir::CALL, 90.0, 0.0, 0.0), quote(OptionDir::PUT, 110.0, 0.0, 0.05)]
+        );
+
+        let (filtered, report) = filter_chain(&chain, &FilterConfig::default());
+
+        assert_eq!(filtered.len(), 2);
+        assert!(report.dropped.is_empty());
+    }
+
+    #[test]
+    fn reports_first_failed_check() {
+        let chain = OptionChain::new(
+            100.0,
+            vec![
+                quote(OptionDir::CALL, 105.0, 1.0, 1.1),
+                quote(OptionDir::CALL, 95.0, 0.0, 6.0),
+                quote(OptionDir::PUT, 95.0, 0.0, 0.05),
+                quote(OptionDir::PUT, 99.0, 1.0, 3.0),
+                quote(OptionDir::PUT, 150.0, 1.0, 1.1)
+            ]
+        );
+
+        let config = FilterConfig {
+            min_bid: 0.01,
+            max_relative_spread: 0.5,
+            max_moneyness: 1.2,
+            otm_only: true,
+            ..FilterConfig::default()
+        };
+        let (filtered, report) = filter_chain(&chain, &config);
+
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(report.kept, vec![0]);
+        assert_eq!(
+            report.dropped,
+            vec![
+                (1, DropReason::InTheMoney),
+                (2, DropReason::Bid),
+                (3, DropReason::Spread),
+                (4, DropReason::Moneyness)
+            ]
+        );
+    }
+
+    #[test]
+    fn spy_zero_bids_dropped() {
+        let chain = read_hist::get_spy_chain();
+        let config = FilterConfig {
+            min_bid: 0.01,
+            ..FilterConfig::default()
+        };
+
+        let (filtered, report) = filter_chain(&chain, &config);
+
+        assert!(report.num_dropped(DropReason::Bid) > 0);
+        assert_eq!(filtered.len() + report.dropped.len(), chain.len());
+        assert!(filtered.quotes.iter().all(|quote| quote.bid > 0.0));
+    }
+}
@@ -0,0 +1,54 @@
+use crate::bs;
+use crate::consts::OptionDir;
+use crate::portfolio::Position;
+use crate::surface::{ FittedSmile, VolSurface };
+
+/*
+    Surfaces, chains and positions shared by the tests of the modules built on the fitted surface.
+*/
+
+// Smile fitted on log-moneyness -1 to 1 around a forward of 100
+pub fn smile(years_to_expiry: f32, coefficients: [f32; 3]) -> FittedSmile {
+    FittedSmile {
+        years_to_expiry,
+        forward: 100.0,
+        coefficients,
+        min_log_moneyness: -1.0,
+        max_log_moneyness: 1.0,
+        rmse: 0.0,
+    }
+}
+
+// Smile with vol c0 + c1 x at 3 months and a year, spot and forwards at 100
+pub fn skew_surface(c0: f32, c1: f32) -> VolSurface {
+    VolSurface { spot: 100.0, smiles: vec![smile(0.25, [c0, c1, 0.0]), smile(1.0, [c0, c1, 0.0])] }
+}
+
+pub fn flat_surface(vol: f32) -> VolSurface {
+    skew_surface(vol, 0.0)
+}
+
+// quantity contracts with a multiplier of 100, expiring in 6 months
+pub fn position(option_dir: OptionDir, strike: f32, quantity: f32) -> Position {
+    Position { option_dir, strike, years_to_expiry: 0.5, quantity, multiplier: 100.0 }
+}
+
+/*
+    Call price, put price, strike and years to expiry columns of a chain priced by bs::price at
+    a flat 20% vol and 3% rates from a spot of 100, with strikes 80 to 120 every strike_step
+    expiring in 3 months and a year.
+*/
+pub fn flat_chain(strike_step: usize) -> [Vec<f32>; 4] {
+    let mut columns: [Vec<f32>; 4] = Default::default();
+
+    for years in [0.25, 1.0] {
+        for strike in (80..=120).step_by(strike_step).map(|strike| strike as f32) {
+            columns[0].push(bs::price(OptionDir::CALL, 100.0, strike, 0.2, 0.03, 0.0, years));
+            columns[1].push(bs::price(OptionDir::PUT, 100.0, strike, 0.2, 0.03, 0.0, years));
+            columns[2].push(strike);
+            columns[3].push(years);
+        }
+    }
+
+    columns
+}
@@ -0,0 +1,578 @@
+use crate::chain::OptionChain;
+use crate::optimize::{ self, Options, Termination };
+use crate::smile::{ self, Smile };
+use rayon::prelude::*;
+use serde::Serialize;
+use std::ops::{ Add, Div, Mul, Sub };
+use wasm_bindgen::prelude::*;
+
+/*
+    Heston model priced with the COS method and calibrated to every expiry of the chain.
+
+    Under the forward measure of an expiry, X = ln(S_T / F) has the characteristic function
+        phi(u) = exp(C(u) + D(u) v0)
+    in the "little trap" form of Albrecher et al., which stays on the principal branch of the
+    complex log. The COS method (Fang and Oosterlee, 2008) expands the density of
+    y = ln(S_T / K) in COS_TERMS cosines on the range c1 +- COS_WIDTH sqrt(c2) around the
+    first two cumulants of X. The range is shifted by ln(F / K) for every strike so it always
+    holds the mass of y, which keeps the sum of the characteristic function strike independent:
+    it is computed once per expiry and every strike then only sums the payoff coefficients.
+
+    Prices are undiscounted and of the out of the money option, like the composite smiles
+    (see smile.rs) they are fitted to, and turned into Black vols with Let's Be Rational.
+
+    The calibration minimizes the vol errors of all expiries at once with the bounded
+    Levenberg-Marquardt of optimize.rs. Points too far in the wings to carry any vega are left out
+    and each expiry is thinned to MAX_POINTS_PER_EXPIRY strikes, so the long dated expiries with
+    few listed strikes are not drowned out. The Feller condition is not imposed.
+*/
+
+const COS_TERMS: usize = 256;
+const COS_WIDTH: f64 = 12.0;
+
+// Calibration points need a vega of at least this fraction of F sqrt(T), about |d1| < 2.6
+const MIN_NORMALIZED_VEGA: f64 = 0.01;
+const MAX_POINTS_PER_EXPIRY: usize = 30;
+
+// Bounds of [kappa, theta, xi, rho, v0]
+const LOWER_BOUNDS: [f64; 5] = [0.01, 0.0001, 0.01, -0.999, 0.0001];
+const UPPER_BOUNDS: [f64; 5] = [20.0, 4.0, 5.0, 0.999, 4.0];
+
+// Composite smiles as in fit_surface
+const BLEND_WIDTH: f32 = 0.01;
+
+/// Heston stochastic volatility parameters, with v0 and theta as variances
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct HestonParams {
+    pub v0: f32,
+    pub kappa: f32,
+    pub theta: f32,
+    pub xi: f32,
+    pub rho: f32,
+}
+
+impl HestonParams {
+    pub fn is_valid(&self) -> bool {
+        self.v0 >= 0.0 &&
+            self.kappa >= 0.0 &&
+            self.theta >= 0.0 &&
+            self.xi >= 0.0 &&
+            (-1.0..=1.0).contains(&self.rho)
+    }
+}
+
+/// Market and model vols of one expiry, at every strike of its smile
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HestonSmile {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    pub strikes: Vec<f32>,
+    pub market_vols: Vec<f32>,
+    pub model_vols: Vec<f32>,
+}
+
+/// Calibrated parameters, with the RMSE of the calibration points in vol points and how the
+/// optimizer stopped
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HestonCalibration {
+    pub params: HestonParams,
+    pub rmse: f32,
+    pub iterations: usize,
+    pub termination: Termination,
+    pub smiles: Vec<HestonSmile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    fn exp(self) -> Complex {
+        let scale = self.re.exp();
+        Complex::new(scale * self.im.cos(), scale * self.im.sin())
+    }
+
+    fn ln(self) -> Complex {
+        Complex::new(self.re.hypot(self.im).ln(), self.im.atan2(self.re))
+    }
+
+    // Principal square root, with the smaller part from the larger so it does not cancel
+    fn sqrt(self) -> Complex {
+        let modulus = self.re.hypot(self.im);
+
+        if modulus == 0.0 {
+            return Complex::new(0.0, 0.0);
+        }
+
+        if self.re >= 0.0 {
+            let re = (0.5 * (modulus + self.re)).sqrt();
+            Complex::new(re, self.im / (2.0 * re))
+        } else {
+            let im = (0.5 * (modulus - self.re)).sqrt();
+            Complex::new(self.im.abs() / (2.0 * im), if self.im < 0.0 { -im } else { im })
+        }
+    }
+
+    fn scale(self, factor: f64) -> Complex {
+        Complex::new(self.re * factor, self.im * factor)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(self.re * other.re - self.im * other.im, self.re * other.im + self.im * other.re)
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex {
+        let norm = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / norm,
+            (self.im * other.re - self.re * other.im) / norm
+        )
+    }
+}
+
+// [kappa, theta, xi, rho, v0] in f64, the order of the bounds
+fn to_vector(params: &HestonParams) -> [f64; 5] {
+    [params.kappa as f64, params.theta as f64, params.xi as f64, params.rho as f64, params.v0 as f64]
+}
+
+fn from_vector(x: &[f64]) -> HestonParams {
+    HestonParams {
+        kappa: x[0] as f32,
+        theta: x[1] as f32,
+        xi: x[2] as f32,
+        rho: x[3] as f32,
+        v0: x[4] as f32,
+    }
+}
+
+// Characteristic function of ln(S_T / F)
+fn characteristic_function(
+    [kappa, theta, xi, rho, v0]: [f64; 5],
+    years_to_expiry: f64,
+    u: f64
+) -> Complex {
+    let iu = Complex::new(0.0, u);
+    let xi2 = xi * xi;
+    let beta = Complex::new(kappa, 0.0) - iu.scale(rho * xi);
+    let d = (beta * beta + Complex::new(u * u, u).scale(xi2)).sqrt();
+    let g = (beta - d) / (beta + d);
+    let decay = d.scale(-years_to_expiry).exp();
+    let one = Complex::new(1.0, 0.0);
+
+    let c = ((beta - d).scale(years_to_expiry) - ((one - g * decay) / (one - g)).ln().scale(2.0)).scale(
+        (kappa * theta) / xi2
+    );
+    let big_d = ((beta - d) * (one - decay)) / ((one - g * decay).scale(xi2));
+
+    (c + big_d.scale(v0)).exp()
+}
+
+// First two cumulants of ln(S_T / F) (Fang and Oosterlee, 2008, Table 11)
+fn cumulants([kappa, theta, xi, rho, v0]: [f64; 5], t: f64) -> (f64, f64) {
+    let decay = (-kappa * t).exp();
+    let c1 = ((1.0 - decay) * (theta - v0)) / (2.0 * kappa) - 0.5 * theta * t;
+    let c2 =
+        (xi * t * kappa * decay * (v0 - theta) * (8.0 * kappa * rho - 4.0 * xi) +
+            kappa * rho * xi * (1.0 - decay) * (16.0 * theta - 8.0 * v0) +
+            2.0 * theta * kappa * t * (-4.0 * kappa * rho * xi + xi * xi + 4.0 * kappa * kappa) +
+            xi * xi * ((theta - 2.0 * v0) * decay * decay + theta * (6.0 * decay - 7.0) + 2.0 * v0) +
+            8.0 * kappa * kappa * (v0 - theta) * (1.0 - decay)) /
+        (8.0 * kappa * kappa * kappa);
+
+    // Expected integrated variance if cancellation leaves nothing sensible
+    let c2 = if c2.is_finite() && c2 > 0.0 { c2 } else { theta * t + ((v0 - theta) * (1.0 - decay)) / kappa };
+
+    (c1, c2.max(1e-12))
+}
+
+/*
+    Undiscounted prices of the out of the money option (put below the forward, call at and
+    above it) at each strike. NaN for invalid parameters or expiries.
+*/
+pub fn otm_prices(params: &HestonParams, forward: f32, years_to_expiry: f32, strikes: &[f32]) -> Vec<f64> {
+    let x = to_vector(params);
+
+    if !(params.is_valid() && forward > 0.0 && years_to_expiry > 0.0) {
+        return vec![f64::NAN; strikes.len()];
+    }
+
+    // A vanishing vol of vol is a deterministic variance, which the formula divides by
+    let x = [x[0].max(1e-4), x[1], x[2].max(1e-4), x[3], x[4]];
+    cos_prices(x, forward as f64, years_to_expiry as f64, strikes)
+}
+
+fn cos_prices(x: [f64; 5], forward: f64, years_to_expiry: f64, strikes: &[f32]) -> Vec<f64> {
+    let (c1, c2) = cumulants(x, years_to_expiry);
+    let half_width = COS_WIDTH * c2.sqrt();
+    let width = 2.0 * half_width;
+
+    // Re(phi(u_k) exp(i u_k (ln(F / K) - a))), where ln(F / K) - a = half_width - c1 for every strike
+    let shift = half_width - c1;
+    let weights: Vec<f64> = (0..COS_TERMS)
+        .map(|k| {
+            let u = ((k as f64) * std::f64::consts::PI) / width;
+            let term = characteristic_function(x, years_to_expiry, u) * Complex::new(0.0, u * shift).exp();
+
+            if k == 0 { 0.5 * term.re } else { term.re }
+        })
+        .collect();
+
+    strikes
+        .par_iter()
+        .map(|strike| {
+            let strike = *strike as f64;
+            let a = (forward / strike).ln() + c1 - half_width;
+            let b = a + width;
+            let is_call = strike >= forward;
+
+            // Payoff of y = ln(S_T / K) on its side of 0, in units of the strike
+            let (lower, upper) = if is_call { (a.max(0.0), b) } else { (a, b.min(0.0)) };
+
+            if lower >= upper {
+                return 0.0;
+            }
+
+            let sum: f64 = weights
+                .iter()
+                .enumerate()
+                .map(|(k, weight)| {
+                    let u = ((k as f64) * std::f64::consts::PI) / width;
+                    let (sin_upper, cos_upper) = (u * (upper - a)).sin_cos();
+                    let (sin_lower, cos_lower) = (u * (lower - a)).sin_cos();
+
+                    // Integrals of e^y cos(u (y - a)) and cos(u (y - a)) over [lower, upper]
+                    let chi =
+                        (cos_upper * upper.exp() - cos_lower * lower.exp() +
+                            u * (sin_upper * upper.exp() - sin_lower * lower.exp())) /
+                        (1.0 + u * u);
+                    let psi = if k == 0 { upper - lower } else { (sin_upper - sin_lower) / u };
+
+                    weight * (if is_call { chi - psi } else { psi - chi })
+                })
+                .sum();
+
+            ((2.0 * strike) / width) * sum.max(0.0)
+        })
+        .collect()
+}
+
+// Black vol of an undiscounted out of the money price, NaN where it has none
+fn black_vol(price: f64, forward: f64, strike: f64, years_to_expiry: f64) -> f64 {
+    let vol = ::implied_vol::implied_black_volatility(price, forward, strike, years_to_expiry, strike >= forward);
+
+    if vol.is_finite() && vol > 0.0 && vol < 10.0 { vol } else { f64::NAN }
+}
+
+// Model vols at each strike, NaN where the price has no Black vol
+pub fn model_vols(params: &HestonParams, forward: f32, years_to_expiry: f32, strikes: &[f32]) -> Vec<f32> {
+    otm_prices(params, forward, years_to_expiry, strikes)
+        .iter()
+        .zip(strikes)
+        .map(|(price, strike)| black_vol(*price, forward as f64, *strike as f64, years_to_expiry as f64) as f32)
+        .collect()
+}
+
+// Strikes of a smile that carry enough vega, at most MAX_POINTS_PER_EXPIRY spread along it
+fn calibration_points(smile: &Smile) -> (Vec<f32>, Vec<f64>) {
+    let years = smile.years_to_expiry as f64;
+    let points: Vec<(f32, f64)> = smile.strikes
+        .iter()
+        .zip(smile.vols.iter())
+        .filter(|(strike, vol)| {
+            let sd = (**vol as f64) * years.sqrt();
+            let d1 = ((smile.forward as f64) / (**strike as f64)).ln() / sd + 0.5 * sd;
+            let normalized_vega = (-0.5 * d1 * d1).exp() / (2.0 * std::f64::consts::PI).sqrt();
+
+            smile::is_valid_vol(**vol) && normalized_vega >= MIN_NORMALIZED_VEGA
+        })
+        .map(|(strike, vol)| (*strike, *vol as f64))
+        .collect();
+
+    let stride = points.len().div_ceil(MAX_POINTS_PER_EXPIRY).max(1);
+    points.iter().step_by(stride).copied().unzip()
+}
+
+// Vol errors at every point, with the price error over the market vega where the model price has no vol
+fn residuals(x: [f64; 5], expiries: &[(&Smile, Vec<f32>, Vec<f64>)]) -> Vec<f64> {
+    expiries
+        .iter()
+        .flat_map(|(smile, strikes, vols)| {
+            let forward = smile.forward as f64;
+            let years = smile.years_to_expiry as f64;
+            let prices = cos_prices(x, forward, years, strikes);
+
+            prices
+                .into_iter()
+                .zip(strikes.iter().zip(vols.iter()))
+                .map(move |(price, (strike, vol))| {
+                    let strike = *strike as f64;
+                    let model_vol = black_vol(price, forward, strike, years);
+
+                    if model_vol.is_finite() {
+                        return model_vol - vol;
+                    }
+
+                    let is_call = strike >= forward;
+                    let market_price = ::implied_vol::calculate_european_option_price_by_black_scholes(
+                        forward,
+                        strike,
+                        *vol,
+                        years,
+                        is_call
+                    );
+                    let sd = vol * years.sqrt();
+                    let d1 = (forward / strike).ln() / sd + 0.5 * sd;
+                    let vega = (forward * years.sqrt() * (-0.5 * d1 * d1).exp()) / (2.0 * std::f64::consts::PI).sqrt();
+
+                    (price - market_price) / vega
+                })
+                .collect::<Vec<f64>>()
+        })
+        .collect()
+}
+
+// ATM vols of the first and last expiry as v0 and theta, with a put skew to start from
+fn initial_guess(first: &Smile, last: &Smile) -> [f64; 5] {
+    let atm_variance = |smile: &Smile| (smile.vol_at(smile.forward) as f64).powi(2);
+
+    [2.0, atm_variance(last), 0.5, -0.5, atm_variance(first)]
+}
+
+/*
+    Calibrates the Heston parameters to the smiles of every expiry, with at most
+    max_iterations Levenberg-Marquardt steps. Without any smile with a calibration point the
+    parameters are NaN.
+*/
+pub fn calibrate(smiles: &[Smile], max_iterations: usize) -> HestonCalibration {
+    let expiries: Vec<(&Smile, Vec<f32>, Vec<f64>)> = smiles
+        .iter()
+        .filter(|smile| smile.years_to_expiry > 0.0 && smile.forward > 0.0)
+        .map(|smile| {
+            let (strikes, vols) = calibration_points(smile);
+            (smile, strikes, vols)
+        })
+        .filter(|(_, strikes, _)| !strikes.is_empty())
+        .collect();
+
+    if expiries.is_empty() {
+        return HestonCalibration {
+            params: from_vector(&[f64::NAN; 5]),
+            rmse: f32::NAN,
+            iterations: 0,
+            termination: Termination::InvalidInput,
+            smiles: Vec::new(),
+        };
+    }
+
+    let calibrated: Vec<&Smile> = expiries.iter().map(|(smile, _, _)| *smile).collect();
+    let initial = initial_guess(calibrated[0], calibrated[calibrated.len() - 1]);
+    let solution = optimize::levenberg_marquardt(
+        |x| residuals([x[0], x[1], x[2], x[3], x[4]], &expiries),
+        &initial,
+        &LOWER_BOUNDS,
+        &UPPER_BOUNDS,
+        &[],
+        &Options { max_iterations, ..Options::default() }
+    );
+    let num_points: usize = expiries.iter().map(|(_, strikes, _)| strikes.len()).sum();
+    let params = from_vector(&solution.x);
+
+    HestonCalibration {
+        params,
+        rmse: (100.0 * (solution.cost / (num_points as f64)).sqrt()) as f32,
+        iterations: solution.iterations,
+        termination: solution.termination,
+        smiles: calibrated
+            .iter()
+            .map(|smile| HestonSmile {
+                years_to_expiry: smile.years_to_expiry,
+                forward: smile.forward,
+                strikes: smile.strikes.clone(),
+                market_vols: smile.vols.clone(),
+                model_vols: model_vols(&params, smile.forward, smile.years_to_expiry, &smile.strikes),
+            })
+            .collect(),
+    }
+}
+
+/*
+    Frontend entry point. Takes the same mid-price arrays as smile_metrics_json, calibrates
+    Heston to the composite OTM smiles of every expiry and returns the HestonCalibration as
+    JSON, with the model vols of every smile for an overlay.
+*/
+#[wasm_bindgen]
+pub fn heston_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    max_iterations: usize
+) -> String {
+    let smiles = if
+        call_price.len() == put_price.len() &&
+        put_price.len() == strike.len() &&
+        strike.len() == years_to_expiry.len()
+    {
+        let chain = OptionChain::from_mids(spot, call_price, put_price, strike, years_to_expiry);
+        smile::otm_smiles(&chain, risk_free_rate, BLEND_WIDTH, 20, 0.0001)
+    } else {
+        Vec::new()
+    };
+
+    serde_json::to_string(&calibrate(&smiles, max_iterations)).unwrap_or_else(|_| String::from("{}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::consts::{ ExoticKind, OptionDir };
+    use crate::montecarlo::{ self, Dynamics, Exotic, MonteCarloConfig };
+    use crate::read_hist;
+
+    // Fang and Oosterlee (2008), Table 4
+    const BENCHMARK: HestonParams = HestonParams { v0: 0.0175, kappa: 1.5768, theta: 0.0398, xi: 0.5751, rho: -0.5711 };
+
+    fn model_smile(params: &HestonParams, years_to_expiry: f32) -> Smile {
+        let strikes: Vec<f32> = (80..=120).step_by(2).map(|strike| strike as f32).collect();
+        let vols = model_vols(params, 100.0, years_to_expiry, &strikes);
+
+        Smile { years_to_expiry, forward: 100.0, strikes, vols }
+    }
+
+    #[test]
+    fn cos_matches_benchmark() {
+        let prices = otm_prices(&BENCHMARK, 100.0, 1.0, &[100.0]);
+        assert!((prices[0] - 5.785155450).abs() < 1e-6, "Got: {}", prices[0]);
+
+        // The put at the same strike by parity
+        let put = otm_prices(&BENCHMARK, 100.0, 1.0, &[99.999_99])[0];
+        assert!((put - (5.785155450 - 100.0 + 99.999_99)).abs() < 1e-4, "Got: {}", put);
+
+        assert!(otm_prices(&HestonParams { rho: 1.5, ..BENCHMARK }, 100.0, 1.0, &[100.0])[0].is_nan());
+        assert!(otm_prices(&BENCHMARK, 100.0, 0.0, &[100.0])[0].is_nan());
+    }
+
+    #[test]
+    fn deterministic_variance_is_black() {
+        let params = HestonParams { v0: 0.09, kappa: 2.0, theta: 0.04, xi: 0.0, rho: 0.0 };
+        let years = 0.5f32;
+
+        // Mean variance over the life of the option
+        let decay = (-2.0f32 * years).exp();
+        let variance = 0.04 + ((0.09 - 0.04) * (1.0 - decay)) / (2.0 * years);
+
+        for strike in [70.0, 90.0, 100.0, 110.0, 140.0] {
+            let vol = model_vols(&params, 100.0, years, &[strike])[0];
+            assert!((vol - variance.sqrt()).abs() < 1e-4, "Strike: {} Vol: {}", strike, vol);
+        }
+    }
+
+    #[test]
+    fn matches_monte_carlo() {
+        let config = MonteCarloConfig { num_paths: 50_000, num_steps: 50, seed: 3 };
+
+        for (option_dir, strike) in [(OptionDir::PUT, 90.0), (OptionDir::CALL, 110.0)] {
+            let exotic = Exotic { kind: ExoticKind::European, option_dir, strike, barrier: 0.0, years_to_expiry: 1.0 };
+            let monte_carlo = montecarlo::monte_carlo_price(&exotic, &Dynamics::Heston(BENCHMARK), 100.0, 0.0, 0.0, &config);
+            let price = otm_prices(&BENCHMARK, 100.0, 1.0, &[strike])[0] as f32;
+
+            assert!(
+                (monte_carlo.price - price).abs() < 4.0 * monte_carlo.std_error + 0.02,
+                "Strike: {} Monte Carlo: {} COS: {}",
+                strike,
+                monte_carlo.price,
+                price
+            );
+        }
+    }
+
+    #[test]
+    fn calibration_recovers_parameters() {
+        let params = HestonParams { v0: 0.03, kappa: 2.5, theta: 0.05, xi: 0.7, rho: -0.65 };
+        let smiles: Vec<Smile> = [0.1, 0.25, 0.5, 1.0, 2.0].iter().map(|years| model_smile(&params, *years)).collect();
+
+        let calibration = calibrate(&smiles, 100);
+        let fitted = calibration.params;
+
+        assert!(calibration.rmse < 0.01, "RMSE: {}", calibration.rmse);
+        assert_ne!(calibration.termination, Termination::MaxIterations);
+        assert!((fitted.v0 - params.v0).abs() < 1e-3, "Fitted: {:?}", fitted);
+        assert!((fitted.theta - params.theta).abs() < 2e-3, "Fitted: {:?}", fitted);
+        assert!((fitted.rho - params.rho).abs() < 0.02, "Fitted: {:?}", fitted);
+        assert!((fitted.xi - params.xi).abs() < 0.05, "Fitted: {:?}", fitted);
+        assert!((fitted.kappa - params.kappa).abs() < 0.3, "Fitted: {:?}", fitted);
+
+        assert_eq!(calibration.smiles.len(), 5);
+        for smile in calibration.smiles {
+            for (market, model) in smile.market_vols.iter().zip(smile.model_vols.iter()) {
+                assert!((market - model).abs() < 1e-3, "Market: {} Model: {}", market, model);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_calibration() {
+        let calibration = calibrate(&[], 100);
+
+        assert!(calibration.rmse.is_nan() && calibration.params.v0.is_nan());
+        assert!(calibration.smiles.is_empty());
+        assert_eq!(calibration.termination, Termination::InvalidInput);
+        assert!(heston_json(&[1.0], &[], 100.0, &[], &[], 0.03, 100).contains(r#""rmse":null"#));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let [call_price, put_price, strike, years_to_expiry] = &fixtures::flat_chain(5);
+        let chain = OptionChain::from_mids(100.0, call_price, put_price, strike, years_to_expiry);
+        let smiles = smile::otm_smiles(&chain, 0.03, BLEND_WIDTH, 20, 0.0001);
+
+        let json = heston_json(call_price, put_price, 100.0, strike, years_to_expiry, 0.03, 20);
+        assert_eq!(json, serde_json::to_string(&calibrate(&smiles, 20)).unwrap());
+    }
+
+    #[test]
+    fn spy_calibration() {
+        let chain = read_hist::get_spy_chain();
+        let smiles = smile::otm_smiles(&chain, 0.05, BLEND_WIDTH, 30, 0.0001);
+        let calibration = calibrate(&smiles, 50);
+        let params = calibration.params;
+
+        assert!(params.is_valid(), "Params: {:?}", params);
+        assert!(params.rho < 0.0, "Params: {:?}", params);
+        assert!(calibration.rmse.is_finite() && calibration.rmse < 5.0, "RMSE: {}", calibration.rmse);
+        assert!(!calibration.smiles.is_empty() && calibration.smiles.len() <= smiles.len());
+    }
+}
@@ -1,6 +1,34 @@
 pub mod bs;
 mod bs32x8;
 
+pub mod buffer;
+pub mod chain;
+pub mod engine;
+pub mod filter;
+#[cfg(test)]
+mod fixtures;
+pub mod heston;
+pub mod localvol;
+pub mod metrics;
+pub mod montecarlo;
+pub mod optimize;
+pub mod portfolio;
+pub mod probability;
 pub mod read_hist;
+pub mod scenario;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod simd;
+pub mod smile;
+pub mod strategy;
+pub mod surface;
+pub mod termstructure;
+pub mod varswap;
+pub mod vix;
 pub mod vol32x8;
-pub mod consts;
\ No newline at end of file
+pub mod consts;
+
+// Awaited from JS as initThreadPool(navigator.hardwareConcurrency) before any other call.
+// Without it rayon runs everything on the calling thread.
+#[cfg(all(feature = "wasm-threads", target_arch = "wasm32"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
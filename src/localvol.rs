@@ -0,0 +1,297 @@
+use crate::smile::MIN_VOL;
+use crate::surface::{ self, FittedSmile, VolSurface };
+use wasm_bindgen::prelude::*;
+
+/*
+    Dupire local volatility of the fitted implied surface.
+
+    In terms of the implied total variance w(y, T) = sigma^2 T at log-moneyness y = ln(K / F(T)),
+    Dupire's formula is
+        sigma_loc^2 = dw/dT / (1 - (y / w) dw/dy + 1/4 (-1/4 - 1/w + y^2 / w^2) (dw/dy)^2
+                               + 1/2 d2w/dy2)
+    (Gatheral, The Volatility Surface, eq. 1.10).
+
+    The derivatives are analytic rather than finite differences of noisy vols. Each smile is a
+    quadratic in y, so its total variance w_i = sigma_i(y)^2 T_i has closed form derivatives in
+    y. Between expiries the surface is linear in T at fixed y, so dw/dT is the slope between the
+    two bracketing smiles. Before the first expiry and after the last one the smile's vol is
+    held, and dw/dT is its variance. At an expiry the slope towards the next one is used.
+
+    Past the fitted strikes the smile is extrapolated rather than fitted, and where it is floored
+    at MIN_VOL it has a kink, whose d2w/dy2 is a delta rather than the quadratic's. Neither says
+    anything about the local vol, so it is NaN outside the fitted strikes of the smiles used and
+    where one of them is floored.
+
+    The formula only holds where the surface is free of arbitrage. Where the denominator is not
+    positive (butterfly arbitrage) or total variance falls with T (calendar arbitrage), the local
+    vol is NaN rather than a made up number.
+
+    The surface is the per-expiry quadratic-in-vol fit of surface::fit_surface, which is not
+    arbitrage-free by construction: nothing in the fit keeps the density positive or the total
+    variance increasing between expiries. Only the NaN guards above keep the arbitrage out of the
+    result, so on real chains part of the grid is undefined. On the SPY chain 682 of the 756
+    nodes of spy_local_vol's grid are defined, and the test only requires half of them. An
+    SVI/SSVI fit, whose parameters can be constrained to be free of arbitrage, is the follow-up
+    that would define the whole grid (see Extensions in the README).
+*/
+
+// Denominator below which the local vol is not defined
+const MIN_DENOMINATOR: f32 = 1e-4;
+
+// Total variance at (y, T) and its derivatives
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Variance {
+    w: f32,
+    dw_dy: f32,
+    d2w_dy2: f32,
+    dw_dt: f32,
+}
+
+// (w, dw/dy, d2w/dy2) of a smile scaled to years_to_expiry. None outside the fitted strikes
+// and where the vol is floored.
+fn smile_variance(smile: &FittedSmile, log_moneyness: f32, years_to_expiry: f32) -> Option<(f32, f32, f32)> {
+    let [c0, c1, c2] = smile.coefficients;
+    let vol = c0 + c1 * log_moneyness + c2 * log_moneyness * log_moneyness;
+
+    if
+        !(
+            smile.min_log_moneyness <= log_moneyness &&
+            log_moneyness <= smile.max_log_moneyness &&
+            vol > MIN_VOL
+        )
+    {
+        return None;
+    }
+
+    let (slope, curvature) = (c1 + 2.0 * c2 * log_moneyness, 2.0 * c2);
+
+    Some((
+        vol * vol * years_to_expiry,
+        2.0 * vol * slope * years_to_expiry,
+        2.0 * (slope * slope + vol * curvature) * years_to_expiry,
+    ))
+}
+
+fn variance(surface: &VolSurface, log_moneyness: f32, years_to_expiry: f32) -> Option<Variance> {
+    let smiles = &surface.smiles;
+    let idx = smiles.partition_point(|smile| smile.years_to_expiry <= years_to_expiry);
+
+    if idx == 0 || idx == smiles.len() {
+        let smile = if idx == 0 { &smiles[0] } else { &smiles[idx - 1] };
+        let (w, dw_dy, d2w_dy2) = smile_variance(smile, log_moneyness, years_to_expiry)?;
+
+        return Some(Variance { w, dw_dy, d2w_dy2, dw_dt: w / years_to_expiry });
+    }
+
+    let (prev, next) = (&smiles[idx - 1], &smiles[idx]);
+    let (w1, dw1_dy, d2w1_dy2) = smile_variance(prev, log_moneyness, prev.years_to_expiry)?;
+    let (w2, dw2_dy, d2w2_dy2) = smile_variance(next, log_moneyness, next.years_to_expiry)?;
+    let dt = next.years_to_expiry - prev.years_to_expiry;
+    let weight = (years_to_expiry - prev.years_to_expiry) / dt;
+
+    Some(Variance {
+        w: w1 + weight * (w2 - w1),
+        dw_dy: dw1_dy + weight * (dw2_dy - dw1_dy),
+        d2w_dy2: d2w1_dy2 + weight * (d2w2_dy2 - d2w1_dy2),
+        dw_dt: (w2 - w1) / dt,
+    })
+}
+
+// NaN without any fitted smile, for expiries at or before today, outside the fitted strikes or
+// where there is arbitrage
+pub fn local_vol(surface: &VolSurface, strike: f32, years_to_expiry: f32) -> f32 {
+    if surface.smiles.is_empty() || years_to_expiry <= 0.0 {
+        return f32::NAN;
+    }
+
+    let y = (strike / surface.forward(years_to_expiry)).ln();
+    let Variance { w, dw_dy, d2w_dy2, dw_dt } = match variance(surface, y, years_to_expiry) {
+        Some(variance) => variance,
+        None => {
+            return f32::NAN;
+        }
+    };
+
+    let denominator =
+        1.0 - (y / w) * dw_dy + 0.25 * (-0.25 - 1.0 / w + (y * y) / (w * w)) * dw_dy * dw_dy +
+        0.5 * d2w_dy2;
+
+    if !(denominator > MIN_DENOMINATOR && dw_dt >= 0.0) {
+        return f32::NAN;
+    }
+
+    (dw_dt / denominator).sqrt()
+}
+
+// Local vols on a grid, where row i holds every strike at years_to_expiry[i] as in VolSurface::grid
+pub fn local_vol_grid(surface: &VolSurface, strikes: &[f32], years_to_expiry: &[f32]) -> Vec<Vec<f32>> {
+    years_to_expiry
+        .iter()
+        .map(|years| {
+            strikes
+                .iter()
+                .map(|strike| local_vol(surface, *strike, *years))
+                .collect()
+        })
+        .collect()
+}
+
+/*
+    Frontend entry point for plotting local vol next to implied vol. Fits the surface with
+    surface::fit_mids and returns the local vol grid as a JSON array of rows, with null where it
+    is not defined.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn local_vol_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    grid_strikes: &[f32],
+    grid_years_to_expiry: &[f32]
+) -> String {
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return String::from("[]");
+        }
+    };
+    let grid = local_vol_grid(&surface, grid_strikes, grid_years_to_expiry);
+
+    serde_json::to_string(&grid).unwrap_or_else(|_| String::from("[]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::read_hist;
+
+    fn smile(years_to_expiry: f32, coefficients: [f32; 3]) -> FittedSmile {
+        FittedSmile { min_log_moneyness: -0.5, max_log_moneyness: 0.5, ..fixtures::smile(years_to_expiry, coefficients) }
+    }
+
+    // Dupire with the derivatives of the total variance by central differences of surface.vol
+    fn numerical_local_vol(surface: &VolSurface, strike: f32, years: f32) -> f64 {
+        let forward = surface.forward(years) as f64;
+        let w = |y: f64, t: f64| -> f64 {
+            let vol = surface.vol((forward * y.exp()) as f32, t as f32) as f64;
+            vol * vol * t
+        };
+        let y = ((strike as f64) / forward).ln();
+        let (t, h, k) = (years as f64, 1e-2, 1e-3);
+
+        let w0 = w(y, t);
+        let dw_dy = (w(y + h, t) - w(y - h, t)) / (2.0 * h);
+        let d2w_dy2 = (w(y + h, t) - 2.0 * w0 + w(y - h, t)) / (h * h);
+        let dw_dt = (w(y, t + k) - w(y, t - k)) / (2.0 * k);
+        let denominator =
+            1.0 - (y / w0) * dw_dy + 0.25 * (-0.25 - 1.0 / w0 + (y * y) / (w0 * w0)) * dw_dy * dw_dy +
+            0.5 * d2w_dy2;
+
+        (dw_dt / denominator).sqrt()
+    }
+
+    #[test]
+    fn flat_surface_is_constant() {
+        let surface = VolSurface { spot: 100.0, smiles: vec![smile(0.25, [0.2, 0.0, 0.0]), smile(1.0, [0.2, 0.0, 0.0])] };
+
+        for years in [0.1, 0.25, 0.5, 1.0, 2.0] {
+            for strike in [70.0, 90.0, 100.0, 120.0, 160.0] {
+                let vol = local_vol(&surface, strike, years);
+                assert!((vol - 0.2).abs() < 1e-5, "Strike: {} Years: {} Vol: {}", strike, years, vol);
+            }
+        }
+
+        assert!(local_vol(&surface, 100.0, 0.0).is_nan());
+        assert!(local_vol(&VolSurface::default(), 100.0, 0.5).is_nan());
+    }
+
+    #[test]
+    fn term_structure_is_forward_vol() {
+        let surface = VolSurface { spot: 100.0, smiles: vec![smile(0.25, [0.2, 0.0, 0.0]), smile(1.0, [0.3, 0.0, 0.0])] };
+
+        // Forward variance between the expiries: (0.09 - 0.01) / 0.75
+        let forward_vol = (0.08f32 / 0.75).sqrt();
+        assert!((local_vol(&surface, 100.0, 0.5) - forward_vol).abs() < 1e-5);
+        assert!((local_vol(&surface, 100.0, 0.25) - forward_vol).abs() < 1e-5);
+        assert!((local_vol(&surface, 100.0, 0.1) - 0.2).abs() < 1e-5);
+        assert!((local_vol(&surface, 100.0, 2.0) - 0.3).abs() < 1e-5);
+    }
+
+    #[test]
+    fn skewed_surface_matches_finite_differences() {
+        let surface = VolSurface {
+            spot: 100.0,
+            smiles: vec![smile(0.25, [0.22, -0.25, 0.4]), smile(1.0, [0.24, -0.15, 0.2])],
+        };
+
+        for strike in [80.0, 90.0, 100.0, 110.0, 125.0] {
+            for years in [0.4, 0.6, 0.8] {
+                let vol = local_vol(&surface, strike, years);
+                let expected = numerical_local_vol(&surface, strike, years);
+
+                assert!(((vol as f64) - expected).abs() < 2e-3, "Strike: {} Years: {} Got: {}, Expected: {}", strike, years, vol, expected);
+            }
+        }
+
+        // Local skew is steeper than the implied one, about twice near the money
+        let local = local_vol(&surface, 95.0, 0.6) - local_vol(&surface, 105.0, 0.6);
+        let implied = surface.vol(95.0, 0.6) - surface.vol(105.0, 0.6);
+        assert!(local > 1.5 * implied, "Local: {} Implied: {}", local, implied);
+    }
+
+    #[test]
+    fn undefined_outside_the_fit() {
+        let surface = VolSurface {
+            spot: 100.0,
+            smiles: vec![
+                FittedSmile { min_log_moneyness: -0.2, ..smile(0.25, [0.22, -0.25, 0.4]) },
+                smile(1.0, [0.24, -0.15, 0.2])
+            ],
+        };
+
+        // Past the strikes fitted at either bracketing expiry
+        assert!(local_vol(&surface, 75.0, 0.5).is_nan());
+        assert!(local_vol(&surface, 75.0, 2.0).is_finite());
+        assert!(local_vol(&surface, 170.0, 2.0).is_nan());
+        assert!(local_vol(&surface, 40.0, 0.1).is_nan());
+
+        // Floored at MIN_VOL
+        let floored = VolSurface { spot: 100.0, smiles: vec![smile(1.0, [0.1, 0.5, 0.0])] };
+        assert!(local_vol(&floored, 70.0, 1.0).is_nan());
+        assert!(local_vol(&floored, 100.0, 1.0).is_finite());
+    }
+
+    #[test]
+    fn arbitrage_is_nan() {
+        // Total variance falls from the first expiry to the second
+        let calendar = VolSurface { spot: 100.0, smiles: vec![smile(0.25, [0.4, 0.0, 0.0]), smile(0.5, [0.2, 0.0, 0.0])] };
+        assert!(local_vol(&calendar, 100.0, 0.3).is_nan());
+
+        // A smile concave enough at the money to give a negative density
+        let butterfly = VolSurface { spot: 100.0, smiles: vec![smile(1.0, [0.2, 0.0, -3.0])] };
+        assert!(local_vol(&butterfly, 100.0, 1.0).is_nan());
+    }
+
+    #[test]
+    fn spy_local_vol() {
+        let chain = read_hist::get_spy_chain();
+        let surface = surface::fit_surface(&chain, 0.05, 30, 0.0001);
+        let strikes: Vec<f32> = (0..21).map(|i| chain.spot * (0.9 + 0.01 * (i as f32))).collect();
+        let years: Vec<f32> = surface.smiles.iter().map(|smile| smile.years_to_expiry).collect();
+
+        let grid = local_vol_grid(&surface, &strikes, &years);
+        let defined = grid.iter().flatten().filter(|vol| vol.is_finite()).count();
+
+        assert_eq!(grid.len(), years.len());
+        assert!(defined * 2 > strikes.len() * years.len(), "Defined: {}", defined);
+
+        for vol in grid.iter().flatten().filter(|vol| vol.is_finite()) {
+            assert!(*vol > 0.0 && *vol < 3.0, "Vol: {}", vol);
+        }
+    }
+}
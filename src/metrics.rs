@@ -0,0 +1,277 @@
+use crate::chain::OptionChain;
+use crate::consts::OptionDir;
+use crate::smile::{ self, Smile };
+use crate::vol32x8;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    Summary numbers of each expiry's smile, the way a trading desk quotes them:
+      - ATM vol: smile volatility at the forward
+      - 25D / 10D risk reversal: call vol minus put vol at the same absolute delta
+      - 25D / 10D butterfly: average of the call and put vol minus ATM vol
+      - Skew slope and curvature: first and second derivative of vol in log-moneyness at
+        the forward, from a quadratic fit to the points within SLOPE_STD_DEVS of the forward
+
+    Deltas are forward (undiscounted) Black-Scholes deltas from vol32x8::delta, and the vol at
+    a given delta is found by interpolating the smile between the two strikes bracketing it.
+*/
+
+const SLOPE_STD_DEVS: f32 = 1.0;
+
+/// One row of the metrics table. Missing values (e.g. a delta beyond the quoted strikes) are NaN.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SmileMetrics {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    pub atm_vol: f32,
+    pub risk_reversal_25: f32,
+    pub butterfly_25: f32,
+    pub risk_reversal_10: f32,
+    pub butterfly_10: f32,
+    pub skew_slope: f32,
+    pub curvature: f32,
+}
+
+/*
+    Weighted least squares fit of y = c0 + c1 x + c2 x^2. Solves the 3x3 normal equations
+    with Cramer's rule. Returns None with fewer than 3 points or a singular system.
+*/
+pub(crate) fn fit_quadratic(xs: &[f32], ys: &[f32], weights: &[f32]) -> Option<[f32; 3]> {
+    if xs.len() < 3 {
+        return None;
+    }
+
+    // Power sums of x up to x^4 and moments of y up to x^2 y
+    let mut s = [0.0f64; 5];
+    let mut t = [0.0f64; 3];
+
+    for i in 0..xs.len() {
+        let (x, y, w) = (xs[i] as f64, ys[i] as f64, weights[i] as f64);
+        let mut p = w;
+
+        for k in 0..5 {
+            s[k] += p;
+
+            if k < 3 {
+                t[k] += p * y;
+            }
+
+            p *= x;
+        }
+    }
+
+    let det3 = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1]) -
+            m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0]) +
+            m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+
+    let a = [
+        [s[0], s[1], s[2]],
+        [s[1], s[2], s[3]],
+        [s[2], s[3], s[4]],
+    ];
+    let det = det3(a);
+
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    let mut coefficients = [0.0f32; 3];
+
+    for (col, coefficient) in coefficients.iter_mut().enumerate() {
+        let mut m = a;
+
+        for row in 0..3 {
+            m[row][col] = t[row];
+        }
+
+        *coefficient = (det3(m) / det) as f32;
+    }
+
+    Some(coefficients)
+}
+
+// Vol at the first point where the (decreasing) call delta crosses target
+fn vol_at_call_delta(call_deltas: &[f32], vols: &[f32], target: f32) -> f32 {
+    for i in 1..call_deltas.len() {
+        let (d0, d1) = (call_deltas[i - 1], call_deltas[i]);
+
+        if (d0 >= target && d1 <= target) || (d0 <= target && d1 >= target) {
+            if d0 == d1 {
+                return vols[i - 1];
+            }
+
+            let weight = (target - d0) / (d1 - d0);
+            return vols[i - 1] + weight * (vols[i] - vols[i - 1]);
+        }
+    }
+
+    f32::NAN
+}
+
+pub fn smile_metrics(smile: &Smile) -> SmileMetrics {
+    let n = smile.strikes.len();
+    let atm_vol = smile.vol_at(smile.forward);
+
+    // Pricing off the forward with zero rates gives forward deltas
+    let call_deltas = vol32x8::delta(
+        OptionDir::CALL,
+        &vec![smile.forward; n],
+        &smile.strikes,
+        &smile.vols,
+        &vec![0.0; n],
+        &vec![0.0; n],
+        &vec![smile.years_to_expiry; n]
+    );
+
+    // A put with delta -x has the same strike as a call with forward delta 1 - x
+    let wings = |delta: f32| {
+        let call_vol = vol_at_call_delta(&call_deltas, &smile.vols, delta);
+        let put_vol = vol_at_call_delta(&call_deltas, &smile.vols, 1.0 - delta);
+        (call_vol - put_vol, (call_vol + put_vol) / 2.0 - atm_vol)
+    };
+    let (risk_reversal_25, butterfly_25) = wings(0.25);
+    let (risk_reversal_10, butterfly_10) = wings(0.1);
+
+    let window = SLOPE_STD_DEVS * atm_vol * smile.years_to_expiry.sqrt();
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+
+    for (x, vol) in smile.log_moneyness().into_iter().zip(smile.vols.iter()) {
+        if x.abs() <= window {
+            xs.push(x);
+            ys.push(*vol);
+        }
+    }
+
+    let (skew_slope, curvature) = match fit_quadratic(&xs, &ys, &vec![1.0; xs.len()]) {
+        Some([_, c1, c2]) => (c1, 2.0 * c2),
+        None => (f32::NAN, f32::NAN),
+    };
+
+    SmileMetrics {
+        years_to_expiry: smile.years_to_expiry,
+        forward: smile.forward,
+        atm_vol,
+        risk_reversal_25,
+        butterfly_25,
+        risk_reversal_10,
+        butterfly_10,
+        skew_slope,
+        curvature,
+    }
+}
+
+pub fn metrics_table(smiles: &[Smile]) -> Vec<SmileMetrics> {
+    smiles.iter().map(smile_metrics).collect()
+}
+
+/*
+    Frontend entry point. Builds the composite OTM smiles from the mid-price arrays and
+    returns the metrics table as a JSON array with one object per expiry.
+*/
+#[wasm_bindgen]
+pub fn smile_metrics_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32
+) -> String {
+    if
+        !(
+            call_price.len() == put_price.len() &&
+            put_price.len() == strike.len() &&
+            strike.len() == years_to_expiry.len()
+        )
+    {
+        return String::from("[]");
+    }
+
+    let chain = OptionChain::from_mids(spot, call_price, put_price, strike, years_to_expiry);
+    let smiles = smile::otm_smiles(&chain, risk_free_rate, 0.01, 20, 0.0001);
+
+    serde_json::to_string(&metrics_table(&smiles)).unwrap_or_else(|_| String::from("[]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_hist;
+
+    fn smile_from(vol: impl Fn(f32) -> f32) -> Smile {
+        let forward = 100.0;
+        let strikes: Vec<f32> = (50..=200).map(|k| k as f32).collect();
+        let vols = strikes
+            .iter()
+            .map(|k| vol((k / forward).ln()))
+            .collect();
+
+        Smile {
+            years_to_expiry: 0.5,
+            forward,
+            strikes,
+            vols,
+        }
+    }
+
+    #[test]
+    fn quadratic_fit_exact() {
+        let xs = [-0.2, -0.1, 0.0, 0.1, 0.2];
+        let ys: Vec<f32> = xs
+            .iter()
+            .map(|x| 0.2 - 0.5 * x + 0.8 * x * x)
+            .collect();
+
+        let [c0, c1, c2] = fit_quadratic(&xs, &ys, &[1.0; 5]).unwrap();
+
+        assert!((c0 - 0.2).abs() < 1e-4);
+        assert!((c1 + 0.5).abs() < 1e-4);
+        assert!((c2 - 0.8).abs() < 1e-3);
+        assert!(fit_quadratic(&xs[..2], &ys[..2], &[1.0; 2]).is_none());
+    }
+
+    #[test]
+    fn flat_smile_has_no_skew() {
+        let metrics = smile_metrics(&smile_from(|_| 0.2));
+
+        assert!((metrics.atm_vol - 0.2).abs() < 1e-6);
+        assert!(metrics.risk_reversal_25.abs() < 1e-6);
+        assert!(metrics.butterfly_25.abs() < 1e-6);
+        assert!(metrics.risk_reversal_10.abs() < 1e-6);
+        assert!(metrics.skew_slope.abs() < 1e-4);
+        assert!(metrics.curvature.abs() < 1e-2);
+    }
+
+    #[test]
+    fn skewed_smile() {
+        let metrics = smile_metrics(&smile_from(|x| 0.2 - 0.3 * x + 0.5 * x * x));
+
+        assert!((metrics.atm_vol - 0.2).abs() < 1e-4);
+        assert!(metrics.risk_reversal_25 < 0.0);
+        assert!(metrics.risk_reversal_10 < metrics.risk_reversal_25);
+        assert!(metrics.butterfly_25 > 0.0);
+        assert!((metrics.skew_slope + 0.3).abs() < 0.01, "Slope: {}", metrics.skew_slope);
+        assert!((metrics.curvature - 1.0).abs() < 0.1, "Curvature: {}", metrics.curvature);
+    }
+
+    #[test]
+    fn spy_metrics() {
+        let chain = read_hist::get_spy_chain();
+        let smiles = smile::otm_smiles(&chain, 0.05, 0.01, 30, 0.0001);
+        let table = metrics_table(&smiles);
+
+        assert_eq!(table.len(), smiles.len());
+
+        // Equity index smiles are put skewed
+        let month = table
+            .iter()
+            .find(|row| row.years_to_expiry > 0.08)
+            .unwrap();
+        assert!(month.risk_reversal_25 < 0.0, "RR: {}", month.risk_reversal_25);
+        assert!(month.skew_slope < 0.0, "Slope: {}", month.skew_slope);
+    }
+}
@@ -0,0 +1,751 @@
+use crate::consts::{ ExoticKind, MonteCarloModel, OptionDir };
+use crate::heston::HestonParams;
+use crate::localvol;
+use crate::simd::{ self, Kernel, Simd, MAX_LANES };
+use crate::surface::{ self, VolSurface };
+use crate::vol32x8;
+use rayon::prelude::*;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    Monte Carlo pricer for European, barrier and Asian options, to check the fitted surface
+    against a model and to get quick indications for exotics the surface cannot price directly.
+
+    The log spot is stepped with an Euler scheme over num_steps equal steps to expiry under one of
+      - GBM: a constant vol
+      - local vol: the Dupire vol of the fitted surface (see localvol), precomputed on a grid of
+        log spot x time step and interpolated linearly in log spot. Where it is not defined
+        (outside the fitted strikes or arbitrage in the surface) the implied vol of the surface
+        is used instead, and the number of such nodes is reported with the price.
+      - Heston: dv = kappa (theta - v) dt + xi sqrt(v) dW, with dW correlated by rho to the
+        spot's Brownian motion, and full truncation of negative variances
+    with a drift of risk_free_rate - dividend_yield. Repricing vanillas under local vol only
+    recovers the surface when the rates match the forwards of the fitted smiles.
+
+    Paths are run V::LANES at a time by the SIMD kernel, and blocks of paths across threads.
+    Every path draws its normals (Box-Muller) from its own SplitMix64 stream, seeded by the seed
+    and the path's index, so the price only depends on the seed and the number of paths and
+    steps, not on the backend or the number of threads.
+
+    Barriers are monitored at the end of every step and at the start, and the Asian option pays
+    off on the arithmetic average of the spots at the end of every step.
+*/
+
+// Paths are indexed by f32 inputs to the kernel, exact up to 2^24
+const MAX_PATHS: usize = 1 << 24;
+
+// Local vol grid: LOCAL_VOL_NODES log spots within LOCAL_VOL_STD_DEVS ATM standard deviations
+const LOCAL_VOL_NODES: usize = 101;
+const LOCAL_VOL_STD_DEVS: f32 = 6.0;
+
+// Implied vol of the Monte Carlo prices, as in fit_surface
+const VOL_ITERATIONS: i32 = 20;
+const VOL_THRESHOLD: f32 = 0.0001;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dynamics<'a> {
+    Gbm { volatility: f32 },
+    LocalVol(&'a VolSurface),
+    Heston(HestonParams),
+}
+
+/// Option priced by monte_carlo_price. barrier is only used by the barrier kinds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Exotic {
+    pub kind: ExoticKind,
+    pub option_dir: OptionDir,
+    pub strike: f32,
+    pub barrier: f32,
+    pub years_to_expiry: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonteCarloConfig {
+    pub num_paths: usize,
+    pub num_steps: usize,
+    pub seed: u64,
+}
+
+/// Mean discounted payoff and its standard error, NaN for invalid inputs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MonteCarloPrice {
+    pub price: f32,
+    pub std_error: f32,
+    pub num_paths: usize,
+    // Local vol grid nodes priced at the implied vol instead, 0 under the other dynamics
+    pub substituted_nodes: usize,
+}
+
+impl MonteCarloPrice {
+    fn invalid() -> MonteCarloPrice {
+        MonteCarloPrice { price: f32::NAN, std_error: f32::NAN, num_paths: 0, substituted_nodes: 0 }
+    }
+}
+
+/// Monte Carlo price of a vanilla next to its price on the surface
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VanillaCheck {
+    pub option_type: &'static str,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub surface_vol: f32,
+    pub surface_price: f32,
+    pub monte_carlo_price: f32,
+    pub std_error: f32,
+    pub monte_carlo_vol: f32,
+    pub substituted_nodes: usize,
+}
+
+// Local vols at the start of every step, on nodes evenly spaced in ln(S / S0)
+struct LocalVolGrid {
+    min_log_spot: f32,
+    node_spacing: f32,
+    vols: Vec<f32>,
+    // Nodes where the local vol is not defined and the implied vol is used
+    substituted_nodes: usize,
+}
+
+impl LocalVolGrid {
+    fn new(surface: &VolSurface, dt: f32, num_steps: usize) -> LocalVolGrid {
+        let years_to_expiry = dt * (num_steps as f32);
+        let forward = surface.forward(years_to_expiry);
+        let width = LOCAL_VOL_STD_DEVS * surface.vol(forward, years_to_expiry) * years_to_expiry.sqrt();
+        let node_spacing = (2.0 * width) / ((LOCAL_VOL_NODES - 1) as f32);
+
+        let strike = |node: usize| surface.spot * (-width + node_spacing * (node as f32)).exp();
+        // Midpoint of the step, as the local vol is not defined today
+        let years = |step: usize| dt * ((step as f32) + 0.5);
+
+        let mut vols: Vec<f32> = (0..num_steps * LOCAL_VOL_NODES)
+            .into_par_iter()
+            .map(|idx| localvol::local_vol(surface, strike(idx % LOCAL_VOL_NODES), years(idx / LOCAL_VOL_NODES)))
+            .collect();
+        let mut substituted_nodes = 0;
+
+        for (idx, vol) in vols.iter_mut().enumerate() {
+            if !vol.is_finite() {
+                *vol = surface.vol(strike(idx % LOCAL_VOL_NODES), years(idx / LOCAL_VOL_NODES));
+                substituted_nodes += 1;
+            }
+        }
+
+        LocalVolGrid { min_log_spot: -width, node_spacing, vols, substituted_nodes }
+    }
+
+    // Held flat past the first and last nodes
+    fn vol(&self, step: usize, log_spot: f32) -> f32 {
+        let row = &self.vols[step * LOCAL_VOL_NODES..(step + 1) * LOCAL_VOL_NODES];
+        let position = ((log_spot - self.min_log_spot) / self.node_spacing).clamp(0.0, (LOCAL_VOL_NODES - 1) as f32);
+        let idx = std::cmp::min(position as usize, LOCAL_VOL_NODES - 2);
+        let weight = position - (idx as f32);
+
+        row[idx] + weight * (row[idx + 1] - row[idx])
+    }
+}
+
+enum PathDynamics {
+    Gbm { volatility: f32 },
+    LocalVol(LocalVolGrid),
+    Heston(HestonParams),
+}
+
+// SplitMix64 (Steele, Lea and Flood, 2014)
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn path_state(seed: u64, path: u64) -> u64 {
+    let mut state = seed;
+    split_mix(&mut state) ^ path.wrapping_mul(0xd1b5_4a32_d192_ed03)
+}
+
+// Uniform in (0, 1) from the top 24 bits
+fn uniform(state: &mut u64) -> f32 {
+    (((split_mix(state) >> 40) as f32) + 0.5) * (1.0 / 16_777_216.0)
+}
+
+// Two independent standard normals per lane by Box-Muller
+#[inline(always)]
+fn normals<V: Simd>(states: &mut [u64; MAX_LANES]) -> (V, V) {
+    let mut u1 = [0.0; MAX_LANES];
+    let mut u2 = [0.0; MAX_LANES];
+
+    for lane in 0..V::LANES {
+        u1[lane] = uniform(&mut states[lane]);
+        u2[lane] = uniform(&mut states[lane]);
+    }
+
+    let radius = (V::splat(-2.0) * V::load(&u1).ln()).sqrt();
+    let angle = V::splat(2.0 * std::f32::consts::PI) * V::load(&u2);
+
+    // sin(angle) = cos(angle - pi / 2)
+    (radius * angle.cos(), radius * (angle - V::splat(std::f32::consts::FRAC_PI_2)).cos())
+}
+
+// [path index] -> [discounted payoff]
+struct PathKernel {
+    exotic: Exotic,
+    dynamics: PathDynamics,
+    spot: f32,
+    drift: f32,
+    dt: f32,
+    num_steps: usize,
+    discount: f32,
+    seed: u64,
+}
+
+impl PathKernel {
+    #[inline(always)]
+    fn breached<V: Simd>(&self, spot: V) -> V {
+        let barrier = V::splat(self.exotic.barrier);
+
+        match self.exotic.kind {
+            ExoticKind::UpAndOut | ExoticKind::UpAndIn => spot.cmp_ge(barrier),
+            ExoticKind::DownAndOut | ExoticKind::DownAndIn => spot.cmp_le(barrier),
+            _ => V::splat(0.0),
+        }
+    }
+}
+
+impl Kernel<1, 1> for PathKernel {
+    const PADDING: [f32; 1] = [0.0];
+
+    #[inline(always)]
+    fn apply<V: Simd>(&self, _active: V, [path]: [V; 1], outputs: &mut [V; 1]) {
+        let mut paths = [0.0; MAX_LANES];
+        path.store(&mut paths);
+
+        let mut states = [0u64; MAX_LANES];
+        for lane in 0..V::LANES {
+            states[lane] = path_state(self.seed, paths[lane] as u64);
+        }
+
+        let dt = V::splat(self.dt);
+        let sqrt_dt = V::splat(self.dt.sqrt());
+        let drift = V::splat(self.drift);
+        let half = V::splat(0.5);
+        let zero = V::splat(0.0);
+
+        let mut log_spot = zero;
+        let mut spot = V::splat(self.spot);
+        let mut spot_sum = zero;
+        let mut breached = self.breached(spot);
+        let mut variance = match &self.dynamics {
+            PathDynamics::Heston(params) => V::splat(params.v0),
+            _ => zero,
+        };
+        let mut spare = None;
+
+        for step in 0..self.num_steps {
+            match &self.dynamics {
+                PathDynamics::Heston(params) => {
+                    let (z1, z2) = normals::<V>(&mut states);
+                    let truncated = variance.max(zero);
+                    let vol = truncated.sqrt();
+                    let z_variance =
+                        V::splat(params.rho) * z1 + V::splat((1.0 - params.rho * params.rho).max(0.0).sqrt()) * z2;
+
+                    log_spot = log_spot + (drift - half * truncated) * dt + vol * sqrt_dt * z1;
+                    variance =
+                        variance +
+                        V::splat(params.kappa) * (V::splat(params.theta) - truncated) * dt +
+                        V::splat(params.xi) * vol * sqrt_dt * z_variance;
+                }
+                dynamics => {
+                    // One normal per step, keeping the second of each pair for the next one
+                    let z = match spare.take() {
+                        Some(z) => z,
+                        None => {
+                            let (z1, z2) = normals::<V>(&mut states);
+                            spare = Some(z2);
+                            z1
+                        }
+                    };
+
+                    let vol = match dynamics {
+                        PathDynamics::LocalVol(grid) => {
+                            let mut log_spots = [0.0; MAX_LANES];
+                            let mut vols = [0.0; MAX_LANES];
+                            log_spot.store(&mut log_spots);
+
+                            for lane in 0..V::LANES {
+                                vols[lane] = grid.vol(step, log_spots[lane]);
+                            }
+
+                            V::load(&vols)
+                        }
+                        PathDynamics::Gbm { volatility } => V::splat(*volatility),
+                        PathDynamics::Heston(_) => zero,
+                    };
+
+                    log_spot = log_spot + (drift - half * vol * vol) * dt + vol * sqrt_dt * z;
+                }
+            }
+
+            spot = V::splat(self.spot) * log_spot.exp();
+            spot_sum = spot_sum + spot;
+            breached = breached.or(self.breached(spot));
+        }
+
+        let underlying = match self.exotic.kind {
+            ExoticKind::Asian => spot_sum / V::splat(self.num_steps as f32),
+            _ => spot,
+        };
+        let strike = V::splat(self.exotic.strike);
+        let vanilla = match self.exotic.option_dir {
+            OptionDir::CALL => (underlying - strike).max(zero),
+            OptionDir::PUT => (strike - underlying).max(zero),
+        };
+        let payoff = match self.exotic.kind {
+            ExoticKind::UpAndOut | ExoticKind::DownAndOut => breached.blend(zero, vanilla),
+            ExoticKind::UpAndIn | ExoticKind::DownAndIn => breached.blend(vanilla, zero),
+            _ => vanilla,
+        };
+
+        outputs[0] = V::splat(self.discount) * payoff;
+    }
+}
+
+pub fn monte_carlo_price(
+    exotic: &Exotic,
+    dynamics: &Dynamics,
+    spot: f32,
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    config: &MonteCarloConfig
+) -> MonteCarloPrice {
+    let is_barrier = !matches!(exotic.kind, ExoticKind::European | ExoticKind::Asian);
+    let valid_dynamics = match dynamics {
+        Dynamics::Gbm { volatility } => *volatility >= 0.0,
+        Dynamics::LocalVol(surface) => !surface.smiles.is_empty(),
+        Dynamics::Heston(params) => params.is_valid(),
+    };
+
+    if
+        !(
+            spot > 0.0 &&
+            exotic.strike >= 0.0 &&
+            exotic.years_to_expiry > 0.0 &&
+            (!is_barrier || exotic.barrier > 0.0) &&
+            risk_free_rate.is_finite() &&
+            dividend_yield.is_finite() &&
+            1 < config.num_paths &&
+            config.num_paths <= MAX_PATHS &&
+            0 < config.num_steps &&
+            valid_dynamics
+        )
+    {
+        return MonteCarloPrice::invalid();
+    }
+
+    let dt = exotic.years_to_expiry / (config.num_steps as f32);
+    let kernel = PathKernel {
+        exotic: *exotic,
+        dynamics: match dynamics {
+            Dynamics::Gbm { volatility } => PathDynamics::Gbm { volatility: *volatility },
+            Dynamics::LocalVol(surface) => PathDynamics::LocalVol(LocalVolGrid::new(surface, dt, config.num_steps)),
+            Dynamics::Heston(params) => PathDynamics::Heston(*params),
+        },
+        spot,
+        drift: risk_free_rate - dividend_yield,
+        dt,
+        num_steps: config.num_steps,
+        discount: (-risk_free_rate * exotic.years_to_expiry).exp(),
+        seed: config.seed,
+    };
+
+    let substituted_nodes = match &kernel.dynamics {
+        PathDynamics::LocalVol(grid) => grid.substituted_nodes,
+        _ => 0,
+    };
+
+    let paths: Vec<f32> = (0..config.num_paths).map(|path| path as f32).collect();
+    let [payoffs] = simd::map(&kernel, [&paths]);
+
+    // Summed in order in f64 so the result does not depend on how the paths were split
+    let num_paths = payoffs.len() as f64;
+    let mean = payoffs.iter().map(|payoff| *payoff as f64).sum::<f64>() / num_paths;
+    let variance =
+        payoffs
+            .iter()
+            .map(|payoff| ((*payoff as f64) - mean).powi(2))
+            .sum::<f64>() / (num_paths - 1.0);
+
+    MonteCarloPrice {
+        price: mean as f32,
+        std_error: (variance / num_paths).sqrt() as f32,
+        num_paths: payoffs.len(),
+        substituted_nodes,
+    }
+}
+
+/*
+    Reprices the out of the money vanilla at each (strike, years_to_expiry) pair under the
+    dynamics and compares it to its price at the surface vol. Under local vol the two should
+    agree within a few standard errors wherever the surface is free of arbitrage, and only
+    approximately when the local vol had to be substituted at some nodes.
+*/
+pub fn reprice_vanillas(
+    surface: &VolSurface,
+    dynamics: &Dynamics,
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    strikes: &[f32],
+    years_to_expiry: &[f32],
+    config: &MonteCarloConfig
+) -> Vec<VanillaCheck> {
+    if strikes.len() != years_to_expiry.len() {
+        return Vec::new();
+    }
+
+    strikes
+        .iter()
+        .zip(years_to_expiry)
+        .map(|(strike, years)| {
+            let option_dir = if *strike >= surface.forward(*years) { OptionDir::CALL } else { OptionDir::PUT };
+            let exotic = Exotic {
+                kind: ExoticKind::European,
+                option_dir,
+                strike: *strike,
+                barrier: 0.0,
+                years_to_expiry: *years,
+            };
+            let monte_carlo = monte_carlo_price(&exotic, dynamics, surface.spot, risk_free_rate, dividend_yield, config);
+            let surface_vol = surface.vol(*strike, *years);
+
+            let (spot, strike_, rate, dividend, years_) = ([surface.spot], [*strike], [risk_free_rate], [dividend_yield], [*years]);
+            let surface_price = vol32x8::price(option_dir, &spot, &strike_, &[surface_vol], &rate, &dividend, &years_);
+            let monte_carlo_vol = vol32x8::implied_vol(
+                option_dir,
+                &[monte_carlo.price],
+                &spot,
+                &strike_,
+                &rate,
+                &dividend,
+                &years_,
+                VOL_ITERATIONS,
+                VOL_THRESHOLD
+            );
+
+            VanillaCheck {
+                option_type: match option_dir {
+                    OptionDir::CALL => "call",
+                    OptionDir::PUT => "put",
+                },
+                strike: *strike,
+                years_to_expiry: *years,
+                surface_vol,
+                surface_price: surface_price[0],
+                monte_carlo_price: monte_carlo.price,
+                std_error: monte_carlo.std_error,
+                monte_carlo_vol: monte_carlo_vol[0],
+                substituted_nodes: monte_carlo.substituted_nodes,
+            }
+        })
+        .collect()
+}
+
+/*
+    Frontend entry point for exotic indications. Fits the surface to the mid-price arrays with
+    surface::fit_mids and prices the exotic under model: GBM at the surface vol of the strike,
+    the surface's local vol, or Heston with the given parameters. Returns a MonteCarloPrice as
+    JSON, with null price and std_error for invalid inputs.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn monte_carlo_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    kind: ExoticKind,
+    option_dir: OptionDir,
+    exotic_strike: f32,
+    barrier: f32,
+    exotic_years_to_expiry: f32,
+    model: MonteCarloModel,
+    v0: f32,
+    kappa: f32,
+    theta: f32,
+    xi: f32,
+    rho: f32,
+    num_paths: usize,
+    num_steps: usize,
+    seed: u32
+) -> String {
+    let surface = surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate);
+    let result = if let Some(surface) = surface {
+        let exotic = Exotic { kind, option_dir, strike: exotic_strike, barrier, years_to_expiry: exotic_years_to_expiry };
+        let dynamics = match model {
+            MonteCarloModel::Gbm => Dynamics::Gbm { volatility: surface.vol(exotic_strike, exotic_years_to_expiry) },
+            MonteCarloModel::LocalVol => Dynamics::LocalVol(&surface),
+            MonteCarloModel::Heston => Dynamics::Heston(HestonParams { v0, kappa, theta, xi, rho }),
+        };
+        let config = MonteCarloConfig { num_paths, num_steps, seed: seed as u64 };
+
+        monte_carlo_price(&exotic, &dynamics, spot, risk_free_rate, dividend_yield, &config)
+    } else {
+        MonteCarloPrice::invalid()
+    };
+
+    serde_json::to_string(&result).unwrap_or_else(|_| String::from("{}"))
+}
+
+/*
+    Frontend entry point for checking the fitted surface. Reprices the vanillas at each
+    (check_strikes, check_years_to_expiry) pair under its local vol and returns the
+    VanillaCheck of each as a JSON array.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn local_vol_check_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    check_strikes: &[f32],
+    check_years_to_expiry: &[f32],
+    num_paths: usize,
+    num_steps: usize,
+    seed: u32
+) -> String {
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return String::from("[]");
+        }
+    };
+    let config = MonteCarloConfig { num_paths, num_steps, seed: seed as u64 };
+    let checks = reprice_vanillas(
+        &surface,
+        &Dynamics::LocalVol(&surface),
+        risk_free_rate,
+        dividend_yield,
+        check_strikes,
+        check_years_to_expiry,
+        &config
+    );
+
+    serde_json::to_string(&checks).unwrap_or_else(|_| String::from("[]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::fixtures;
+
+    const CONFIG: MonteCarloConfig = MonteCarloConfig { num_paths: 20_000, num_steps: 25, seed: 7 };
+
+    fn european(option_dir: OptionDir, strike: f32, years_to_expiry: f32) -> Exotic {
+        Exotic { kind: ExoticKind::European, option_dir, strike, barrier: 0.0, years_to_expiry }
+    }
+
+    // Within 4 standard errors
+    fn close(got: MonteCarloPrice, expected: f32) {
+        assert!(
+            (got.price - expected).abs() < 4.0 * got.std_error,
+            "Got: {} +- {}, Expected: {}",
+            got.price,
+            got.std_error,
+            expected
+        );
+    }
+
+    // Smile with vol c0 + c1 x at every expiry, with the forward at 3% rates from a spot of 100
+    fn skew_surface(c0: f32, c1: f32) -> VolSurface {
+        let mut surface = fixtures::skew_surface(c0, c1);
+
+        for smile in &mut surface.smiles {
+            smile.forward = 100.0 * (0.03 * smile.years_to_expiry).exp();
+        }
+
+        surface
+    }
+
+    #[test]
+    fn gbm_matches_bs() {
+        let dynamics = Dynamics::Gbm { volatility: 0.25 };
+
+        for (option_dir, strike) in [(OptionDir::CALL, 90.0), (OptionDir::CALL, 110.0), (OptionDir::PUT, 95.0)] {
+            let result = monte_carlo_price(&european(option_dir, strike, 0.5), &dynamics, 100.0, 0.03, 0.01, &CONFIG);
+            let expected = bs::price(option_dir, 100.0, strike, 0.25, 0.03, 0.01, 0.5);
+
+            close(result, expected);
+            assert!(result.std_error < 0.02 * expected, "Std error: {}", result.std_error);
+            assert_eq!(result.num_paths, CONFIG.num_paths);
+        }
+    }
+
+    #[test]
+    fn seeded_and_reproducible() {
+        let exotic = european(OptionDir::CALL, 100.0, 1.0);
+        let dynamics = Dynamics::Gbm { volatility: 0.2 };
+        let price = |seed| monte_carlo_price(&exotic, &dynamics, 100.0, 0.03, 0.0, &MonteCarloConfig { seed, ..CONFIG });
+
+        assert_eq!(price(7), price(7));
+        assert_ne!(price(7).price, price(8).price);
+
+        // Bit for bit the same on the scalar backend
+        let kernel = PathKernel {
+            exotic,
+            dynamics: PathDynamics::Gbm { volatility: 0.2 },
+            spot: 100.0,
+            drift: 0.03,
+            dt: 1.0 / 25.0,
+            num_steps: 25,
+            discount: (-0.03f32).exp(),
+            seed: 7,
+        };
+        let paths: Vec<f32> = (0..1000).map(|path| path as f32).collect();
+        let mut scalar = vec![0.0; paths.len()];
+        let mut detected = vec![0.0; paths.len()];
+        simd::map_into_with(simd::Backend::Scalar, &kernel, [&paths], [&mut scalar]);
+        simd::map_into_with(simd::detected(), &kernel, [&paths], [&mut detected]);
+
+        assert_eq!(scalar, detected);
+    }
+
+    #[test]
+    fn barrier_in_and_out_add_up() {
+        let dynamics = Dynamics::Gbm { volatility: 0.2 };
+        let vanilla = monte_carlo_price(&european(OptionDir::CALL, 100.0, 1.0), &dynamics, 100.0, 0.03, 0.0, &CONFIG);
+
+        for (out_kind, in_kind, barrier) in [
+            (ExoticKind::UpAndOut, ExoticKind::UpAndIn, 120.0),
+            (ExoticKind::DownAndOut, ExoticKind::DownAndIn, 90.0),
+        ] {
+            let exotic = |kind| Exotic { kind, barrier, ..european(OptionDir::CALL, 100.0, 1.0) };
+            let knock_out = monte_carlo_price(&exotic(out_kind), &dynamics, 100.0, 0.03, 0.0, &CONFIG);
+            let knock_in = monte_carlo_price(&exotic(in_kind), &dynamics, 100.0, 0.03, 0.0, &CONFIG);
+
+            assert!((knock_out.price + knock_in.price - vanilla.price).abs() < 1e-3);
+            assert!(knock_out.price > 0.0 && knock_in.price > 0.0);
+        }
+
+        // Knocked out from the start
+        let breached = Exotic { kind: ExoticKind::DownAndOut, barrier: 105.0, ..european(OptionDir::CALL, 100.0, 1.0) };
+        assert_eq!(monte_carlo_price(&breached, &dynamics, 100.0, 0.03, 0.0, &CONFIG).price, 0.0);
+    }
+
+    #[test]
+    fn asian_is_cheaper() {
+        let dynamics = Dynamics::Gbm { volatility: 0.3 };
+        let vanilla = monte_carlo_price(&european(OptionDir::CALL, 100.0, 1.0), &dynamics, 100.0, 0.03, 0.0, &CONFIG);
+        let asian = Exotic { kind: ExoticKind::Asian, ..european(OptionDir::CALL, 100.0, 1.0) };
+        let asian = monte_carlo_price(&asian, &dynamics, 100.0, 0.03, 0.0, &CONFIG);
+
+        // Roughly a vanilla at vol / sqrt(3)
+        assert!(asian.price < 0.7 * vanilla.price, "Asian: {} Vanilla: {}", asian.price, vanilla.price);
+        assert!(asian.price > 0.4 * vanilla.price, "Asian: {} Vanilla: {}", asian.price, vanilla.price);
+    }
+
+    #[test]
+    fn heston_without_vol_of_vol_is_bs() {
+        let params = HestonParams { v0: 0.04, kappa: 1.5, theta: 0.04, xi: 0.0, rho: -0.7 };
+        let result = monte_carlo_price(&european(OptionDir::CALL, 105.0, 0.5), &Dynamics::Heston(params), 100.0, 0.03, 0.0, &CONFIG);
+        close(result, bs::price(OptionDir::CALL, 100.0, 105.0, 0.2, 0.03, 0.0, 0.5));
+
+        // Negative correlation skews the distribution, richening downside puts over ATM vol
+        let skewed = HestonParams { xi: 0.6, ..params };
+        let put = monte_carlo_price(&european(OptionDir::PUT, 85.0, 0.5), &Dynamics::Heston(skewed), 100.0, 0.03, 0.0, &CONFIG);
+        assert!(put.price > bs::price(OptionDir::PUT, 100.0, 85.0, 0.2, 0.03, 0.0, 0.5));
+    }
+
+    #[test]
+    fn local_vol_reprices_the_surface() {
+        for surface in [skew_surface(0.2, 0.0), skew_surface(0.22, -0.2)] {
+            let strikes = [85.0, 100.0, 115.0];
+            let years = [0.5, 0.5, 0.5];
+            let checks = reprice_vanillas(&surface, &Dynamics::LocalVol(&surface), 0.03, 0.0, &strikes, &years, &CONFIG);
+
+            assert_eq!(checks.len(), 3);
+            for check in checks {
+                assert_eq!(check.substituted_nodes, 0, "Check: {:?}", check);
+                assert!(
+                    (check.monte_carlo_price - check.surface_price).abs() < 4.0 * check.std_error + 0.01,
+                    "Check: {:?}",
+                    check
+                );
+                assert!((check.monte_carlo_vol - check.surface_vol).abs() < 0.01, "Check: {:?}", check);
+            }
+        }
+    }
+
+    #[test]
+    fn substituted_local_vols_are_counted() {
+        let exotic = european(OptionDir::CALL, 100.0, 0.5);
+        let mut surface = skew_surface(0.2, 0.0);
+
+        for smile in &mut surface.smiles {
+            (smile.min_log_moneyness, smile.max_log_moneyness) = (-0.2, 0.2);
+        }
+
+        let local_vol = monte_carlo_price(&exotic, &Dynamics::LocalVol(&surface), 100.0, 0.03, 0.0, &CONFIG);
+        let gbm = monte_carlo_price(&exotic, &Dynamics::Gbm { volatility: 0.2 }, 100.0, 0.03, 0.0, &CONFIG);
+
+        // Outside ln(S / F) = +-0.2 of the 6 standard deviation grid
+        assert!(local_vol.substituted_nodes > CONFIG.num_steps * LOCAL_VOL_NODES / 2, "Price: {:?}", local_vol);
+        assert!(local_vol.substituted_nodes < CONFIG.num_steps * LOCAL_VOL_NODES, "Price: {:?}", local_vol);
+        assert_eq!(gbm.substituted_nodes, 0);
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        let dynamics = Dynamics::Gbm { volatility: 0.2 };
+        let exotic = european(OptionDir::CALL, 100.0, 1.0);
+        let price = |exotic: &Exotic, dynamics: &Dynamics, config: &MonteCarloConfig|
+            monte_carlo_price(exotic, dynamics, 100.0, 0.03, 0.0, config).price;
+
+        assert!(price(&european(OptionDir::CALL, 100.0, 0.0), &dynamics, &CONFIG).is_nan());
+        assert!(price(&Exotic { kind: ExoticKind::UpAndOut, ..exotic }, &dynamics, &CONFIG).is_nan());
+        assert!(price(&exotic, &dynamics, &MonteCarloConfig { num_steps: 0, ..CONFIG }).is_nan());
+        assert!(price(&exotic, &dynamics, &MonteCarloConfig { num_paths: MAX_PATHS + 1, ..CONFIG }).is_nan());
+        assert!(price(&exotic, &Dynamics::LocalVol(&VolSurface::default()), &CONFIG).is_nan());
+        assert!(
+            price(&exotic, &Dynamics::Heston(HestonParams { v0: 0.04, kappa: 1.0, theta: 0.04, xi: 0.5, rho: -1.5 }), &CONFIG).is_nan()
+        );
+        assert!(reprice_vanillas(&skew_surface(0.2, 0.0), &dynamics, 0.03, 0.0, &[100.0], &[], &CONFIG).is_empty());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let [call_price, put_price, strike, years_to_expiry] = &fixtures::flat_chain(5);
+        let surface = surface::fit_mids(call_price, put_price, 100.0, strike, years_to_expiry, 0.03).unwrap();
+        let config = MonteCarloConfig { num_paths: 2_000, num_steps: 10, seed: 1 };
+
+        // Up-and-out call under the local vol of the fitted surface
+        let barrier = Exotic { kind: ExoticKind::UpAndOut, option_dir: OptionDir::CALL, strike: 100.0, barrier: 120.0, years_to_expiry: 0.5 };
+        let expected = monte_carlo_price(&barrier, &Dynamics::LocalVol(&surface), 100.0, 0.03, 0.0, &config);
+        let json = monte_carlo_json(
+            call_price, put_price, 100.0, strike, years_to_expiry, 0.03, 0.0,
+            ExoticKind::UpAndOut, OptionDir::CALL, 100.0, 120.0, 0.5,
+            MonteCarloModel::LocalVol, 0.0, 0.0, 0.0, 0.0, 0.0, 2_000, 10, 1
+        );
+        assert_eq!(json, serde_json::to_string(&expected).unwrap());
+
+        let checks = reprice_vanillas(&surface, &Dynamics::LocalVol(&surface), 0.03, 0.0, &[90.0, 110.0], &[0.5, 0.5], &config);
+        let json = local_vol_check_json(
+            call_price, put_price, 100.0, strike, years_to_expiry, 0.03, 0.0, &[90.0, 110.0], &[0.5, 0.5], 2_000, 10, 1
+        );
+        assert_eq!(json, serde_json::to_string(&checks).unwrap());
+
+        // Mismatched mid-price columns
+        assert_eq!(monte_carlo_json(&[1.0], &[], 100.0, &[], &[], 0.03, 0.0,
+            ExoticKind::European, OptionDir::CALL, 100.0, 0.0, 0.5,
+            MonteCarloModel::Gbm, 0.0, 0.0, 0.0, 0.0, 0.0, 20_000, 10, 1), r#"{"price":null,"std_error":null,"num_paths":0,"substituted_nodes":0}"#);
+        assert_eq!(local_vol_check_json(&[1.0], &[], 100.0, &[], &[], 0.03, 0.0, &[100.0], &[0.5], 2_000, 10, 1), "[]");
+    }
+}
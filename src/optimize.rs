@@ -0,0 +1,618 @@
+use rayon::prelude::*;
+use serde::Serialize;
+
+/*
+    Bounded, weighted nonlinear least squares for the calibrations.
+
+    Both solvers minimize the cost sum(w_i r_i(x)^2) of a residual function within box bounds
+    lower <= x <= upper, where empty weights weigh every residual by 1 and empty bounds leave
+    the parameters unbounded.
+      - levenberg_marquardt: Gauss-Newton steps damped by lambda diag(J'J), with the Jacobian by
+        forward differences (backwards at an upper bound) evaluated in parallel. Steps are
+        clipped to the box. Fast near a solution, for smooth residuals.
+      - nelder_mead: the downhill simplex on the cost with its vertices clipped to the box.
+        Needs no derivatives, so it also works on noisy or kinked residuals, but takes many
+        more evaluations.
+
+    Each returns the best parameters found with diagnostics: the initial and final cost, the
+    number of iterations and residual evaluations, and why it stopped. Levenberg-Marquardt has
+    converged when an iteration lowers the cost by less than tolerance relative to it or moves
+    no parameter by more than step_tolerance relative to it. Nelder-Mead has converged when the
+    costs of the simplex are within tolerance of each other relative to the best one and its
+    vertices are within step_tolerance of the best one, as equal costs alone can straddle the
+    minimum.
+*/
+
+// Levenberg-Marquardt damping
+const INITIAL_DAMPING: f64 = 1e-3;
+const MIN_DAMPING: f64 = 1e-12;
+const MAX_DAMPING: f64 = 1e10;
+const DAMPING_FACTOR: f64 = 10.0;
+
+// Relative forward difference step of the Jacobian
+const JACOBIAN_STEP: f64 = 1e-6;
+
+// Nelder-Mead reflection, expansion, contraction and shrink coefficients and initial simplex
+const REFLECTION: f64 = 1.0;
+const EXPANSION: f64 = 2.0;
+const CONTRACTION: f64 = 0.5;
+const SHRINK: f64 = 0.5;
+const SIMPLEX_STEP: f64 = 0.05;
+const SIMPLEX_ZERO_STEP: f64 = 0.00025;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Termination {
+    // The cost or the parameters stopped changing by more than the tolerances
+    Converged,
+    // No step within the damping range lowered the cost, typically at a minimum on a bound or
+    // where the residuals are not smooth
+    Stalled,
+    MaxIterations,
+    // Bounds or weights of the wrong length, negative weights, an empty box or residuals that
+    // change length between evaluations
+    InvalidInput,
+    // The cost at the initial parameters is not a number
+    NonFinite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    pub max_iterations: usize,
+    pub tolerance: f64,
+    pub step_tolerance: f64,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { max_iterations: 100, tolerance: 1e-10, step_tolerance: 1e-8 }
+    }
+}
+
+/// Best parameters found and convergence diagnostics
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Solution {
+    pub x: Vec<f64>,
+    pub cost: f64,
+    pub initial_cost: f64,
+    pub iterations: usize,
+    pub evaluations: usize,
+    pub termination: Termination,
+}
+
+impl Solution {
+    fn stopped(x: &[f64], termination: Termination) -> Solution {
+        Solution {
+            x: x.to_vec(),
+            cost: f64::NAN,
+            initial_cost: f64::NAN,
+            iterations: 0,
+            evaluations: 0,
+            termination,
+        }
+    }
+}
+
+// Bounds of every parameter, None if they do not match the parameters or leave no room
+fn box_bounds(num_params: usize, lower: &[f64], upper: &[f64]) -> Option<(Vec<f64>, Vec<f64>)> {
+    let lower = if lower.is_empty() { vec![f64::NEG_INFINITY; num_params] } else { lower.to_vec() };
+    let upper = if upper.is_empty() { vec![f64::INFINITY; num_params] } else { upper.to_vec() };
+
+    if lower.len() != num_params || upper.len() != num_params {
+        return None;
+    }
+
+    if lower.iter().zip(upper.iter()).all(|(lower, upper)| lower <= upper) { Some((lower, upper)) } else { None }
+}
+
+fn clamp(x: &[f64], lower: &[f64], upper: &[f64]) -> Vec<f64> {
+    x.iter()
+        .zip(lower.iter().zip(upper.iter()))
+        .map(|(x, (lower, upper))| x.clamp(*lower, *upper))
+        .collect()
+}
+
+// Residuals scaled by the square roots of the weights, None if their lengths differ
+fn weighted(residuals: Vec<f64>, weights: &[f64]) -> Option<Vec<f64>> {
+    if weights.is_empty() {
+        return Some(residuals);
+    }
+
+    if weights.len() != residuals.len() {
+        return None;
+    }
+
+    Some(
+        residuals
+            .iter()
+            .zip(weights.iter())
+            .map(|(residual, weight)| residual * weight.sqrt())
+            .collect()
+    )
+}
+
+fn sum_of_squares(values: &[f64]) -> f64 {
+    values
+        .iter()
+        .map(|value| value * value)
+        .sum()
+}
+
+// Solves a x = b by Gaussian elimination with partial pivoting, None if a is singular
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+
+    for col in 0..n {
+        let pivot = (col..n).max_by(|i, j| a[*i][col].abs().total_cmp(&a[*j][col].abs()))?;
+
+        if a[pivot][col] == 0.0 || !a[pivot][col].is_finite() {
+            return None;
+        }
+
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        let pivot_row = a[col].clone();
+        for row in col + 1..n {
+            let factor = a[row][col] / pivot_row[col];
+
+            for (value, pivot) in a[row][col..].iter_mut().zip(pivot_row[col..].iter()) {
+                *value -= factor * pivot;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let tail: f64 = (row + 1..n).map(|idx| a[row][idx] * x[idx]).sum();
+        x[row] = (b[row] - tail) / a[row][row];
+    }
+
+    Some(x)
+}
+
+pub fn levenberg_marquardt(
+    residuals: impl Fn(&[f64]) -> Vec<f64> + Sync,
+    initial: &[f64],
+    lower: &[f64],
+    upper: &[f64],
+    weights: &[f64],
+    options: &Options
+) -> Solution {
+    let n = initial.len();
+    let (lower, upper) = match box_bounds(n, lower, upper) {
+        Some(bounds) => bounds,
+        None => {
+            return Solution::stopped(initial, Termination::InvalidInput);
+        }
+    };
+
+    if weights.iter().any(|weight| weight.is_nan() || *weight < 0.0) {
+        return Solution::stopped(initial, Termination::InvalidInput);
+    }
+
+    let evaluate = |x: &[f64]| weighted(residuals(x), weights);
+
+    let mut x = clamp(initial, &lower, &upper);
+    let mut r = match evaluate(&x) {
+        Some(r) => r,
+        None => {
+            return Solution::stopped(&x, Termination::InvalidInput);
+        }
+    };
+    let mut cost = sum_of_squares(&r);
+    let initial_cost = cost;
+    let mut damping = INITIAL_DAMPING;
+    let mut iterations = 0;
+    let mut evaluations = 1;
+
+    let termination = loop {
+        if !cost.is_finite() {
+            break Termination::NonFinite;
+        }
+
+        if iterations == options.max_iterations {
+            break Termination::MaxIterations;
+        }
+
+        iterations += 1;
+
+        let columns: Option<Vec<Vec<f64>>> = (0..n)
+            .into_par_iter()
+            .map(|idx| {
+                let step = JACOBIAN_STEP * x[idx].abs().max(1.0);
+                let step = if x[idx] + step > upper[idx] { -step } else { step };
+                let mut bumped = x.clone();
+                bumped[idx] += step;
+
+                let bumped_r = evaluate(&bumped).filter(|bumped_r| bumped_r.len() == r.len())?;
+
+                Some(
+                    bumped_r
+                        .iter()
+                        .zip(r.iter())
+                        .map(|(bumped, base)| (bumped - base) / step)
+                        .collect()
+                )
+            })
+            .collect();
+        evaluations += n;
+
+        let columns = match columns {
+            Some(columns) => columns,
+            None => {
+                break Termination::InvalidInput;
+            }
+        };
+
+        let gradient: Vec<f64> = columns
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .zip(r.iter())
+                    .map(|(j, r)| j * r)
+                    .sum()
+            })
+            .collect();
+        let hessian: Vec<Vec<f64>> = columns
+            .iter()
+            .map(|left| {
+                columns
+                    .iter()
+                    .map(|right| {
+                        left.iter()
+                            .zip(right.iter())
+                            .map(|(l, r)| l * r)
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Parameters on a bound that the gradient pushes out of the box are held there, so the
+        // others move along the bound instead of being clipped back to it
+        let active: Vec<bool> = (0..n)
+            .map(|idx| (x[idx] <= lower[idx] && gradient[idx] > 0.0) || (x[idx] >= upper[idx] && gradient[idx] < 0.0))
+            .collect();
+
+        // Raise the damping until a step lowers the cost
+        let mut reduction = None;
+
+        while damping < MAX_DAMPING {
+            let mut damped = hessian.clone();
+            for (idx, row) in damped.iter_mut().enumerate() {
+                row[idx] += damping * hessian[idx][idx].max(MIN_DAMPING);
+
+                for (other, value) in row.iter_mut().enumerate() {
+                    if active[idx] || active[other] {
+                        *value = if idx == other { 1.0 } else { 0.0 };
+                    }
+                }
+            }
+
+            let rhs = gradient
+                .iter()
+                .zip(active.iter())
+                .map(|(g, active)| if *active { 0.0 } else { -g })
+                .collect();
+
+            let step = match solve(damped, rhs) {
+                Some(step) => step,
+                None => {
+                    damping *= DAMPING_FACTOR;
+                    continue;
+                }
+            };
+
+            let candidate: Vec<f64> = x
+                .iter()
+                .zip(step.iter())
+                .map(|(x, step)| x + step)
+                .collect();
+            let candidate = clamp(&candidate, &lower, &upper);
+            let candidate_r = evaluate(&candidate).filter(|candidate_r| candidate_r.len() == r.len());
+            evaluations += 1;
+
+            // Residuals of another length are rejected like an infinite cost
+            let (candidate_r, candidate_cost) = match candidate_r {
+                Some(candidate_r) => {
+                    let candidate_cost = sum_of_squares(&candidate_r);
+                    (candidate_r, candidate_cost)
+                }
+                None => (Vec::new(), f64::INFINITY),
+            };
+
+            if candidate_cost < cost {
+                let small_step = x
+                    .iter()
+                    .zip(candidate.iter())
+                    .all(|(x, candidate)| (candidate - x).abs() <= options.step_tolerance * x.abs().max(1.0));
+                reduction = Some(if small_step { 0.0 } else { (cost - candidate_cost) / cost });
+                x = candidate;
+                r = candidate_r;
+                cost = candidate_cost;
+                damping = (damping / DAMPING_FACTOR).max(MIN_DAMPING);
+                break;
+            }
+
+            damping *= DAMPING_FACTOR;
+        }
+
+        match reduction {
+            Some(reduction) if reduction <= options.tolerance || cost == 0.0 => {
+                break Termination::Converged;
+            }
+            Some(_) => {}
+            None => {
+                break Termination::Stalled;
+            }
+        }
+    };
+
+    Solution { x, cost, initial_cost, iterations, evaluations, termination }
+}
+
+pub fn nelder_mead(
+    residuals: impl Fn(&[f64]) -> Vec<f64>,
+    initial: &[f64],
+    lower: &[f64],
+    upper: &[f64],
+    weights: &[f64],
+    options: &Options
+) -> Solution {
+    let n = initial.len();
+    let (lower, upper) = match box_bounds(n, lower, upper) {
+        Some(bounds) => bounds,
+        None => {
+            return Solution::stopped(initial, Termination::InvalidInput);
+        }
+    };
+
+    if weights.iter().any(|weight| weight.is_nan() || *weight < 0.0) {
+        return Solution::stopped(initial, Termination::InvalidInput);
+    }
+
+    let start = clamp(initial, &lower, &upper);
+    let initial_cost = match weighted(residuals(&start), weights) {
+        Some(r) => sum_of_squares(&r),
+        None => {
+            return Solution::stopped(&start, Termination::InvalidInput);
+        }
+    };
+
+    if !initial_cost.is_finite() {
+        return Solution {
+            x: start,
+            cost: initial_cost,
+            initial_cost,
+            iterations: 0,
+            evaluations: 1,
+            termination: Termination::NonFinite,
+        };
+    }
+
+    // Costs that are not a number rank last, so the simplex moves away from them
+    let mut evaluations = 1;
+    let mut cost = |x: &[f64]| -> f64 {
+        evaluations += 1;
+        let cost = weighted(residuals(x), weights).map_or(f64::NAN, |r| sum_of_squares(&r));
+        if cost.is_nan() { f64::INFINITY } else { cost }
+    };
+
+    // Initial simplex along each axis, stepping inwards at an upper bound
+    let mut simplex: Vec<(Vec<f64>, f64)> = vec![(start.clone(), initial_cost)];
+    for idx in 0..n {
+        let mut vertex = start.clone();
+        let step = if vertex[idx] == 0.0 { SIMPLEX_ZERO_STEP } else { SIMPLEX_STEP * vertex[idx].abs() };
+        vertex[idx] = if vertex[idx] + step > upper[idx] { vertex[idx] - step } else { vertex[idx] + step };
+        let vertex = clamp(&vertex, &lower, &upper);
+        let vertex_cost = cost(&vertex);
+        simplex.push((vertex, vertex_cost));
+    }
+
+    let mut iterations = 0;
+
+    let termination = loop {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        let (best, worst) = (simplex[0].1, simplex[n].1);
+        let small_simplex = simplex[1..].iter().all(|(vertex, _)| {
+            vertex
+                .iter()
+                .zip(simplex[0].0.iter())
+                .all(|(x, best)| (x - best).abs() <= options.step_tolerance * best.abs().max(1.0))
+        });
+
+        if worst - best <= options.tolerance * (best.abs() + options.tolerance) && small_simplex {
+            break Termination::Converged;
+        }
+
+        if iterations == options.max_iterations {
+            break Termination::MaxIterations;
+        }
+
+        iterations += 1;
+
+        let centroid: Vec<f64> = (0..n)
+            .map(|idx| simplex[..n].iter().map(|(vertex, _)| vertex[idx]).sum::<f64>() / (n as f64))
+            .collect();
+        let toward = |coefficient: f64| -> Vec<f64> {
+            let point: Vec<f64> = centroid
+                .iter()
+                .zip(simplex[n].0.iter())
+                .map(|(centre, worst)| centre + coefficient * (centre - worst))
+                .collect();
+            clamp(&point, &lower, &upper)
+        };
+
+        let reflected = toward(REFLECTION);
+        let reflected_cost = cost(&reflected);
+
+        if reflected_cost < simplex[0].1 {
+            let expanded = toward(EXPANSION);
+            let expanded_cost = cost(&expanded);
+
+            simplex[n] = if expanded_cost < reflected_cost {
+                (expanded, expanded_cost)
+            } else {
+                (reflected, reflected_cost)
+            };
+            continue;
+        }
+
+        if reflected_cost < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_cost);
+            continue;
+        }
+
+        // Contract outside towards the reflection if it beats the worst vertex, else inside
+        let (contracted, bound) = if reflected_cost < simplex[n].1 {
+            (toward(CONTRACTION * REFLECTION), reflected_cost)
+        } else {
+            (toward(-CONTRACTION), simplex[n].1)
+        };
+        let contracted_cost = cost(&contracted);
+
+        if contracted_cost < bound {
+            simplex[n] = (contracted, contracted_cost);
+            continue;
+        }
+
+        // Shrink towards the best vertex
+        let best_vertex = simplex[0].0.clone();
+        for (vertex, vertex_cost) in simplex.iter_mut().skip(1) {
+            *vertex = best_vertex
+                .iter()
+                .zip(vertex.iter())
+                .map(|(best, x)| best + SHRINK * (x - best))
+                .collect();
+            *vertex_cost = cost(vertex);
+        }
+    };
+
+    let (x, final_cost) = simplex.swap_remove(0);
+
+    Solution {
+        x,
+        cost: final_cost,
+        initial_cost,
+        iterations,
+        evaluations,
+        termination,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Minimum of 0 at (1, 1), at the bottom of a curved valley
+    fn rosenbrock(x: &[f64]) -> Vec<f64> {
+        vec![10.0 * (x[1] - x[0] * x[0]), 1.0 - x[0]]
+    }
+
+    fn close(got: &[f64], expected: &[f64], tolerance: f64) {
+        for (got, expected) in got.iter().zip(expected.iter()) {
+            assert!((got - expected).abs() < tolerance, "Got: {:?}, Expected: {:?}", got, expected);
+        }
+    }
+
+    #[test]
+    fn levenberg_marquardt_fits_curve() {
+        // y = 2 exp(-0.7 t), sampled without noise
+        let times: Vec<f64> = (0..20).map(|t| (t as f64) * 0.25).collect();
+        let data: Vec<f64> = times.iter().map(|t| 2.0 * (-0.7 * t).exp()).collect();
+        let residuals = |x: &[f64]| -> Vec<f64> {
+            times
+                .iter()
+                .zip(data.iter())
+                .map(|(t, y)| x[0] * (-x[1] * t).exp() - y)
+                .collect()
+        };
+
+        let solution = levenberg_marquardt(residuals, &[1.0, 0.1], &[], &[], &[], &Options::default());
+
+        close(&solution.x, &[2.0, 0.7], 1e-6);
+        assert_eq!(solution.termination, Termination::Converged);
+        assert!(solution.cost < 1e-12 && solution.initial_cost > 1.0);
+        assert!(solution.evaluations > solution.iterations);
+
+        let solution = levenberg_marquardt(rosenbrock, &[-1.2, 1.0], &[], &[], &[], &Options::default());
+        close(&solution.x, &[1.0, 1.0], 1e-6);
+    }
+
+    #[test]
+    fn nelder_mead_minimizes() {
+        let options = Options { max_iterations: 1000, ..Options::default() };
+        let solution = nelder_mead(rosenbrock, &[-1.2, 1.0], &[], &[], &[], &options);
+
+        close(&solution.x, &[1.0, 1.0], 1e-3);
+        assert_eq!(solution.termination, Termination::Converged);
+        assert!(solution.cost < 1e-8 && solution.cost < solution.initial_cost);
+    }
+
+    #[test]
+    fn bounds_hold() {
+        // The valley floor x1 = x0^2 at the bound x0 = 0.5
+        let lower = [-2.0, -2.0];
+        let upper = [0.5, 2.0];
+
+        let solution = levenberg_marquardt(rosenbrock, &[-1.2, 1.0], &lower, &upper, &[], &Options::default());
+        close(&solution.x, &[0.5, 0.25], 1e-6);
+
+        let options = Options { max_iterations: 1000, ..Options::default() };
+        let solution = nelder_mead(rosenbrock, &[-1.2, 1.0], &lower, &upper, &[], &options);
+        close(&solution.x, &[0.5, 0.25], 1e-3);
+
+        // Starting outside of the box
+        let solution = levenberg_marquardt(rosenbrock, &[3.0, 1.0], &lower, &upper, &[], &Options::default());
+        assert!(solution.x[0] <= 0.5);
+    }
+
+    #[test]
+    fn weights_scale_residuals() {
+        // Weighted mean of 1 and 3 with weights 3 and 1
+        let residuals = |x: &[f64]| vec![x[0] - 1.0, x[0] - 3.0];
+        let weights = [3.0, 1.0];
+
+        let solution = levenberg_marquardt(residuals, &[0.0], &[], &[], &weights, &Options::default());
+        close(&solution.x, &[1.5], 1e-8);
+        assert!((solution.cost - 3.0).abs() < 1e-8);
+
+        let solution = nelder_mead(residuals, &[0.0], &[], &[], &weights, &Options::default());
+        close(&solution.x, &[1.5], 1e-4);
+    }
+
+    #[test]
+    fn diagnostics() {
+        let options = Options { max_iterations: 2, ..Options::default() };
+        let solution = levenberg_marquardt(rosenbrock, &[-1.2, 1.0], &[], &[], &[], &options);
+        assert_eq!(solution.termination, Termination::MaxIterations);
+        assert_eq!(solution.iterations, 2);
+
+        // Bounds, weights of the wrong length and negative weights
+        for solution in [
+            levenberg_marquardt(rosenbrock, &[0.0, 0.0], &[0.0], &[], &[], &Options::default()),
+            levenberg_marquardt(rosenbrock, &[0.0, 0.0], &[1.0, 0.0], &[0.0, 0.0], &[], &Options::default()),
+            levenberg_marquardt(rosenbrock, &[0.0, 0.0], &[], &[], &[1.0], &Options::default()),
+            nelder_mead(rosenbrock, &[0.0, 0.0], &[], &[], &[1.0, -1.0], &Options::default()),
+        ] {
+            assert_eq!(solution.termination, Termination::InvalidInput);
+            assert!(solution.cost.is_nan());
+        }
+
+        // Residuals that change length in the Jacobian or at a candidate step
+        let shrinking = |x: &[f64]| if x[0] == 0.0 { vec![1.0, 2.0] } else { vec![x[0]] };
+        assert_eq!(levenberg_marquardt(shrinking, &[0.0], &[], &[], &[], &Options::default()).termination, Termination::InvalidInput);
+
+        let vanishing = |x: &[f64]| if x[0] > 0.5 { Vec::new() } else { vec![x[0] - 1.0] };
+        let solution = levenberg_marquardt(vanishing, &[0.0], &[], &[], &[], &Options::default());
+        assert!(solution.x[0] <= 0.5 && solution.cost >= 0.25, "Solution: {:?}", solution);
+        assert_ne!(solution.termination, Termination::Converged);
+
+        let not_a_number = |_: &[f64]| vec![f64::NAN];
+        assert_eq!(levenberg_marquardt(not_a_number, &[0.0], &[], &[], &[], &Options::default()).termination, Termination::NonFinite);
+        assert_eq!(nelder_mead(not_a_number, &[0.0], &[], &[], &[], &Options::default()).termination, Termination::NonFinite);
+    }
+}
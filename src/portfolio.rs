@@ -0,0 +1,369 @@
+use crate::consts::OptionDir;
+use crate::surface::{ self, VolSurface };
+use crate::vol32x8;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    Mark-to-model value and dollar Greeks of a book of option positions.
+
+    Every position is priced with the SIMD pricer at the fitted surface's vol for its strike and
+    expiry, off the surface's spot. The Greeks are scaled by quantity x multiplier into the
+    P&L a desk reads off a risk report:
+      - value: price x quantity x multiplier
+      - delta: dollar delta, delta x spot, the P&L of a 100% move in spot to first order
+      - gamma: change of the dollar delta for a 1% move in spot, gamma x spot^2 / 100
+      - vega: P&L of a 1 vol point (0.01) rise in implied vol
+      - theta: P&L of one calendar day passing, theta / 365
+      - rho: P&L of a 1% (0.01) rise in the risk free rate
+
+    Positions the surface cannot price (no fitted smiles, or expired) are NaN, and so are the
+    totals then, rather than silently leaving their risk out.
+*/
+
+const DAYS_PER_YEAR: f32 = 365.0;
+
+/// A holding of quantity contracts of one option, each on multiplier units of the underlying
+/// (100 for US equity options). Short positions have a negative quantity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub option_dir: OptionDir,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub quantity: f32,
+    pub multiplier: f32,
+}
+
+/// Value and dollar Greeks of one position, with the per-contract vol and price it was marked at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PositionRisk {
+    pub option_type: &'static str,
+    pub strike: f32,
+    pub years_to_expiry: f32,
+    pub quantity: f32,
+    pub multiplier: f32,
+    pub implied_vol: f32,
+    pub price: f32,
+    pub value: f32,
+    pub delta: f32,
+    pub gamma: f32,
+    pub vega: f32,
+    pub theta: f32,
+    pub rho: f32,
+}
+
+/// Sums of the position values and dollar Greeks
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RiskTotals {
+    pub value: f32,
+    pub delta: f32,
+    pub gamma: f32,
+    pub vega: f32,
+    pub theta: f32,
+    pub rho: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PortfolioRisk {
+    pub spot: f32,
+    pub positions: Vec<PositionRisk>,
+    pub total: RiskTotals,
+}
+
+pub fn portfolio_risk(
+    surface: &VolSurface,
+    positions: &[Position],
+    risk_free_rate: f32,
+    dividend_yield: f32
+) -> PortfolioRisk {
+    let spot = surface.spot;
+    let num_positions = positions.len();
+
+    // Per-contract price and Greeks, filled one option direction at a time since the kernels
+    // take a single direction
+    let mut price = vec![f32::NAN; num_positions];
+    let mut greeks = vec![[f32::NAN; 5]; num_positions];
+    let implied_vol: Vec<f32> = positions
+        .iter()
+        .map(|position| surface.vol(position.strike, position.years_to_expiry))
+        .collect();
+
+    for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+        let idx: Vec<usize> = (0..num_positions)
+            .filter(|i| positions[*i].option_dir == option_dir)
+            .collect();
+
+        if idx.is_empty() {
+            continue;
+        }
+
+        let n = idx.len();
+        let strike: Vec<f32> = idx.iter().map(|i| positions[*i].strike).collect();
+        let years_to_expiry: Vec<f32> = idx.iter().map(|i| positions[*i].years_to_expiry).collect();
+        let volatility: Vec<f32> = idx.iter().map(|i| implied_vol[*i]).collect();
+        let spots = vec![spot; n];
+        let rates = vec![risk_free_rate; n];
+        let dividends = vec![dividend_yield; n];
+
+        let prices = vol32x8::price(option_dir, &spots, &strike, &volatility, &rates, &dividends, &years_to_expiry);
+        let contract = vol32x8::greeks(option_dir, &spots, &strike, &volatility, &rates, &dividends, &years_to_expiry);
+
+        for (j, i) in idx.iter().enumerate() {
+            price[*i] = prices[j];
+            greeks[*i] = [
+                contract.delta[j],
+                contract.gamma[j],
+                contract.vega[j],
+                contract.theta[j],
+                contract.rho[j],
+            ];
+        }
+    }
+
+    let positions: Vec<PositionRisk> = positions
+        .iter()
+        .enumerate()
+        .map(|(i, position)| {
+            let scale = position.quantity * position.multiplier;
+            let [delta, gamma, vega, theta, rho] = greeks[i];
+
+            PositionRisk {
+                option_type: match position.option_dir {
+                    OptionDir::CALL => "call",
+                    OptionDir::PUT => "put",
+                },
+                strike: position.strike,
+                years_to_expiry: position.years_to_expiry,
+                quantity: position.quantity,
+                multiplier: position.multiplier,
+                implied_vol: implied_vol[i],
+                price: price[i],
+                value: price[i] * scale,
+                delta: delta * spot * scale,
+                gamma: (gamma * spot * spot) / 100.0 * scale,
+                vega: (vega / 100.0) * scale,
+                theta: (theta / DAYS_PER_YEAR) * scale,
+                rho: (rho / 100.0) * scale,
+            }
+        })
+        .collect();
+
+    let mut total = RiskTotals::default();
+
+    for position in positions.iter() {
+        total.value += position.value;
+        total.delta += position.delta;
+        total.gamma += position.gamma;
+        total.vega += position.vega;
+        total.theta += position.theta;
+        total.rho += position.rho;
+    }
+
+    PortfolioRisk { spot, positions, total }
+}
+
+/*
+    Positions from the columns the wasm exports take, with option_dir holding the OptionDir
+    values (OptionDir.CALL or OptionDir.PUT). None if the lengths do not match or a direction
+    is not valid.
+*/
+pub(crate) fn positions_from_columns(
+    option_dir: &[i32],
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    quantity: &[f32],
+    multiplier: &[f32]
+) -> Option<Vec<Position>> {
+    if
+        !(
+            option_dir.len() == strike.len() &&
+            strike.len() == years_to_expiry.len() &&
+            years_to_expiry.len() == quantity.len() &&
+            quantity.len() == multiplier.len()
+        )
+    {
+        return None;
+    }
+
+    (0..option_dir.len())
+        .map(|i| {
+            let option_dir = match option_dir[i] {
+                dir if dir == (OptionDir::CALL as i32) => OptionDir::CALL,
+                dir if dir == (OptionDir::PUT as i32) => OptionDir::PUT,
+                _ => {
+                    return None;
+                }
+            };
+
+            Some(Position {
+                option_dir,
+                strike: strike[i],
+                years_to_expiry: years_to_expiry[i],
+                quantity: quantity[i],
+                multiplier: multiplier[i],
+            })
+        })
+        .collect()
+}
+
+/*
+    Frontend entry point. Fits the surface to the mid-price arrays with surface::fit_mids and
+    returns the portfolio risk of the positions as JSON, see positions_from_columns for the
+    position columns. Returns a portfolio without positions for invalid columns.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn portfolio_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    option_dir: &[i32],
+    position_strike: &[f32],
+    position_years_to_expiry: &[f32],
+    quantity: &[f32],
+    multiplier: &[f32]
+) -> String {
+    let empty = || serde_json::to_string(&PortfolioRisk::default()).unwrap_or_else(|_| String::from("{}"));
+
+    let positions = positions_from_columns(
+        option_dir,
+        position_strike,
+        position_years_to_expiry,
+        quantity,
+        multiplier
+    );
+    let positions = match positions {
+        Some(positions) => positions,
+        None => {
+            return empty();
+        }
+    };
+
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return empty();
+        }
+    };
+
+    serde_json::to_string(&portfolio_risk(&surface, &positions, risk_free_rate, dividend_yield)).unwrap_or_else(|_| empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::fixtures::{ self, flat_surface, position };
+
+    #[test]
+    fn flat_surface_matches_bs() {
+        let surface = flat_surface(0.2);
+        let positions = [
+            position(OptionDir::CALL, 105.0, 3.0),
+            position(OptionDir::PUT, 95.0, -2.0),
+            position(OptionDir::CALL, 90.0, 1.0),
+        ];
+        let risk = portfolio_risk(&surface, &positions, 0.03, 0.01);
+
+        assert_eq!(risk.positions.len(), positions.len());
+
+        for (position, row) in positions.iter().zip(risk.positions.iter()) {
+            let scale = position.quantity * position.multiplier;
+            let price = bs::price(position.option_dir, 100.0, position.strike, 0.2, 0.03, 0.01, 0.5);
+
+            assert!((row.implied_vol - 0.2).abs() < 1e-6);
+            assert!((row.value - price * scale).abs() < 0.01 * scale.abs(), "Got: {}, Expected: {}", row.value, price * scale);
+        }
+
+        let value: f32 = risk.positions.iter().map(|row| row.value).sum();
+        assert!((risk.total.value - value).abs() < 1e-3);
+    }
+
+    #[test]
+    fn dollar_greeks_match_bumps() {
+        let surface = flat_surface(0.2);
+        let positions = [position(OptionDir::CALL, 100.0, 2.0), position(OptionDir::PUT, 95.0, 1.0)];
+        let risk = portfolio_risk(&surface, &positions, 0.03, 0.0);
+
+        let book = |spot: f32, vol: f32, rate: f32, years: f32| -> f64 {
+            positions
+                .iter()
+                .map(|p| {
+                    let price = bs::price(p.option_dir, spot, p.strike, vol, rate, 0.0, years) as f64;
+                    price * ((p.quantity * p.multiplier) as f64)
+                })
+                .sum()
+        };
+
+        // Dollar delta is per 100% spot move, so a 1% bump moves the book by delta / 100
+        let delta = (book(101.0, 0.2, 0.03, 0.5) - book(99.0, 0.2, 0.03, 0.5)) / 2.0 * 100.0;
+        let gamma = (book(101.0, 0.2, 0.03, 0.5) - 2.0 * book(100.0, 0.2, 0.03, 0.5) + book(99.0, 0.2, 0.03, 0.5)) * 100.0;
+        let vega = (book(100.0, 0.21, 0.03, 0.5) - book(100.0, 0.19, 0.03, 0.5)) / 2.0;
+        let rho = (book(100.0, 0.2, 0.04, 0.5) - book(100.0, 0.2, 0.02, 0.5)) / 2.0;
+        let theta = book(100.0, 0.2, 0.03, 0.5 - 1.0 / 365.0) - book(100.0, 0.2, 0.03, 0.5);
+
+        let total = &risk.total;
+        let close = |got: f32, expected: f64, tolerance: f64| {
+            assert!(((got as f64) - expected).abs() < tolerance, "Got: {}, Expected: {}", got, expected);
+        };
+
+        close(total.delta, delta, 0.01 * delta.abs());
+        close(total.gamma, gamma, 0.05 * gamma.abs());
+        close(total.vega, vega, 0.01 * vega.abs());
+        close(total.rho, rho, 0.02 * rho.abs());
+        close(total.theta, theta, 0.05 * theta.abs());
+    }
+
+    #[test]
+    fn long_and_short_cancel() {
+        let surface = flat_surface(0.25);
+        let positions = [position(OptionDir::PUT, 97.0, 5.0), position(OptionDir::PUT, 97.0, -5.0)];
+        let risk = portfolio_risk(&surface, &positions, 0.03, 0.0);
+
+        assert!(risk.positions[0].value > 0.0);
+        assert_eq!(risk.total, RiskTotals::default());
+    }
+
+    #[test]
+    fn unpriceable_positions() {
+        let risk = portfolio_risk(&VolSurface::default(), &[position(OptionDir::CALL, 100.0, 1.0)], 0.03, 0.0);
+
+        assert!(risk.positions[0].value.is_nan());
+        assert!(risk.total.value.is_nan());
+        assert_eq!(portfolio_risk(&flat_surface(0.2), &[], 0.03, 0.0).total, RiskTotals::default());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let [call_price, put_price, strike, years_to_expiry] = fixtures::flat_chain(5);
+        let surface = surface::fit_mids(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03).unwrap();
+        let positions = [position(OptionDir::CALL, 105.0, 3.0), position(OptionDir::PUT, 95.0, -2.0)];
+
+        let json = portfolio_json(
+            &call_price,
+            &put_price,
+            100.0,
+            &strike,
+            &years_to_expiry,
+            0.03,
+            0.0,
+            &[OptionDir::CALL as i32, OptionDir::PUT as i32],
+            &[105.0, 95.0],
+            &[0.5, 0.5],
+            &[3.0, -2.0],
+            &[100.0, 100.0]
+        );
+        assert_eq!(json, serde_json::to_string(&portfolio_risk(&surface, &positions, 0.03, 0.0)).unwrap());
+
+        // Unknown option directions and position columns of different lengths
+        let empty = serde_json::to_string(&PortfolioRisk::default()).unwrap();
+        let bad_dir = portfolio_json(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03, 0.0, &[0], &[100.0], &[0.5], &[1.0], &[100.0]);
+        let bad_len = portfolio_json(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03, 0.0, &[2], &[100.0], &[0.5], &[1.0], &[]);
+        assert_eq!(bad_dir, empty);
+        assert_eq!(bad_len, empty);
+    }
+}
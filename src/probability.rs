@@ -0,0 +1,364 @@
+use crate::bs32x8::{ normal_cdf, normal_pdf };
+use crate::consts::OptionDir;
+use crate::surface::{ self, VolSurface };
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    Risk-neutral probabilities and expected moves read off the fitted surface, with the
+    forward of the surface so no rates are needed.
+
+    The probability of finishing above a strike is minus the slope of the undiscounted call
+    price in strike. With the vol depending on strike this is
+        P(S_T > K) = N(d2) - F n(d1) sqrt(T) dsigma/dK
+    where the second term is the forward vega times the smile slope. On a put skewed smile it
+    raises the probability of finishing above a downside strike over N(d2).
+
+    The probability of touching a barrier before expiry comes from the reflection principle for
+    a Brownian motion with drift in log spot, at the surface vol of the barrier:
+        P = N((m - b) / s) + exp(2 m b / s^2) N((-b - m) / s)
+    with b = |ln(H / S)|, s = sigma sqrt(T) and m the mean of ln(S_T / S), of the same sign as b.
+    Near the money this is about twice the probability of finishing past the barrier.
+
+    The expected move of an expiry is the undiscounted ATM forward straddle, E|S_T - F|, at the
+    ATM vol. The 1 standard deviation range is the 15.9% and 84.1% quantiles of the skew
+    adjusted distribution above.
+*/
+
+// Strike bump for the smile slope, relative to the strike
+const SLOPE_BUMP: f32 = 0.001;
+
+// Quantiles of a 1 standard deviation move, N(-1) and N(1)
+const LOWER_QUANTILE: f32 = 0.158_655_26;
+#[allow(clippy::excessive_precision)]
+const UPPER_QUANTILE: f32 = 0.841_344_74;
+
+// Bisection of the quantiles within +- QUANTILE_STD_DEVS standard deviations of the forward
+const QUANTILE_STD_DEVS: f32 = 8.0;
+const QUANTILE_ITERATIONS: usize = 60;
+
+/// Expected move of one expiry in price terms
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExpectedMove {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    pub atm_vol: f32,
+    pub straddle_move: f32,
+    pub lower: f32,
+    pub upper: f32,
+}
+
+/// Probabilities of one strike at one expiry
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StrikeProbability {
+    pub strike: f32,
+    pub itm_probability: f32,
+    pub touch_probability: f32,
+}
+
+// Risk-neutral probability of finishing above strike
+fn above_probability(surface: &VolSurface, strike: f32, years_to_expiry: f32) -> f32 {
+    let forward = surface.forward(years_to_expiry);
+    let vol = surface.vol(strike, years_to_expiry);
+    let sqrt_t = years_to_expiry.sqrt();
+    let sd = vol * sqrt_t;
+
+    let d1 = ((forward / strike).ln() + 0.5 * sd * sd) / sd;
+    let d2 = d1 - sd;
+
+    let bump = SLOPE_BUMP * strike;
+    let slope =
+        (surface.vol(strike + bump, years_to_expiry) - surface.vol(strike - bump, years_to_expiry)) /
+        (2.0 * bump);
+
+    (normal_cdf(d2) - forward * normal_pdf(d1) * sqrt_t * slope).clamp(0.0, 1.0)
+}
+
+// NaN where the surface has no vol, i.e. without smiles or for expiries at or before today
+pub fn itm_probability(surface: &VolSurface, option_dir: OptionDir, strike: f32, years_to_expiry: f32) -> f32 {
+    let above = above_probability(surface, strike, years_to_expiry);
+
+    match option_dir {
+        OptionDir::CALL => above,
+        OptionDir::PUT => 1.0 - above,
+    }
+}
+
+pub fn touch_probability(surface: &VolSurface, barrier: f32, years_to_expiry: f32) -> f32 {
+    let spot = surface.spot;
+    let sd = surface.vol(barrier, years_to_expiry) * years_to_expiry.sqrt();
+
+    // min below would turn NaN into 1
+    if sd.is_nan() {
+        return f32::NAN;
+    }
+
+    if barrier == spot {
+        return 1.0;
+    }
+
+    let drift = (surface.forward(years_to_expiry) / spot).ln() - 0.5 * sd * sd;
+    let distance = (barrier / spot).ln();
+
+    // A lower barrier is an upper one of -ln(S_T / S)
+    let (b, m) = if distance > 0.0 { (distance, drift) } else { (-distance, -drift) };
+
+    let reflected = ((2.0 * m * b) / (sd * sd)).exp() * normal_cdf((-b - m) / sd);
+    (normal_cdf((m - b) / sd) + reflected).min(1.0)
+}
+
+// Strike the spot finishes below with the given probability, by bisection in log-strike
+fn quantile(surface: &VolSurface, years_to_expiry: f32, probability: f32) -> f32 {
+    let forward = surface.forward(years_to_expiry);
+    let sd = surface.vol(forward, years_to_expiry) * years_to_expiry.sqrt();
+
+    if sd.is_nan() {
+        return f32::NAN;
+    }
+
+    let mut lo = -QUANTILE_STD_DEVS * sd;
+    let mut hi = QUANTILE_STD_DEVS * sd;
+
+    for _ in 0..QUANTILE_ITERATIONS {
+        let mid = 0.5 * (lo + hi);
+
+        if 1.0 - above_probability(surface, forward * mid.exp(), years_to_expiry) < probability {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    forward * (0.5 * (lo + hi)).exp()
+}
+
+pub fn expected_move(surface: &VolSurface, years_to_expiry: f32) -> ExpectedMove {
+    let forward = surface.forward(years_to_expiry);
+    let atm_vol = surface.vol(forward, years_to_expiry);
+    let sd = atm_vol * years_to_expiry.sqrt();
+
+    ExpectedMove {
+        years_to_expiry,
+        forward,
+        atm_vol,
+        // Call and put are both worth F (2 N(s / 2) - 1) at the forward
+        straddle_move: 2.0 * forward * (2.0 * normal_cdf(0.5 * sd) - 1.0),
+        lower: quantile(surface, years_to_expiry, LOWER_QUANTILE),
+        upper: quantile(surface, years_to_expiry, UPPER_QUANTILE),
+    }
+}
+
+// Expected move of every fitted expiry
+pub fn expected_moves(surface: &VolSurface) -> Vec<ExpectedMove> {
+    surface.smiles
+        .iter()
+        .map(|smile| expected_move(surface, smile.years_to_expiry))
+        .collect()
+}
+
+/*
+    Frontend entry point. Fits the surface to the mid-price arrays with surface::fit_mids and
+    returns the expected move at each of years_to_expiry as a JSON array, or at every fitted
+    expiry if it is empty.
+*/
+#[wasm_bindgen]
+pub fn expected_move_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    horizons: &[f32]
+) -> String {
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return String::from("[]");
+        }
+    };
+
+    let moves = if horizons.is_empty() {
+        expected_moves(&surface)
+    } else {
+        horizons
+            .iter()
+            .map(|horizon| expected_move(&surface, *horizon))
+            .collect()
+    };
+
+    serde_json::to_string(&moves).unwrap_or_else(|_| String::from("[]"))
+}
+
+/*
+    Frontend entry point. ITM and touch probabilities of option_dir options at each of
+    strikes expiring at expiry, as a JSON array.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn probability_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    option_dir: OptionDir,
+    strikes: &[f32],
+    expiry: f32
+) -> String {
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return String::from("[]");
+        }
+    };
+
+    let probabilities: Vec<StrikeProbability> = strikes
+        .iter()
+        .map(|strike| StrikeProbability {
+            strike: *strike,
+            itm_probability: itm_probability(&surface, option_dir, *strike, expiry),
+            touch_probability: touch_probability(&surface, *strike, expiry),
+        })
+        .collect();
+
+    serde_json::to_string(&probabilities).unwrap_or_else(|_| String::from("[]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::fixtures;
+
+    // Smile with vol c0 + c1 x at every expiry, around a forward of 100
+    fn skew_surface(spot: f32, c0: f32, c1: f32) -> VolSurface {
+        VolSurface { spot, ..fixtures::skew_surface(c0, c1) }
+    }
+
+    fn close(got: f32, expected: f32, tolerance: f32) {
+        assert!((got - expected).abs() < tolerance, "Got: {}, Expected: {}", got, expected);
+    }
+
+    #[test]
+    fn flat_itm_is_n_d2() {
+        let surface = skew_surface(100.0, 0.2, 0.0);
+
+        for strike in [70.0f32, 90.0, 100.0, 110.0, 140.0] {
+            let sd = 0.2 * 0.5f32.sqrt();
+            let d2 = ((100.0 / strike).ln() - 0.5 * sd * sd) / sd;
+            let call = itm_probability(&surface, OptionDir::CALL, strike, 0.5);
+            let put = itm_probability(&surface, OptionDir::PUT, strike, 0.5);
+
+            close(call, normal_cdf(d2), 1e-5);
+            close(call + put, 1.0, 1e-6);
+        }
+
+        assert!(itm_probability(&surface, OptionDir::CALL, 100.0, 0.0).is_nan());
+        assert!(itm_probability(&VolSurface::default(), OptionDir::CALL, 100.0, 0.5).is_nan());
+    }
+
+    #[test]
+    fn skew_adjusted_itm_matches_call_spread() {
+        // Put skew, with the forward equal to spot and no rates
+        let surface = skew_surface(100.0, 0.2, -0.3);
+
+        for strike in [85.0, 95.0, 100.0, 105.0, 115.0] {
+            let call = |k: f32| bs::price(OptionDir::CALL, 100.0, k, surface.vol(k, 0.5), 0.0, 0.0, 0.5);
+            let spread = (call(strike - 0.5) - call(strike + 0.5)) / 1.0;
+            let probability = itm_probability(&surface, OptionDir::CALL, strike, 0.5);
+
+            close(probability, spread, 0.005);
+
+            // The skew moves probability to the downside, so finishing above is more likely
+            let sd = surface.vol(strike, 0.5) * 0.5f32.sqrt();
+            let d2 = ((100.0 / strike).ln() - 0.5 * sd * sd) / sd;
+            assert!(probability > normal_cdf(d2), "Strike: {}", strike);
+        }
+    }
+
+    #[test]
+    fn touch_without_drift_is_twice_itm() {
+        // Spot below the forward by the convexity term, so ln(S_T / S) has zero mean
+        let sd = 0.2 * 0.5f32.sqrt();
+        let surface = skew_surface(100.0 * (-0.5 * sd * sd).exp(), 0.2, 0.0);
+        let spot = surface.spot;
+
+        for barrier in [80.0, 90.0, 110.0, 125.0] {
+            let b = (barrier / spot).ln().abs();
+            close(touch_probability(&surface, barrier, 0.5), 2.0 * normal_cdf(-b / sd), 1e-4);
+        }
+
+        close(touch_probability(&surface, spot, 0.5), 1.0, 1e-6);
+        assert!(touch_probability(&surface, 100.0, 0.0).is_nan());
+    }
+
+    #[test]
+    fn touch_is_more_likely_than_finishing_past() {
+        let surface = skew_surface(100.0, 0.2, -0.2);
+
+        for strike in [80.0, 95.0, 105.0, 130.0] {
+            let option_dir = if strike > 100.0 { OptionDir::CALL } else { OptionDir::PUT };
+            let itm = itm_probability(&surface, option_dir, strike, 0.25);
+            let touch = touch_probability(&surface, strike, 0.25);
+
+            assert!(touch > itm && touch <= 1.0, "Strike: {} ITM: {} Touch: {}", strike, itm, touch);
+        }
+    }
+
+    #[test]
+    fn lognormal_expected_move() {
+        let surface = skew_surface(100.0, 0.2, 0.0);
+        let moves = expected_moves(&surface);
+
+        assert_eq!(moves.len(), 2);
+
+        for expected in moves.iter() {
+            let sd = 0.2 * expected.years_to_expiry.sqrt();
+
+            close(expected.forward, 100.0, 1e-4);
+            close(expected.atm_vol, 0.2, 1e-6);
+            // About 0.8 standard deviations
+            close(expected.straddle_move, 100.0 * (2.0 / std::f32::consts::PI).sqrt() * sd, 0.05);
+            close(expected.lower, 100.0 * (-0.5 * sd * sd - sd).exp(), 0.01);
+            close(expected.upper, 100.0 * (-0.5 * sd * sd + sd).exp(), 0.01);
+        }
+
+        // On a skewed smile the range still holds the middle 68% of the distribution
+        let skewed_surface = skew_surface(100.0, 0.2, -0.3);
+        let skewed = expected_move(&skewed_surface, 1.0);
+
+        assert!(skewed.lower < 100.0 && 100.0 < skewed.upper);
+        close(itm_probability(&skewed_surface, OptionDir::PUT, skewed.lower, 1.0), LOWER_QUANTILE, 1e-4);
+        close(itm_probability(&skewed_surface, OptionDir::CALL, skewed.upper, 1.0), LOWER_QUANTILE, 1e-4);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let [call_price, put_price, strike, years_to_expiry] = fixtures::flat_chain(5);
+        let surface = surface::fit_mids(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03).unwrap();
+        let json = |value: &Vec<ExpectedMove>| serde_json::to_string(value).unwrap();
+
+        // Every fitted expiry without horizons
+        assert_eq!(
+            expected_move_json(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03, &[]),
+            json(&expected_moves(&surface))
+        );
+        assert_eq!(
+            expected_move_json(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03, &[5.0 / 365.0]),
+            json(&vec![expected_move(&surface, 5.0 / 365.0)])
+        );
+
+        let probabilities: serde_json::Value = serde_json::from_str(
+            &probability_json(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03, OptionDir::PUT, &[90.0, 110.0], 0.25)
+        ).unwrap();
+        assert_eq!(probabilities.as_array().unwrap().len(), 2);
+        assert_eq!(probabilities[1]["strike"], 110.0);
+        let itm = probabilities[1]["itm_probability"].as_f64().unwrap() as f32;
+        assert_eq!(itm, itm_probability(&surface, OptionDir::PUT, 110.0, 0.25));
+
+        assert_eq!(expected_move_json(&call_price, &put_price, 100.0, &[], &years_to_expiry, 0.03, &[]), "[]");
+        assert_eq!(probability_json(&call_price, &put_price, 100.0, &strike, &[], 0.03, OptionDir::PUT, &[90.0], 0.25), "[]");
+    }
+}
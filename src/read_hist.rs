@@ -4,6 +4,8 @@ use std::path::Path;
 use serde::{ Serialize, Deserialize };
 use serde_json;
 use std::io::prelude::*;
+use crate::chain::{ OptionChain, OptionQuote };
+use crate::consts::OptionDir;
 
 // This file contains helper functions that extracts data from two sample .dat files containing
 // option data from AAPL (2013) and SPY (2024)
@@ -17,7 +19,10 @@ struct HistoricalData {
     put_strikes: Vec<f32>,
     spot: f32,
     years_to_expiry: Vec<f32>,
+    // Missing from older exports such as data/aapl.json
+    #[serde(default)]
     option_name: String,
+    #[serde(default)]
     time: String,
 }
 
@@ -34,7 +39,7 @@ fn write_lines(filename: &str, data: String) {
     let display = path.display();
 
     // Open a file in write-only mode, returns `io::Result<File>`
-    let mut file = match File::create(&path) {
+    let mut file = match File::create(path) {
         Err(why) => panic!("couldn't create {}: {}", display, why),
         Ok(file) => file,
     };
@@ -46,8 +51,9 @@ fn write_lines(filename: &str, data: String) {
     }
 }
 
+// Accepts both abbreviated and full month names
 fn get_days_from_jan(month: &str) -> f32 {
-    match month {
+    match month.get(0..3).unwrap_or("") {
         "Jan" => 31.0,
         "Feb" => 57.0,
         "Mar" => 90.0,
@@ -64,6 +70,91 @@ fn get_days_from_jan(month: &str) -> f32 {
     }
 }
 
+// Month number (1 - 12) of an abbreviated or full month name
+fn month_number(month: &str) -> Option<i64> {
+    let months = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+    months
+        .iter()
+        .position(|name| month.get(0..3) == Some(*name))
+        .map(|idx| (idx as i64) + 1)
+}
+
+// Days since Jan 1 1970 of a Gregorian calendar date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // Years start in March so the leap day is the last day of the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+// Equity options stop trading at 4:00 PM ET on the expiration date
+const CLOSE_MINUTES: i64 = 16 * 60;
+const MINUTES_PER_YEAR: f32 = 365.0 * 24.0 * 60.0;
+
+/*
+    Minutes since Jan 1 1970 of a quote time such as "Feb 8 2024 at 11:23 AM EST" or
+    "February 8, 2024 at 11:23 AM EST". Without a time of day the quote is taken at midnight.
+*/
+fn parse_quote_time(date: &str) -> Option<i64> {
+    let tokens = date
+        .split([' ', ','])
+        .filter(|token| !token.is_empty())
+        .collect::<Vec<&str>>();
+
+    if tokens.len() < 3 {
+        return None;
+    }
+
+    let days = days_from_civil(
+        tokens[2].parse().ok()?,
+        month_number(tokens[0])?,
+        tokens[1].parse().ok()?
+    );
+
+    let minutes = match tokens.iter().position(|token| token.contains(':')) {
+        Some(idx) => {
+            let (hours, minutes) = tokens[idx].split_once(':')?;
+            let hours: i64 = hours.parse().ok()?;
+            let minutes: i64 = minutes.parse().ok()?;
+
+            // 12 AM is midnight and 12 PM is noon
+            let hours = match tokens.get(idx + 1) {
+                Some(&"PM") => (hours % 12) + 12,
+                Some(&"AM") => hours % 12,
+                _ => hours,
+            };
+
+            hours * 60 + minutes
+        }
+        None => 0,
+    };
+
+    Some(days * 24 * 60 + minutes)
+}
+
+// Years from the quote time to the close of an expiration date such as "Fri Mar 08 2024"
+fn years_to_close(quote_time: i64, expiration: &str) -> Option<f32> {
+    let tokens = expiration.split(' ').collect::<Vec<&str>>();
+
+    if tokens.len() != 4 {
+        return None;
+    }
+
+    let days = days_from_civil(
+        tokens[3].parse().ok()?,
+        month_number(tokens[1])?,
+        tokens[2].parse().ok()?
+    );
+
+    Some(((days * 24 * 60 + CLOSE_MINUTES - quote_time) as f32) / MINUTES_PER_YEAR)
+}
+
+#[allow(clippy::type_complexity)]
 pub fn get_appl_data() -> (f32, Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>, Vec<String>) {
     let mut spot = 0.0;
     let mut today: f32 = 0.0;
@@ -80,7 +171,7 @@ pub fn get_appl_data() -> (f32, Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>
     // File hosts.txt must exist in the current path
     if let Ok(lines) = read_lines("data/AAPLQuoteData.dat") {
         // Extract option strike, prices, and current spot
-        for line in lines.flatten() {
+        for line in lines.map_while(Result::ok) {
             line_counter += 1;
 
             if line_counter == 1 {
@@ -134,9 +225,10 @@ pub fn get_appl_data() -> (f32, Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>, Vec<f32>
         }
     }
 
-    return (spot, call_prices, call_strikes, put_prices, put_strikes, years_to_expiry, names);
+    (spot, call_prices, call_strikes, put_prices, put_strikes, years_to_expiry, names)
 }
 
+#[allow(clippy::type_complexity)]
 pub fn get_spy_data() -> (
     f32,
     Vec<f32>,
@@ -165,7 +257,7 @@ pub fn get_spy_data() -> (
     // File hosts.txt must exist in the current path
     if let Ok(lines) = read_lines("data/SPYQuoteData.dat") {
         // Extract option strike, prices, and current spot
-        for line in lines.flatten() {
+        for line in lines.map_while(Result::ok) {
             line_counter += 1;
 
             if line_counter == 1 {
@@ -248,13 +340,13 @@ pub fn print_appl_data() {
         get_appl_data();
 
     let data = HistoricalData {
-        call_prices: call_prices,
-        call_strikes: call_strikes,
-        names: names,
-        put_prices: put_prices,
-        put_strikes: put_strikes,
-        spot: spot,
-        years_to_expiry: years_to_expiry,
+        call_prices,
+        call_strikes,
+        names,
+        put_prices,
+        put_strikes,
+        spot,
+        years_to_expiry,
         option_name: String::from("AAPL (APPLE INC)"),
         time: String::from("Dec 19 2013 @ 15:02 ET"),
     };
@@ -281,14 +373,14 @@ pub fn print_spy_data() {
     ) = get_spy_data();
 
     let data = HistoricalData {
-        call_prices: call_prices,
-        call_strikes: call_strikes,
-        names: names,
-        put_prices: put_prices,
-        put_strikes: put_strikes,
-        spot: spot,
-        years_to_expiry: years_to_expiry,
-        option_name: option_name,
+        call_prices,
+        call_strikes,
+        names,
+        put_prices,
+        put_strikes,
+        spot,
+        years_to_expiry,
+        option_name,
         time,
     };
 
@@ -299,3 +391,208 @@ pub fn print_spy_data() {
 
     write_lines("data/spy.json", json_string);
 }
+
+fn parse_field<T: std::str::FromStr>(field: &str, line_counter: usize) -> Result<T, String> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| format!("line {}: could not parse '{}'", line_counter, field))
+}
+
+// Quote time (see parse_quote_time) and the spot bid and ask from the second line of the file.
+// The .dat layout looks like:
+//   Feb 8 2024 at 11:23 AM EST,497.7,497.71,...
+// and the CSV downloaded from the CBOE dashboard like:
+//   "Date: February 8, 2024 at 11:23 AM EST",Bid: 497.7,Ask: 497.71,...
+fn parse_quote_line(line: &str, line_counter: usize) -> Result<(i64, f32, f32), String> {
+    let (date, fields) = match line.strip_prefix("\"Date: ") {
+        Some(rest) => {
+            let end = rest.find('"').ok_or(format!("line {}: unterminated date", line_counter))?;
+            let fields: Vec<&str> = rest[end + 1..]
+                .split(",")
+                .filter_map(|field| {
+                    field
+                        .strip_prefix("Bid: ")
+                        .or_else(|| field.strip_prefix("Ask: "))
+                })
+                .collect();
+            (&rest[..end], fields)
+        }
+        None => {
+            let split_line = line.split(",").collect::<Vec<&str>>();
+            (split_line[0], split_line[1..].iter().take(2).cloned().collect())
+        }
+    };
+
+    if fields.len() < 2 {
+        return Err(format!("line {}: expected quote date, bid and ask", line_counter));
+    }
+
+    Ok((
+        parse_quote_time(date).ok_or(format!("line {}: bad quote date '{}'", line_counter, date))?,
+        parse_field(fields[0], line_counter)?,
+        parse_field(fields[1], line_counter)?,
+    ))
+}
+
+/*
+    Parses a CBOE quote table into a chain, keeping bid, ask, volume and open interest of every
+    contract so it can be filtered before calculating implied volatility. Accepts the layout of
+    data/SPYQuoteData.dat and the CSV from the CBOE delayed quotes dashboard (see js/parse.js).
+
+    Unlike get_spy_data, which counts whole days to the end of the expiration day, time to expiry
+    is counted from the quote time to the 4:00 PM close of the expiration date.
+*/
+pub fn parse_cboe_chain(contents: &str) -> Result<OptionChain, String> {
+    let mut spot = 0.0;
+    let mut quote_time = 0;
+    let mut quotes: Vec<OptionQuote> = Vec::new();
+
+    let mut line_counter = 0;
+
+    for line in contents.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        line_counter += 1;
+
+        if line_counter == 2 {
+            let (time, bid, ask) = parse_quote_line(line, line_counter)?;
+
+            spot = (bid + ask) / 2.0;
+            quote_time = time;
+        } else if line_counter >= 4 {
+            let split_line = line.split(",").collect::<Vec<&str>>();
+
+            if split_line.len() != 22 {
+                continue;
+            }
+
+            let strike: f32 = parse_field(split_line[11], line_counter)?;
+
+            if strike <= 0.0 {
+                continue;
+            }
+
+            let years_to_expiry = years_to_close(quote_time, split_line[0]).ok_or(
+                format!("line {}: bad expiration date '{}'", line_counter, split_line[0])
+            )?;
+
+            // Calls occupy columns 1-10 and puts columns 12-21, with the strike in between
+            for (option_dir, offset) in [(OptionDir::CALL, 1), (OptionDir::PUT, 12)] {
+                quotes.push(OptionQuote {
+                    option_dir,
+                    name: split_line[offset].to_string(),
+                    strike,
+                    years_to_expiry,
+                    bid: parse_field(split_line[offset + 3], line_counter)?,
+                    ask: parse_field(split_line[offset + 4], line_counter)?,
+                    volume: parse_field(split_line[offset + 5], line_counter)?,
+                    open_interest: parse_field(split_line[offset + 9], line_counter)?,
+                });
+            }
+        }
+    }
+
+    if line_counter < 2 {
+        return Err(String::from("missing quote header"));
+    }
+
+    Ok(OptionChain::new(spot, quotes))
+}
+
+// Parses a chain written by print_appl_data / print_spy_data (data/aapl.json, data/spy.json).
+// Only mids are stored in that format, so bids and asks are set to the mid.
+pub fn parse_json_chain(contents: &str) -> Result<OptionChain, String> {
+    let data: HistoricalData = serde_json::from_str(contents).map_err(|err| err.to_string())?;
+
+    if
+        !(
+            data.call_prices.len() == data.put_prices.len() &&
+            data.put_prices.len() == data.call_strikes.len() &&
+            data.call_strikes.len() == data.years_to_expiry.len()
+        )
+    {
+        return Err(String::from("price, strike and expiry arrays differ in length"));
+    }
+
+    Ok(
+        OptionChain::from_mids(
+            data.spot,
+            &data.call_prices,
+            &data.put_prices,
+            &data.call_strikes,
+            &data.years_to_expiry
+        )
+    )
+}
+
+// Same source as get_spy_data, with the quote and activity of every contract
+pub fn get_spy_chain() -> OptionChain {
+    std::fs::read_to_string("data/SPYQuoteData.dat")
+        .ok()
+        .and_then(|contents| parse_cboe_chain(&contents).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dashboard_csv() {
+        let contents = [
+            "SPDR S&P 500 ETF Trust,Last: 497.71,Change:  -0.39",
+            "\"Date: February 8, 2024 at 11:23 AM EST\",Bid: 497.7,Ask: 497.72,Size: 17*14,\"Volume: 10,598,673\"",
+            "Expiration Date,Calls,Last Sale,Net,Bid,Ask,Volume,IV,Delta,Gamma,Open Interest,Strike,Puts,Last Sale,Net,Bid,Ask,Volume,IV,Delta,Gamma,Open Interest",
+            "",
+            "Fri Feb 16 2024,SPY240216C00490000,8.5,0,8.4,8.6,12,0.15,0.8,0.02,300,490.00,SPY240216P00490000,1.1,0,1.05,1.1,40,0.16,-0.2,0.02,900",
+        ].join("\n");
+
+        let chain = parse_cboe_chain(&contents).unwrap();
+
+        assert!((chain.spot - 497.71).abs() < 1e-4);
+        assert_eq!(chain.len(), 2);
+
+        let put = &chain.quotes[1];
+        assert_eq!(put.option_dir, OptionDir::PUT);
+        assert_eq!(put.name, "SPY240216P00490000");
+        assert_eq!(put.strike, 490.0);
+        assert_eq!((put.bid, put.ask, put.volume, put.open_interest), (1.05, 1.1, 40, 900));
+        // 8 days and 4h 37m from 11:23 AM to the 4:00 PM close
+        assert!((put.years_to_expiry - (8.0 * 1440.0 + 277.0) / 525600.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn calendar_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 3, 1) - days_from_civil(2024, 2, 8), 22);
+        assert_eq!(days_from_civil(2023, 3, 1) - days_from_civil(2023, 2, 8), 21);
+        assert_eq!(days_from_civil(2025, 1, 17) - days_from_civil(2024, 12, 20), 28);
+
+        assert_eq!(parse_quote_time("February 8, 2024 at 12:05 AM EST"), Some(days_from_civil(2024, 2, 8) * 1440 + 5));
+        assert_eq!(parse_quote_time("Feb 8 2024 at 12:05 PM EST"), Some(days_from_civil(2024, 2, 8) * 1440 + 725));
+        assert_eq!(parse_quote_time("Feb 8 2024"), Some(days_from_civil(2024, 2, 8) * 1440));
+        assert_eq!(years_to_close(days_from_civil(2024, 2, 8) * 1440 + 683, "Fri Mar 08 2024"), Some((29.0 * 1440.0 + 277.0) / 525600.0));
+    }
+
+    #[test]
+    fn spy_chain_matches_mids() {
+        let (spot, call_prices, call_strikes, _, _, years_to_expiry, _, _, _) = get_spy_data();
+        let chain = get_spy_chain();
+
+        assert_eq!(chain.spot, spot);
+        assert_eq!(chain.len(), 2 * call_prices.len());
+
+        let (prices, strikes, years) = chain.columns(OptionDir::CALL);
+        assert_eq!(prices, call_prices);
+        assert_eq!(strikes, call_strikes);
+
+        // get_spy_data counts days to the end of the expiration day with approximate month
+        // lengths (get_days_from_jan), so it only agrees to within a week
+        for (years, days_years) in years.iter().zip(years_to_expiry.iter()) {
+            assert!((days_years - years).abs() < 7.0 / 365.0, "Years: {} {}", years, days_years);
+        }
+    }
+}
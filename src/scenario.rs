@@ -0,0 +1,352 @@
+use crate::consts::OptionDir;
+use crate::portfolio::{ self, Position };
+use crate::smile::MIN_VOL;
+use crate::surface::{ self, VolSurface };
+use crate::vol32x8;
+use rayon::prelude::*;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    Full revaluation of a portfolio over a grid of spot and vol scenarios.
+
+    The fitted surface is first shocked as a whole (VolShock), which moves the quadratic of
+    each expiry's smile in log-moneyness x = ln(K / F):
+      - parallel: the same vol shift everywhere
+      - skew: vol shift of skew * x, so a negative skew richens the puts and cheapens the calls
+      - term: vol shift of term at 1 year scaled by sqrt(1 / T), so short expiries move the
+        most as they do in a sell-off
+
+    On top of the shocked surface, each cell of the grid moves spot by a relative shift and all
+    vols by an absolute shift, and reprices every position. Vols are sticky strike: a position
+    keeps the shocked surface's vol for its strike when spot moves. The P&L of a cell is its
+    value minus the portfolio value today, on the unshocked surface.
+
+    All cells of a direction are priced in one call of the SIMD pricer, which splits the work
+    across threads.
+*/
+
+const TERM_REFERENCE_YEARS: f32 = 1.0;
+
+/// Whole-surface vol shock, in vol units (0.01 = 1 vol point)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VolShock {
+    pub parallel: f32,
+    pub skew: f32,
+    pub term: f32,
+}
+
+/// P&L of every scenario, where row i holds every spot shift at vol_shifts[i]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScenarioGrid {
+    pub spot_shifts: Vec<f32>,
+    pub vol_shifts: Vec<f32>,
+    pub base_value: f32,
+    pub pnl: Vec<Vec<f32>>,
+}
+
+pub fn shock_surface(surface: &VolSurface, shock: &VolShock) -> VolSurface {
+    let smiles = surface.smiles
+        .iter()
+        .map(|smile| {
+            let term = shock.term * (TERM_REFERENCE_YEARS / smile.years_to_expiry).sqrt();
+            let [c0, c1, c2] = smile.coefficients;
+
+            let mut shocked = smile.clone();
+            shocked.coefficients = [c0 + shock.parallel + term, c1 + shock.skew, c2];
+            shocked
+        })
+        .collect();
+
+    VolSurface { spot: surface.spot, smiles }
+}
+
+pub fn scenario_grid(
+    surface: &VolSurface,
+    positions: &[Position],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    spot_shifts: &[f32],
+    vol_shifts: &[f32],
+    shock: &VolShock
+) -> ScenarioGrid {
+    let base_value = portfolio::portfolio_risk(surface, positions, risk_free_rate, dividend_yield).total.value;
+    let shocked = shock_surface(surface, shock);
+    let num_cells = spot_shifts.len() * vol_shifts.len();
+
+    if num_cells == 0 {
+        return ScenarioGrid {
+            spot_shifts: spot_shifts.to_vec(),
+            vol_shifts: vol_shifts.to_vec(),
+            base_value,
+            pnl: Vec::new(),
+        };
+    }
+
+    let mut value = vec![0.0; num_cells];
+
+    for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+        let legs: Vec<&Position> = positions
+            .iter()
+            .filter(|position| position.option_dir == option_dir)
+            .collect();
+
+        if legs.is_empty() {
+            continue;
+        }
+
+        let vols: Vec<f32> = legs
+            .iter()
+            .map(|leg| shocked.vol(leg.strike, leg.years_to_expiry))
+            .collect();
+
+        // One option per (vol shift, spot shift, leg), in that order
+        let n = num_cells * legs.len();
+        let mut spot = Vec::with_capacity(n);
+        let mut strike = Vec::with_capacity(n);
+        let mut volatility = Vec::with_capacity(n);
+        let mut years_to_expiry = Vec::with_capacity(n);
+
+        for vol_shift in vol_shifts.iter() {
+            for spot_shift in spot_shifts.iter() {
+                for (leg, vol) in legs.iter().zip(vols.iter()) {
+                    // Comparing instead of max keeps NaN vols NaN
+                    let shifted = vol + vol_shift;
+
+                    spot.push(shocked.spot * (1.0 + spot_shift));
+                    strike.push(leg.strike);
+                    volatility.push(if shifted < MIN_VOL { MIN_VOL } else { shifted });
+                    years_to_expiry.push(leg.years_to_expiry);
+                }
+            }
+        }
+
+        let prices = vol32x8::price(
+            option_dir,
+            &spot,
+            &strike,
+            &volatility,
+            &vec![risk_free_rate; n],
+            &vec![dividend_yield; n],
+            &years_to_expiry
+        );
+
+        value
+            .par_iter_mut()
+            .zip(prices.par_chunks(legs.len()))
+            .for_each(|(cell, prices)| {
+                *cell += prices
+                    .iter()
+                    .zip(legs.iter())
+                    .map(|(price, leg)| price * leg.quantity * leg.multiplier)
+                    .sum::<f32>();
+            });
+    }
+
+    ScenarioGrid {
+        spot_shifts: spot_shifts.to_vec(),
+        vol_shifts: vol_shifts.to_vec(),
+        base_value,
+        pnl: value
+            .chunks(spot_shifts.len())
+            .map(|row| row.iter().map(|value| value - base_value).collect())
+            .collect(),
+    }
+}
+
+/*
+    Frontend entry point for a P&L heatmap. Fits the surface and reads the positions as in
+    portfolio::portfolio_json, then returns the scenario grid as JSON. Spot shifts are relative
+    (0.05 = +5%) and vol shifts absolute (0.01 = +1 vol point). Returns an empty grid for
+    invalid columns.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn scenario_grid_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    option_dir: &[i32],
+    position_strike: &[f32],
+    position_years_to_expiry: &[f32],
+    quantity: &[f32],
+    multiplier: &[f32],
+    spot_shifts: &[f32],
+    vol_shifts: &[f32],
+    parallel: f32,
+    skew: f32,
+    term: f32
+) -> String {
+    let empty = || serde_json::to_string(&ScenarioGrid::default()).unwrap_or_else(|_| String::from("{}"));
+
+    let positions = portfolio::positions_from_columns(
+        option_dir,
+        position_strike,
+        position_years_to_expiry,
+        quantity,
+        multiplier
+    );
+    let positions = match positions {
+        Some(positions) => positions,
+        None => {
+            return empty();
+        }
+    };
+
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return empty();
+        }
+    };
+    let shock = VolShock { parallel, skew, term };
+
+    let grid = scenario_grid(
+        &surface,
+        &positions,
+        risk_free_rate,
+        dividend_yield,
+        spot_shifts,
+        vol_shifts,
+        &shock
+    );
+
+    serde_json::to_string(&grid).unwrap_or_else(|_| empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::fixtures::{ self, flat_surface, position };
+
+    #[test]
+    fn shocks_move_the_surface() {
+        let surface = flat_surface(0.2);
+
+        let parallel = shock_surface(&surface, &VolShock { parallel: 0.05, ..VolShock::default() });
+        assert!((parallel.vol(90.0, 0.5) - 0.25).abs() < 1e-6);
+
+        // Rotates around the forward
+        let skew = shock_surface(&surface, &VolShock { skew: -0.1, ..VolShock::default() });
+        let x = 0.2;
+        assert!((skew.vol(100.0 * f32::exp(x), 1.0) - (0.2 - 0.1 * x)).abs() < 1e-5);
+        assert!((skew.vol(100.0, 1.0) - 0.2).abs() < 1e-6);
+
+        // Twice the shift at a quarter of the reference expiry
+        let term = shock_surface(&surface, &VolShock { term: 0.01, ..VolShock::default() });
+        assert!((term.vol(100.0, 0.25) - 0.22).abs() < 1e-5);
+        assert!((term.vol(100.0, 1.0) - 0.21).abs() < 1e-5);
+
+        assert_eq!(shock_surface(&surface, &VolShock::default()), surface);
+    }
+
+    #[test]
+    fn grid_matches_repricing() {
+        let surface = flat_surface(0.2);
+        let positions = [
+            position(OptionDir::CALL, 105.0, 2.0),
+            position(OptionDir::PUT, 95.0, -3.0),
+            position(OptionDir::PUT, 100.0, 1.0),
+        ];
+        let spot_shifts = [-0.1, -0.02, 0.0, 0.03, 0.1];
+        let vol_shifts = [-0.05, 0.0, 0.04];
+
+        let grid = scenario_grid(&surface, &positions, 0.03, 0.01, &spot_shifts, &vol_shifts, &VolShock::default());
+
+        let value = |spot: f32, vol: f32| -> f32 {
+            positions
+                .iter()
+                .map(|p| {
+                    bs::price(p.option_dir, spot, p.strike, vol, 0.03, 0.01, p.years_to_expiry) *
+                        p.quantity *
+                        p.multiplier
+                })
+                .sum()
+        };
+
+        assert_eq!(grid.pnl.len(), vol_shifts.len());
+        assert!((grid.base_value - value(100.0, 0.2)).abs() < 0.1);
+
+        for (i, vol_shift) in vol_shifts.iter().enumerate() {
+            assert_eq!(grid.pnl[i].len(), spot_shifts.len());
+
+            for (j, spot_shift) in spot_shifts.iter().enumerate() {
+                let expected = value(100.0 * (1.0 + spot_shift), 0.2 + vol_shift) - value(100.0, 0.2);
+                let pnl = grid.pnl[i][j];
+
+                assert!((pnl - expected).abs() < 0.5, "Vol: {} Spot: {} Got: {}, Expected: {}", vol_shift, spot_shift, pnl, expected);
+            }
+        }
+
+        // No move, no P&L
+        assert!(grid.pnl[1][2].abs() < 1e-3);
+    }
+
+    #[test]
+    fn long_straddle_is_long_gamma_and_vega() {
+        let surface = flat_surface(0.2);
+        let positions = [position(OptionDir::CALL, 100.0, 1.0), position(OptionDir::PUT, 100.0, 1.0)];
+        let grid = scenario_grid(&surface, &positions, 0.03, 0.0, &[-0.1, 0.0, 0.1], &[0.0, 0.05], &VolShock::default());
+
+        assert!(grid.pnl[0][0] > 0.0 && grid.pnl[0][2] > 0.0);
+        assert!(grid.pnl[1][1] > 0.0);
+
+        // A parallel shock is the same as a vol shift
+        let shocked = scenario_grid(
+            &surface,
+            &positions,
+            0.03,
+            0.0,
+            &[-0.1, 0.0, 0.1],
+            &[0.0],
+            &VolShock { parallel: 0.05, ..VolShock::default() }
+        );
+        assert!((shocked.pnl[0][1] - grid.pnl[1][1]).abs() < 1e-3);
+    }
+
+    #[test]
+    fn empty_inputs() {
+        let surface = flat_surface(0.2);
+        let positions = [position(OptionDir::CALL, 100.0, 1.0)];
+
+        assert!(scenario_grid(&surface, &positions, 0.03, 0.0, &[], &[0.0], &VolShock::default()).pnl.is_empty());
+        assert_eq!(scenario_grid(&surface, &[], 0.03, 0.0, &[0.1], &[0.0, 0.1], &VolShock::default()).pnl, vec![vec![0.0], vec![0.0]]);
+
+        let json = scenario_grid_json(&[1.0], &[1.0], 100.0, &[100.0], &[0.5], 0.03, 0.0, &[3], &[100.0], &[0.5], &[1.0], &[100.0], &[0.0], &[0.0], 0.0, 0.0, 0.0);
+        assert_eq!(json, serde_json::to_string(&ScenarioGrid::default()).unwrap());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let [call_price, put_price, strike, years_to_expiry] = fixtures::flat_chain(5);
+        let surface = surface::fit_mids(&call_price, &put_price, 100.0, &strike, &years_to_expiry, 0.03).unwrap();
+        let shock = VolShock { parallel: 0.01, skew: -0.1, term: 0.02 };
+        let grid = scenario_grid(&surface, &[position(OptionDir::CALL, 100.0, 1.0)], 0.03, 0.0, &[-0.05, 0.0, 0.05], &[0.0, 0.01], &shock);
+
+        let json = scenario_grid_json(
+            &call_price,
+            &put_price,
+            100.0,
+            &strike,
+            &years_to_expiry,
+            0.03,
+            0.0,
+            &[OptionDir::CALL as i32],
+            &[100.0],
+            &[0.5],
+            &[1.0],
+            &[100.0],
+            &[-0.05, 0.0, 0.05],
+            &[0.0, 0.01],
+            0.01,
+            -0.1,
+            0.02
+        );
+        assert_eq!(json, serde_json::to_string(&grid).unwrap());
+    }
+}
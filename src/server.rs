@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use serde::Serialize;
+use serde_json::json;
+use tiny_http::{ Header, Method, Response, Server };
+use crate::chain::OptionChain;
+use crate::filter::{ self, FilterConfig };
+use crate::localvol;
+use crate::metrics;
+use crate::read_hist;
+use crate::smile;
+use crate::surface;
+
+/*
+    Local HTTP/JSON service, so dashboards can run the SIMD and multithreaded kernels natively
+    instead of through single-threaded WASM. One chain is held at a time:
+
+      GET  /health            liveness check
+      POST /chain             upload a CBOE quote table or a JSON chain, replacing the current one
+                              (query: min_bid, max_spread, otm_only)
+      GET  /chain             spot, number of contracts and expiries of the current chain
+      GET  /iv                implied volatility of every contract
+      GET  /greeks            implied volatility and Greeks of every contract
+      GET  /surface           fitted surface and its implied and Dupire local vols on a strike x
+                              expiry grid
+                              (query: strikes, years as comma separated lists)
+      GET  /metrics           smile metrics per expiry
+
+    The GET endpoints take rate (default: implied by put-call parity), dividend_yield,
+    max_iterations and threshold as query parameters. Errors are returned as {"error": ...}.
+*/
+
+const DEFAULT_MAX_ITERATIONS: i32 = 20;
+const DEFAULT_THRESHOLD: f32 = 0.0001;
+const BLEND_WIDTH: f32 = 0.01;
+const NUM_GRID_STRIKES: usize = 41;
+
+#[derive(Serialize)]
+struct IvRow<'a> {
+    name: &'a str,
+    option_type: &'static str,
+    strike: f32,
+    years_to_expiry: f32,
+    mid: f32,
+    implied_vol: f32,
+}
+
+/// Chain shared between requests
+#[derive(Default)]
+pub struct ServerState {
+    chain: RwLock<Option<OptionChain>>,
+}
+
+struct Params {
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    max_iterations: i32,
+    threshold: f32,
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "error": message }).to_string())
+}
+
+fn to_json<T: Serialize>(value: &T) -> (u16, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, body),
+        Err(err) => error(500, &err.to_string()),
+    }
+}
+
+// Only numbers, commas and flags are expected, so %2C is the only escape decoded
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (key.to_string(), value.replace("%2C", ",").replace("%2c", ","))
+        })
+        .collect()
+}
+
+fn query_number<T: std::str::FromStr>(
+    query: &HashMap<String, String>,
+    key: &str
+) -> Result<Option<T>, String> {
+    match query.get(key) {
+        Some(value) => value.parse().map(Some).map_err(|_| format!("invalid {} '{}'", key, value)),
+        None => Ok(None),
+    }
+}
+
+fn query_list(query: &HashMap<String, String>, key: &str) -> Result<Option<Vec<f32>>, String> {
+    match query.get(key) {
+        Some(value) =>
+            value
+                .split(',')
+                .map(|item| item.parse().map_err(|_| format!("invalid {} '{}'", key, item)))
+                .collect::<Result<Vec<f32>, String>>()
+                .map(Some),
+        None => Ok(None),
+    }
+}
+
+fn params(chain: &OptionChain, query: &HashMap<String, String>) -> Result<Params, String> {
+    Ok(Params {
+        risk_free_rate: match query_number(query, "rate")? {
+            Some(rate) => rate,
+            None => chain.parity_interest_rate(),
+        },
+        dividend_yield: query_number(query, "dividend_yield")?.unwrap_or(0.0),
+        max_iterations: query_number(query, "max_iterations")?.unwrap_or(DEFAULT_MAX_ITERATIONS),
+        threshold: query_number(query, "threshold")?.unwrap_or(DEFAULT_THRESHOLD),
+    })
+}
+
+fn upload_chain(
+    state: &ServerState,
+    query: &HashMap<String, String>,
+    body: &str
+) -> Result<(u16, String), String> {
+    let chain = if body.trim_start().starts_with('{') {
+        read_hist::parse_json_chain(body)?
+    } else {
+        read_hist::parse_cboe_chain(body)?
+    };
+
+    let config = FilterConfig {
+        min_bid: query_number(query, "min_bid")?.unwrap_or(0.0),
+        max_relative_spread: query_number(query, "max_spread")?.unwrap_or(f32::INFINITY),
+        otm_only: query.get("otm_only").is_some_and(|value| value != "false"),
+        ..FilterConfig::default()
+    };
+    let (chain, report) = filter::filter_chain(&chain, &config);
+    let summary = chain_summary(&chain, report.dropped.len());
+
+    *state.chain.write().map_err(|err| err.to_string())? = Some(chain);
+
+    Ok(summary)
+}
+
+fn chain_summary(chain: &OptionChain, num_dropped: usize) -> (u16, String) {
+    to_json(
+        &json!({
+            "spot": chain.spot,
+            "contracts": chain.len(),
+            "dropped": num_dropped,
+            "expiries": chain.expiries(),
+            "parity_rate": chain.parity_interest_rate(),
+        })
+    )
+}
+
+fn surface_response(
+    chain: &OptionChain,
+    params: &Params,
+    query: &HashMap<String, String>
+) -> Result<(u16, String), String> {
+    let surface = surface::fit_surface(
+        chain,
+        params.risk_free_rate,
+        params.max_iterations,
+        params.threshold
+    );
+
+    // Default grid spans the quoted expiries and +/- 20% around the spot
+    let strikes = query_list(query, "strikes")?.unwrap_or_else(|| {
+        (0..NUM_GRID_STRIKES)
+            .map(|i| chain.spot * (0.8 + (0.4 * (i as f32)) / ((NUM_GRID_STRIKES - 1) as f32)))
+            .collect()
+    });
+    let years = query_list(query, "years")?.unwrap_or_else(|| {
+        surface.smiles
+            .iter()
+            .map(|smile| smile.years_to_expiry)
+            .collect()
+    });
+    let vols = surface.grid(&strikes, &years);
+    let local_vols = localvol::local_vol_grid(&surface, &strikes, &years);
+
+    Ok(
+        to_json(
+            &json!({
+                "spot": surface.spot,
+                "smiles": surface.smiles,
+                "strikes": strikes,
+                "years_to_expiry": years,
+                "vols": vols,
+                "local_vols": local_vols,
+            })
+        )
+    )
+}
+
+fn route(
+    state: &ServerState,
+    method: &str,
+    path: &str,
+    query: &HashMap<String, String>,
+    body: &str
+) -> Result<(u16, String), String> {
+    match (method, path) {
+        ("GET", "/health") => {
+            return Ok(to_json(&json!({ "status": "ok" })));
+        }
+        ("POST", "/chain") => {
+            return upload_chain(state, query, body);
+        }
+        ("GET", "/chain" | "/iv" | "/greeks" | "/surface" | "/metrics") => {}
+        (_, "/health" | "/chain" | "/iv" | "/greeks" | "/surface" | "/metrics") => {
+            return Ok(error(405, "method not allowed"));
+        }
+        _ => {
+            return Ok(error(404, "not found"));
+        }
+    }
+
+    let guard = state.chain.read().map_err(|err| err.to_string())?;
+
+    let chain = match guard.as_ref() {
+        Some(chain) => chain,
+        None => {
+            return Ok(error(409, "no chain uploaded, POST one to /chain first"));
+        }
+    };
+
+    if path == "/chain" {
+        return Ok(chain_summary(chain, 0));
+    }
+
+    let params = params(chain, query)?;
+
+    match path {
+        "/iv" | "/greeks" => {
+            let rows = chain.analytics(
+                params.risk_free_rate,
+                params.dividend_yield,
+                params.max_iterations,
+                params.threshold
+            );
+
+            if path == "/greeks" {
+                return Ok(to_json(&rows));
+            }
+
+            let rows: Vec<IvRow> = rows
+                .iter()
+                .map(|row| IvRow {
+                    name: &row.name,
+                    option_type: row.option_type,
+                    strike: row.strike,
+                    years_to_expiry: row.years_to_expiry,
+                    mid: row.mid,
+                    implied_vol: row.implied_vol,
+                })
+                .collect();
+
+            Ok(to_json(&rows))
+        }
+        "/surface" => surface_response(chain, &params, query),
+        _ => {
+            let smiles = smile::otm_smiles(
+                chain,
+                params.risk_free_rate,
+                BLEND_WIDTH,
+                params.max_iterations,
+                params.threshold
+            );
+
+            Ok(to_json(&metrics::metrics_table(&smiles)))
+        }
+    }
+}
+
+// Returns the status code and JSON body for one request, independently of the transport
+pub fn handle(state: &ServerState, method: &str, url: &str, body: &str) -> (u16, String) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    match route(state, method, path, &parse_query(query), body) {
+        Ok(response) => response,
+        Err(err) => error(400, &err),
+    }
+}
+
+// Serves requests on addr (e.g. "127.0.0.1:8080") until the process exits
+pub fn serve(addr: &str) -> Result<(), String> {
+    let server = Server::http(addr).map_err(|err| err.to_string())?;
+    let state = ServerState::default();
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let allow_origin = Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap();
+    let allow_headers = Header::from_bytes("Access-Control-Allow-Headers", "Content-Type").unwrap();
+
+    for mut request in server.incoming_requests() {
+        let (status, body) = if request.method() == &Method::Options {
+            // CORS preflight from dashboards served on another origin
+            (204, String::new())
+        } else {
+            let mut body = String::new();
+
+            match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => handle(&state, request.method().as_str(), request.url(), &body),
+                Err(err) => error(400, &err.to_string()),
+            }
+        };
+
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type.clone())
+            .with_header(allow_origin.clone())
+            .with_header(allow_headers.clone());
+
+        if let Err(err) = request.respond(response) {
+            eprintln!("simd-vol-server: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spy_state() -> ServerState {
+        let state = ServerState::default();
+        let contents = std::fs::read_to_string("data/SPYQuoteData.dat").unwrap();
+        let (status, _) = handle(&state, "POST", "/chain?max_spread=0.5", &contents);
+
+        assert_eq!(status, 200);
+        state
+    }
+
+    #[test]
+    fn requires_chain() {
+        let state = ServerState::default();
+
+        assert_eq!(handle(&state, "GET", "/health", "").0, 200);
+        assert_eq!(handle(&state, "GET", "/iv", "").0, 409);
+        assert_eq!(handle(&state, "GET", "/unknown", "").0, 404);
+        assert_eq!(handle(&state, "DELETE", "/chain", "").0, 405);
+        assert_eq!(handle(&state, "POST", "/chain", "not a chain").0, 400);
+    }
+
+    #[test]
+    fn spy_endpoints() {
+        let state = spy_state();
+
+        let (status, body) = handle(&state, "GET", "/chain", "");
+        let summary: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(status, 200);
+        assert!(summary["contracts"].as_u64().unwrap() > 0);
+
+        let (status, body) = handle(&state, "GET", "/greeks?rate=0.05", "");
+        let rows: Vec<serde_json::Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(rows.len() as u64, summary["contracts"].as_u64().unwrap());
+        assert!(rows[0]["delta"].is_number());
+
+        let (status, body) = handle(&state, "GET", "/surface?rate=0.05&strikes=480,500&years=0.1", "");
+        let surface: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(status, 200);
+        let vol = surface["vols"][0][1].as_f64().unwrap();
+        assert!(vol > 0.03 && vol < 1.0, "Vol: {}", vol);
+        assert_eq!(surface["local_vols"][0].as_array().unwrap().len(), 2);
+
+        let (status, body) = handle(&state, "GET", "/metrics?rate=0.05", "");
+        assert_eq!(status, 200);
+        assert!(body.contains("risk_reversal_25"));
+
+        assert_eq!(handle(&state, "GET", "/iv?rate=abc", "").0, 400);
+    }
+}
@@ -0,0 +1,1639 @@
+use std::ops::{ Add, Div, Mul, Neg, Sub };
+use std::sync::OnceLock;
+
+/*
+    Lane-generic SIMD with runtime CPU feature detection.
+
+    The kernels in bs32x8 are written against the Simd trait, whose required methods are only
+    exactly rounded IEEE operations (+, -, *, /, sqrt), comparisons and bitwise masks. The
+    transcendental functions (exp, ln, acos, cos) are built from those primitives below instead
+    of the platform's math library, and multiply-adds are never fused, so every backend computes
+    bit for bit the same result as the scalar one whatever its lane width.
+
+    On x86_64 the widest of AVX-512 (16 lanes a register), AVX2 (8) and SSE2 (4) supported by
+    the CPU is picked at runtime, on aarch64 NEON (4), and on wasm32 simd128 (4) when the module
+    is built with +simd128. Everything else uses the scalar backend. The SIMD_VOL_BACKEND environment
+    variable (scalar, sse, avx2, avx512, neon, simd128) overrides the choice, and falls back to
+    the detected backend if the CPU does not support the requested one.
+
+    Kernels are run over slices with map and map_into, which split the options into blocks
+    for rayon and call the kernel on every vector of a block from a #[target_feature] function,
+    so the backend's instructions are inlined into the kernel. The SIMD backends work on two
+    registers at a time (see Pair), so Backend::lanes is twice the register width.
+
+    The larger functions (exp, ln, acos, cos and those in bs32x8) are only forced inline in
+    release builds. Unoptimized, a kernel inlined whole gives every temporary its own stack slot
+    and overflows the 2 MB stack of a rayon thread with the wider backends.
+*/
+
+// Options per rayon task
+const BLOCK_LEN: usize = 256;
+
+// Widest backend (a pair of AVX-512 registers), used to pad the last vector of a slice
+pub(crate) const MAX_LANES: usize = 32;
+
+// 0, 1, 2, ... loaded to build lane masks
+const LANE_INDEX: [f32; MAX_LANES] = {
+    let mut index = [0.0; MAX_LANES];
+    let mut idx = 0;
+    while idx < MAX_LANES {
+        index[idx] = idx as f32;
+        idx += 1;
+    }
+    index
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Scalar,
+    Sse,
+    Avx2,
+    Avx512,
+    Neon,
+    Simd128,
+}
+
+// Widest first
+const PREFERENCE: [Backend; 6] = [
+    Backend::Avx512,
+    Backend::Avx2,
+    Backend::Neon,
+    Backend::Simd128,
+    Backend::Sse,
+    Backend::Scalar,
+];
+
+impl Backend {
+    // Lanes per block, the kernels run a Pair of vector registers on every backend but Scalar
+    pub fn lanes(self) -> usize {
+        match self {
+            Backend::Scalar => 1,
+            Backend::Sse | Backend::Neon | Backend::Simd128 => 8,
+            Backend::Avx2 => 16,
+            Backend::Avx512 => 32,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::Scalar => "scalar",
+            Backend::Sse => "sse",
+            Backend::Avx2 => "avx2",
+            Backend::Avx512 => "avx512",
+            Backend::Neon => "neon",
+            Backend::Simd128 => "simd128",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Backend> {
+        PREFERENCE.into_iter().find(|backend| backend.name() == name)
+    }
+
+    pub fn is_supported(self) -> bool {
+        match self {
+            Backend::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Sse => true,
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(target_arch = "x86_64")]
+            Backend::Avx512 => is_x86_feature_detected!("avx512f"),
+            #[cfg(target_arch = "aarch64")]
+            Backend::Neon => std::arch::is_aarch64_feature_detected!("neon"),
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            Backend::Simd128 => true,
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+// Widest backend supported by this CPU
+pub fn detected() -> Backend {
+    PREFERENCE
+        .into_iter()
+        .find(|backend| backend.is_supported())
+        .unwrap_or(Backend::Scalar)
+}
+
+// Backend used by the kernels: SIMD_VOL_BACKEND if it is supported, otherwise detected()
+pub fn backend() -> Backend {
+    static BACKEND: OnceLock<Backend> = OnceLock::new();
+
+    *BACKEND.get_or_init(|| {
+        std::env
+            ::var("SIMD_VOL_BACKEND")
+            .ok()
+            .and_then(|name| Backend::from_name(name.trim()))
+            .filter(|backend| backend.is_supported())
+            .unwrap_or_else(detected)
+    })
+}
+
+/*
+    Vector of LANES f32 values. Comparisons return masks with every bit of a lane set where
+    they hold, and every other method treats masks the same way.
+*/
+pub(crate) trait Simd: Copy +
+    Add<Output = Self> +
+    Sub<Output = Self> +
+    Mul<Output = Self> +
+    Div<Output = Self> +
+    Neg<Output = Self>
+{
+    const LANES: usize;
+
+    fn splat(value: f32) -> Self;
+
+    // Reads or writes the first LANES values
+    fn load(values: &[f32]) -> Self;
+    fn store(self, out: &mut [f32]);
+
+    fn sqrt(self) -> Self;
+
+    // Ordered comparisons, false for NaN, except cmp_ne which is true for NaN
+    fn cmp_lt(self, other: Self) -> Self;
+    fn cmp_le(self, other: Self) -> Self;
+    fn cmp_eq(self, other: Self) -> Self;
+    fn cmp_ne(self, other: Self) -> Self;
+
+    fn and(self, other: Self) -> Self;
+    fn or(self, other: Self) -> Self;
+    fn xor(self, other: Self) -> Self;
+    // self & !other
+    fn and_not(self, other: Self) -> Self;
+
+    // Lanes of t where the mask self is set, otherwise lanes of f
+    fn blend(self, t: Self, f: Self) -> Self;
+    fn all(self) -> bool;
+
+    // 2^self for integer valued lanes in [-126, 127]
+    fn pow2i(self) -> Self;
+    // (m, e) with self = m 2^e and m in [0.5, 1) for positive normal numbers
+    fn frexp(self) -> (Self, Self);
+
+    // Pads with padding past the end of values
+    #[inline(always)]
+    fn load_partial(values: &[f32], padding: f32) -> Self {
+        if values.len() >= Self::LANES {
+            return Self::load(values);
+        }
+
+        let mut padded = [padding; MAX_LANES];
+        padded[..values.len()].copy_from_slice(values);
+        Self::load(&padded)
+    }
+
+    // Mask of the first len lanes
+    #[inline(always)]
+    fn lane_mask(len: usize) -> Self {
+        Self::load(&LANE_INDEX).cmp_lt(Self::splat(len as f32))
+    }
+
+    #[inline(always)]
+    fn store_partial(self, out: &mut [f32]) {
+        if out.len() >= Self::LANES {
+            return self.store(out);
+        }
+
+        let mut padded = [0.0; MAX_LANES];
+        self.store(&mut padded);
+        let len = out.len();
+        out.copy_from_slice(&padded[..len]);
+    }
+
+    #[inline(always)]
+    fn cmp_gt(self, other: Self) -> Self {
+        other.cmp_lt(self)
+    }
+
+    #[inline(always)]
+    fn cmp_ge(self, other: Self) -> Self {
+        other.cmp_le(self)
+    }
+
+    #[inline(always)]
+    fn is_nan(self) -> Self {
+        self.cmp_ne(self)
+    }
+
+    #[inline(always)]
+    fn abs(self) -> Self {
+        self.and_not(Self::splat(-0.0))
+    }
+
+    // NaN in self is kept, NaN in other is ignored
+    #[inline(always)]
+    fn min(self, other: Self) -> Self {
+        other.cmp_lt(self).blend(other, self)
+    }
+
+    #[inline(always)]
+    fn max(self, other: Self) -> Self {
+        self.cmp_lt(other).blend(other, self)
+    }
+
+    // self * m + a, rounded twice
+    #[inline(always)]
+    fn mul_add(self, m: Self, a: Self) -> Self {
+        self * m + a
+    }
+
+    // Round half to even for |self| < 2^22
+    #[inline(always)]
+    fn round(self) -> Self {
+        let magic = Self::splat(12582912.0);
+        self + magic - magic
+    }
+
+    // Cephes expf. Saturates at exp(-87.3) and exp(88) instead of 0 and infinity.
+    #[cfg_attr(not(debug_assertions), inline(always))]
+    fn exp(self) -> Self {
+        self.exp_sum(Self::splat(0.0))
+    }
+
+    /*
+        exp(self + lo) without rounding the sum, for |lo| small next to 0.35 (half of ln 2). lo
+        is only added to the reduced argument, so a large self loses none of lo's bits.
+    */
+    #[cfg_attr(not(debug_assertions), inline(always))]
+    #[allow(clippy::excessive_precision)]
+    fn exp_sum(self, lo: Self) -> Self {
+        let x = self.max(Self::splat(-87.3)).min(Self::splat(88.0));
+        let n = ((x + lo) * Self::splat(std::f32::consts::LOG2_E)).round();
+        let r = x - n * Self::splat(0.693359375) - n * Self::splat(-2.12194440e-4) + lo;
+
+        let p = Self::splat(1.9875691500e-4)
+            .mul_add(r, Self::splat(1.3981999507e-3))
+            .mul_add(r, Self::splat(8.3334519073e-3))
+            .mul_add(r, Self::splat(4.1665795894e-2))
+            .mul_add(r, Self::splat(1.6666665459e-1))
+            .mul_add(r, Self::splat(5.0000001201e-1));
+        let y = p * r * r + r + Self::splat(1.0);
+
+        y * n.pow2i()
+    }
+
+    // Cephes logf, ignoring subnormals. NaN for negative numbers and -infinity at zero.
+    #[cfg_attr(not(debug_assertions), inline(always))]
+    #[allow(clippy::excessive_precision)]
+    fn ln(self) -> Self {
+        let (m, e) = self.frexp();
+
+        // Shift m into [sqrt(0.5), sqrt(2)) so the polynomial is centered on 1
+        let small = m.cmp_lt(Self::splat(std::f32::consts::FRAC_1_SQRT_2));
+        let e = e - small.and(Self::splat(1.0));
+        let x = small.blend(m + m, m) - Self::splat(1.0);
+        let z = x * x;
+
+        let p = Self::splat(7.0376836292e-2)
+            .mul_add(x, Self::splat(-1.151461031e-1))
+            .mul_add(x, Self::splat(1.167699874e-1))
+            .mul_add(x, Self::splat(-1.2420140846e-1))
+            .mul_add(x, Self::splat(1.4249322787e-1))
+            .mul_add(x, Self::splat(-1.6668057665e-1))
+            .mul_add(x, Self::splat(2.0000714765e-1))
+            .mul_add(x, Self::splat(-2.4999993993e-1))
+            .mul_add(x, Self::splat(3.3333331174e-1));
+        let y = p * x * z + e * Self::splat(-2.12194440e-4) - Self::splat(0.5) * z;
+        let res = x + y + e * Self::splat(0.693359375);
+
+        let res = self.cmp_eq(Self::splat(0.0)).blend(Self::splat(f32::NEG_INFINITY), res);
+        let res = self.cmp_eq(Self::splat(f32::INFINITY)).blend(self, res);
+        self.cmp_lt(Self::splat(0.0)).or(self.is_nan()).blend(Self::splat(f32::NAN), res)
+    }
+
+    // Cephes acosf, NaN outside of [-1, 1]
+    #[cfg_attr(not(debug_assertions), inline(always))]
+    #[allow(clippy::excessive_precision)]
+    fn acos(self) -> Self {
+        let a = self.abs();
+        let wide = a.cmp_gt(Self::splat(0.5));
+        let negative = self.cmp_lt(Self::splat(0.0));
+
+        // asin(s) for s = sqrt((1 - |x|) / 2) past 0.5, otherwise s = |x|
+        let z = wide.blend(Self::splat(0.5) * (Self::splat(1.0) - a), a * a);
+        let s = wide.blend(z.sqrt(), a);
+        let p = Self::splat(4.2163199048e-2)
+            .mul_add(z, Self::splat(2.4181311049e-2))
+            .mul_add(z, Self::splat(4.5470025998e-2))
+            .mul_add(z, Self::splat(7.4953002686e-2))
+            .mul_add(z, Self::splat(1.6666752422e-1));
+        let asin = s + s * z * p;
+
+        let pi = Self::splat(std::f32::consts::PI);
+        let half_pi = Self::splat(std::f32::consts::FRAC_PI_2);
+        let near = half_pi - negative.blend(-asin, asin);
+        let far = negative.blend(pi - (asin + asin), asin + asin);
+
+        wide.blend(far, near)
+    }
+
+    // Cephes cosf after reducing self by multiples of pi / 2, accurate for |self| < 1e4
+    #[cfg_attr(not(debug_assertions), inline(always))]
+    #[allow(clippy::excessive_precision)]
+    fn cos(self) -> Self {
+        let n = (self * Self::splat(std::f32::consts::FRAC_2_PI)).round();
+        let r =
+            self -
+            n * Self::splat(1.5703125) -
+            n * Self::splat(4.837512969970703125e-4) -
+            n * Self::splat(7.54978995489188216e-8);
+        let z = r * r;
+
+        let cos = Self::splat(2.443315711809948e-5)
+            .mul_add(z, Self::splat(-1.388731625493765e-3))
+            .mul_add(z, Self::splat(4.166664568298827e-2)) * z * z -
+            Self::splat(0.5) * z +
+            Self::splat(1.0);
+        let sin =
+            Self::splat(-1.9515295891e-4)
+                .mul_add(z, Self::splat(8.3321608736e-3))
+                .mul_add(z, Self::splat(-1.6666654611e-1)) * z * r +
+            r;
+
+        // Quadrant n mod 4: cos(r), -sin(r), -cos(r), sin(r)
+        let quarter = (n * Self::splat(0.25)).round();
+        let quarter = quarter.cmp_gt(n * Self::splat(0.25)).blend(quarter - Self::splat(1.0), quarter);
+        let quadrant = n - Self::splat(4.0) * quarter;
+        let odd = quadrant.cmp_eq(Self::splat(1.0)).or(quadrant.cmp_eq(Self::splat(3.0)));
+        let negate = quadrant.cmp_eq(Self::splat(1.0)).or(quadrant.cmp_eq(Self::splat(2.0)));
+        let res = odd.blend(sin, cos);
+
+        negate.blend(-res, res)
+    }
+}
+
+/*
+    Function of I input vectors to O output vectors, run over slices by map and map_into.
+    Implementations should be #[inline(always)] so the backend's instructions inline into them.
+
+    Slices are cut into vectors of V::LANES options. When their length is not a multiple of it,
+    the lanes of the last vector past the end are inactive: their inputs are PADDING, their
+    outputs start at zero, and what the kernel writes to them is dropped.
+*/
+pub(crate) trait Kernel<const I: usize, const O: usize>: Sync {
+    // Inputs of the inactive lanes, chosen so the kernel computes finite values from them
+    const PADDING: [f32; I];
+
+    // active is the mask of the lanes that hold options from the slices. outputs holds the
+    // current contents of the output slices, zeros for map.
+    fn apply<V: Simd>(&self, active: V, inputs: [V; I], outputs: &mut [V; O]);
+}
+
+// Kernel over slices of equal length into newly allocated outputs
+pub(crate) fn map<K: Kernel<I, O>, const I: usize, const O: usize>(
+    kernel: &K,
+    inputs: [&[f32]; I]
+) -> [Vec<f32>; O] {
+    let len = inputs.first().map_or(0, |input| input.len());
+    let mut outputs: [Vec<f32>; O] = std::array::from_fn(|_| vec![0.0; len]);
+
+    map_into(kernel, inputs, outputs.each_mut().map(|output| output.as_mut_slice()));
+
+    outputs
+}
+
+pub(crate) fn map_into<K: Kernel<I, O>, const I: usize, const O: usize>(
+    kernel: &K,
+    inputs: [&[f32]; I],
+    outputs: [&mut [f32]; O]
+) {
+    map_into_with(backend(), kernel, inputs, outputs)
+}
+
+/*
+    map_into on a given backend, which falls back to the scalar one if the CPU does not support
+    it. Every slice must have the same length.
+*/
+pub(crate) fn map_into_with<K: Kernel<I, O>, const I: usize, const O: usize>(
+    backend: Backend,
+    kernel: &K,
+    inputs: [&[f32]; I],
+    outputs: [&mut [f32]; O]
+) {
+    let len = outputs.first().map_or(0, |output| output.len());
+
+    assert!(
+        inputs.iter().all(|input| input.len() == len) &&
+            outputs.iter().all(|output| output.len() == len),
+        "Kernel slices must have the same length"
+    );
+
+    let backend = if backend.is_supported() { backend } else { Backend::Scalar };
+
+    map_blocks(backend, kernel, inputs, outputs);
+}
+
+/*
+    Halves the slices at a block boundary until a single block is left, running both halves
+    in parallel. Splitting in place rather than collecting the blocks keeps map_into free of
+    allocations, which would grow wasm memory and detach the views of buffer::ChainBuffer.
+*/
+fn map_blocks<K: Kernel<I, O>, const I: usize, const O: usize>(
+    backend: Backend,
+    kernel: &K,
+    inputs: [&[f32]; I],
+    outputs: [&mut [f32]; O]
+) {
+    let len = outputs.first().map_or(0, |output| output.len());
+
+    if len <= BLOCK_LEN {
+        let mut outputs = outputs;
+        run(backend, kernel, inputs, &mut outputs);
+        return;
+    }
+
+    let mid = (len.div_ceil(BLOCK_LEN) / 2) * BLOCK_LEN;
+    let left_inputs = inputs.map(|input| &input[..mid]);
+    let right_inputs = inputs.map(|input| &input[mid..]);
+
+    let mut right_outputs: [&mut [f32]; O] = std::array::from_fn(|_| Default::default());
+    let mut left_parts = outputs
+        .into_iter()
+        .zip(right_outputs.iter_mut())
+        .map(|(output, right)| {
+            let (left, rest) = output.split_at_mut(mid);
+            *right = rest;
+            left
+        });
+    let left_outputs: [&mut [f32]; O] = std::array::from_fn(|_| left_parts.next().unwrap());
+    drop(left_parts);
+
+    rayon::join(
+        || map_blocks(backend, kernel, left_inputs, left_outputs),
+        || map_blocks(backend, kernel, right_inputs, right_outputs)
+    );
+}
+
+fn run<K: Kernel<I, O>, const I: usize, const O: usize>(
+    backend: Backend,
+    kernel: &K,
+    inputs: [&[f32]; I],
+    outputs: &mut [&mut [f32]; O]
+) {
+    // Safety: map_into_with only passes backends the CPU supports
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx512 => unsafe { x86::run_avx512(kernel, inputs, outputs) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 => unsafe { x86::run_avx2(kernel, inputs, outputs) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse => run_block::<Pair<x86::Sse>, K, I, O>(kernel, inputs, outputs),
+        #[cfg(target_arch = "aarch64")]
+        Backend::Neon => run_block::<Pair<neon::Neon>, K, I, O>(kernel, inputs, outputs),
+        #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+        Backend::Simd128 => run_block::<Pair<wasm::Simd128>, K, I, O>(kernel, inputs, outputs),
+        _ => run_block::<Portable<1>, K, I, O>(kernel, inputs, outputs),
+    }
+}
+
+#[inline(always)]
+fn run_block<V: Simd, K: Kernel<I, O>, const I: usize, const O: usize>(
+    kernel: &K,
+    inputs: [&[f32]; I],
+    outputs: &mut [&mut [f32]; O]
+) {
+    let len = outputs.first().map_or(0, |output| output.len());
+    let all_active = V::lane_mask(V::LANES);
+    let mut start_idx = 0;
+
+    while start_idx < len {
+        let end_idx = std::cmp::min(len, start_idx + V::LANES);
+        let active = if end_idx - start_idx == V::LANES { all_active } else { V::lane_mask(end_idx - start_idx) };
+
+        let mut vector_inputs = [V::splat(0.0); I];
+        for ((vector, input), padding) in vector_inputs.iter_mut().zip(inputs.iter()).zip(K::PADDING) {
+            *vector = V::load_partial(&input[start_idx..end_idx], padding);
+        }
+
+        let mut vector_outputs = [V::splat(0.0); O];
+        for (vector, output) in vector_outputs.iter_mut().zip(outputs.iter()) {
+            *vector = V::load_partial(&output[start_idx..end_idx], 0.0);
+        }
+
+        kernel.apply(active, vector_inputs, &mut vector_outputs);
+
+        for (vector, output) in vector_outputs.iter().zip(outputs.iter_mut()) {
+            vector.store_partial(&mut output[start_idx..end_idx]);
+        }
+
+        start_idx = end_idx;
+    }
+}
+
+macro_rules! impl_ops {
+    ($simd:ident, $add:ident, $sub:ident, $mul:ident, $div:ident) => {
+        impl std::ops::Add for $simd {
+            type Output = Self;
+
+            #[inline(always)]
+            #[allow(unused_unsafe)]
+            fn add(self, other: Self) -> Self {
+                unsafe { $simd($add(self.0, other.0)) }
+            }
+        }
+
+        impl std::ops::Sub for $simd {
+            type Output = Self;
+
+            #[inline(always)]
+            #[allow(unused_unsafe)]
+            fn sub(self, other: Self) -> Self {
+                unsafe { $simd($sub(self.0, other.0)) }
+            }
+        }
+
+        impl std::ops::Mul for $simd {
+            type Output = Self;
+
+            #[inline(always)]
+            #[allow(unused_unsafe)]
+            fn mul(self, other: Self) -> Self {
+                unsafe { $simd($mul(self.0, other.0)) }
+            }
+        }
+
+        impl std::ops::Div for $simd {
+            type Output = Self;
+
+            #[inline(always)]
+            #[allow(unused_unsafe)]
+            fn div(self, other: Self) -> Self {
+                unsafe { $simd($div(self.0, other.0)) }
+            }
+        }
+
+        impl std::ops::Neg for $simd {
+            type Output = Self;
+
+            #[inline(always)]
+            fn neg(self) -> Self {
+                self.xor(<$simd as Simd>::splat(-0.0))
+            }
+        }
+    };
+}
+
+/*
+    Plain arrays, one f32 operation per lane. Portable<1> is the scalar backend and the
+    reference the SIMD backends are tested against.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Portable<const N: usize>(pub(crate) [f32; N]);
+
+const MASK: u32 = u32::MAX;
+
+impl<const N: usize> Portable<N> {
+    #[inline(always)]
+    fn map(self, f: impl Fn(f32) -> f32) -> Self {
+        Portable(self.0.map(f))
+    }
+
+    #[inline(always)]
+    fn zip(self, other: Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        Portable(std::array::from_fn(|idx| f(self.0[idx], other.0[idx])))
+    }
+
+    #[inline(always)]
+    fn zip_bits(self, other: Self, f: impl Fn(u32, u32) -> u32) -> Self {
+        self.zip(other, |a, b| f32::from_bits(f(a.to_bits(), b.to_bits())))
+    }
+
+    #[inline(always)]
+    fn mask(self, other: Self, f: impl Fn(f32, f32) -> bool) -> Self {
+        self.zip(other, |a, b| f32::from_bits(if f(a, b) { MASK } else { 0 }))
+    }
+}
+
+impl<const N: usize> From<f32> for Portable<N> {
+    fn from(value: f32) -> Self {
+        Portable([value; N])
+    }
+}
+
+impl<const N: usize> Add for Portable<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self.zip(other, |a, b| a + b)
+    }
+}
+
+impl<const N: usize> Sub for Portable<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        self.zip(other, |a, b| a - b)
+    }
+}
+
+impl<const N: usize> Mul for Portable<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, other: Self) -> Self {
+        self.zip(other, |a, b| a * b)
+    }
+}
+
+impl<const N: usize> Div for Portable<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn div(self, other: Self) -> Self {
+        self.zip(other, |a, b| a / b)
+    }
+}
+
+impl<const N: usize> Neg for Portable<N> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn neg(self) -> Self {
+        self.map(|a| -a)
+    }
+}
+
+impl<const N: usize> Simd for Portable<N> {
+    const LANES: usize = N;
+
+    #[inline(always)]
+    fn splat(value: f32) -> Self {
+        Portable([value; N])
+    }
+
+    #[inline(always)]
+    fn load(values: &[f32]) -> Self {
+        Portable(std::array::from_fn(|idx| values[idx]))
+    }
+
+    #[inline(always)]
+    fn store(self, out: &mut [f32]) {
+        out[..N].copy_from_slice(&self.0);
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        self.map(f32::sqrt)
+    }
+
+    #[inline(always)]
+    fn cmp_lt(self, other: Self) -> Self {
+        self.mask(other, |a, b| a < b)
+    }
+
+    #[inline(always)]
+    fn cmp_le(self, other: Self) -> Self {
+        self.mask(other, |a, b| a <= b)
+    }
+
+    #[inline(always)]
+    fn cmp_eq(self, other: Self) -> Self {
+        self.mask(other, |a, b| a == b)
+    }
+
+    #[inline(always)]
+    fn cmp_ne(self, other: Self) -> Self {
+        self.mask(other, |a, b| a != b)
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        self.zip_bits(other, |a, b| a & b)
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        self.zip_bits(other, |a, b| a | b)
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        self.zip_bits(other, |a, b| a ^ b)
+    }
+
+    #[inline(always)]
+    fn and_not(self, other: Self) -> Self {
+        self.zip_bits(other, |a, b| a & !b)
+    }
+
+    #[inline(always)]
+    fn blend(self, t: Self, f: Self) -> Self {
+        Portable(std::array::from_fn(|idx| if self.0[idx].to_bits() != 0 { t.0[idx] } else { f.0[idx] }))
+    }
+
+    #[inline(always)]
+    fn all(self) -> bool {
+        self.0.iter().all(|mask| mask.to_bits() != 0)
+    }
+
+    #[inline(always)]
+    fn pow2i(self) -> Self {
+        self.map(|n| f32::from_bits((((n as i32) + 127) << 23) as u32))
+    }
+
+    #[inline(always)]
+    fn frexp(self) -> (Self, Self) {
+        let m = self.map(|x| f32::from_bits((x.to_bits() & 0x807fffff) | 0x3f000000));
+        let e = self.map(|x| ((((x.to_bits() >> 23) & 0xff) as i32) - 126) as f32);
+
+        (m, e)
+    }
+}
+
+/*
+    Two vectors processed side by side. The 128-bit backends run as pairs, as the latency of
+    one chain of dependent operations hides behind the other.
+*/
+#[derive(Clone, Copy)]
+pub(crate) struct Pair<V>(V, V);
+
+impl<V: Simd> Add for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        Pair(self.0 + other.0, self.1 + other.1)
+    }
+}
+
+impl<V: Simd> Sub for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        Pair(self.0 - other.0, self.1 - other.1)
+    }
+}
+
+impl<V: Simd> Mul for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, other: Self) -> Self {
+        Pair(self.0 * other.0, self.1 * other.1)
+    }
+}
+
+impl<V: Simd> Div for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn div(self, other: Self) -> Self {
+        Pair(self.0 / other.0, self.1 / other.1)
+    }
+}
+
+impl<V: Simd> Neg for Pair<V> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn neg(self) -> Self {
+        Pair(-self.0, -self.1)
+    }
+}
+
+impl<V: Simd> Simd for Pair<V> {
+    const LANES: usize = 2 * V::LANES;
+
+    #[inline(always)]
+    fn splat(value: f32) -> Self {
+        Pair(V::splat(value), V::splat(value))
+    }
+
+    #[inline(always)]
+    fn load(values: &[f32]) -> Self {
+        Pair(V::load(values), V::load(&values[V::LANES..]))
+    }
+
+    #[inline(always)]
+    fn store(self, out: &mut [f32]) {
+        self.0.store(out);
+        self.1.store(&mut out[V::LANES..]);
+    }
+
+    #[inline(always)]
+    fn sqrt(self) -> Self {
+        Pair(self.0.sqrt(), self.1.sqrt())
+    }
+
+    #[inline(always)]
+    fn cmp_lt(self, other: Self) -> Self {
+        Pair(self.0.cmp_lt(other.0), self.1.cmp_lt(other.1))
+    }
+
+    #[inline(always)]
+    fn cmp_le(self, other: Self) -> Self {
+        Pair(self.0.cmp_le(other.0), self.1.cmp_le(other.1))
+    }
+
+    #[inline(always)]
+    fn cmp_eq(self, other: Self) -> Self {
+        Pair(self.0.cmp_eq(other.0), self.1.cmp_eq(other.1))
+    }
+
+    #[inline(always)]
+    fn cmp_ne(self, other: Self) -> Self {
+        Pair(self.0.cmp_ne(other.0), self.1.cmp_ne(other.1))
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        Pair(self.0.and(other.0), self.1.and(other.1))
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        Pair(self.0.or(other.0), self.1.or(other.1))
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        Pair(self.0.xor(other.0), self.1.xor(other.1))
+    }
+
+    #[inline(always)]
+    fn and_not(self, other: Self) -> Self {
+        Pair(self.0.and_not(other.0), self.1.and_not(other.1))
+    }
+
+    #[inline(always)]
+    fn blend(self, t: Self, f: Self) -> Self {
+        Pair(self.0.blend(t.0, f.0), self.1.blend(t.1, f.1))
+    }
+
+    #[inline(always)]
+    fn all(self) -> bool {
+        self.0.all() && self.1.all()
+    }
+
+    #[inline(always)]
+    fn pow2i(self) -> Self {
+        Pair(self.0.pow2i(), self.1.pow2i())
+    }
+
+    #[inline(always)]
+    fn frexp(self) -> (Self, Self) {
+        let (m0, e0) = self.0.frexp();
+        let (m1, e1) = self.1.frexp();
+
+        (Pair(m0, m1), Pair(e0, e1))
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::{ run_block, Kernel, Pair, Simd };
+    use std::arch::x86_64::*;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Sse(__m128);
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Avx2(__m256);
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Avx512(__m512);
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn run_avx2<K: Kernel<I, O>, const I: usize, const O: usize>(
+        kernel: &K,
+        inputs: [&[f32]; I],
+        outputs: &mut [&mut [f32]; O]
+    ) {
+        run_block::<Pair<Avx2>, K, I, O>(kernel, inputs, outputs)
+    }
+
+    #[target_feature(enable = "avx512f")]
+    pub(super) unsafe fn run_avx512<K: Kernel<I, O>, const I: usize, const O: usize>(
+        kernel: &K,
+        inputs: [&[f32]; I],
+        outputs: &mut [&mut [f32]; O]
+    ) {
+        run_block::<Pair<Avx512>, K, I, O>(kernel, inputs, outputs)
+    }
+
+    // SSE2 is part of the x86_64 baseline
+    impl_ops!(Sse, _mm_add_ps, _mm_sub_ps, _mm_mul_ps, _mm_div_ps);
+
+    #[allow(unused_unsafe)]
+    impl Simd for Sse {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        fn splat(value: f32) -> Self {
+            unsafe { Sse(_mm_set1_ps(value)) }
+        }
+
+        #[inline(always)]
+        fn load(values: &[f32]) -> Self {
+            let values = &values[..4];
+            unsafe { Sse(_mm_loadu_ps(values.as_ptr())) }
+        }
+
+        #[inline(always)]
+        fn store(self, out: &mut [f32]) {
+            let out = &mut out[..4];
+            unsafe { _mm_storeu_ps(out.as_mut_ptr(), self.0) }
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            unsafe { Sse(_mm_sqrt_ps(self.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_lt(self, other: Self) -> Self {
+            unsafe { Sse(_mm_cmplt_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_le(self, other: Self) -> Self {
+            unsafe { Sse(_mm_cmple_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_eq(self, other: Self) -> Self {
+            unsafe { Sse(_mm_cmpeq_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_ne(self, other: Self) -> Self {
+            unsafe { Sse(_mm_cmpneq_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn and(self, other: Self) -> Self {
+            unsafe { Sse(_mm_and_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn or(self, other: Self) -> Self {
+            unsafe { Sse(_mm_or_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            unsafe { Sse(_mm_xor_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn and_not(self, other: Self) -> Self {
+            unsafe { Sse(_mm_andnot_ps(other.0, self.0)) }
+        }
+
+        #[inline(always)]
+        fn blend(self, t: Self, f: Self) -> Self {
+            unsafe { Sse(_mm_or_ps(_mm_and_ps(self.0, t.0), _mm_andnot_ps(self.0, f.0))) }
+        }
+
+        #[inline(always)]
+        fn all(self) -> bool {
+            unsafe { _mm_movemask_ps(self.0) == 0xf }
+        }
+
+        #[inline(always)]
+        fn pow2i(self) -> Self {
+            unsafe {
+                let exponent = _mm_add_epi32(_mm_cvtps_epi32(self.0), _mm_set1_epi32(127));
+                Sse(_mm_castsi128_ps(_mm_slli_epi32::<23>(exponent)))
+            }
+        }
+
+        #[inline(always)]
+        fn frexp(self) -> (Self, Self) {
+            unsafe {
+                let bits = _mm_castps_si128(self.0);
+                let mantissa = _mm_or_si128(
+                    _mm_and_si128(bits, _mm_set1_epi32(0x807fffffu32 as i32)),
+                    _mm_set1_epi32(0x3f000000)
+                );
+                let exponent = _mm_sub_epi32(
+                    _mm_srli_epi32::<23>(_mm_and_si128(bits, _mm_set1_epi32(0x7f800000))),
+                    _mm_set1_epi32(126)
+                );
+
+                (Sse(_mm_castsi128_ps(mantissa)), Sse(_mm_cvtepi32_ps(exponent)))
+            }
+        }
+    }
+
+    impl_ops!(Avx2, _mm256_add_ps, _mm256_sub_ps, _mm256_mul_ps, _mm256_div_ps);
+
+    // Only created inside run_avx2, after the CPU was checked for AVX2
+    impl Simd for Avx2 {
+        const LANES: usize = 8;
+
+        #[inline(always)]
+        fn splat(value: f32) -> Self {
+            unsafe { Avx2(_mm256_set1_ps(value)) }
+        }
+
+        #[inline(always)]
+        fn load(values: &[f32]) -> Self {
+            let values = &values[..8];
+            unsafe { Avx2(_mm256_loadu_ps(values.as_ptr())) }
+        }
+
+        #[inline(always)]
+        fn store(self, out: &mut [f32]) {
+            let out = &mut out[..8];
+            unsafe { _mm256_storeu_ps(out.as_mut_ptr(), self.0) }
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            unsafe { Avx2(_mm256_sqrt_ps(self.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_lt(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_cmp_ps::<_CMP_LT_OQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_le(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_cmp_ps::<_CMP_LE_OQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_eq(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_cmp_ps::<_CMP_EQ_OQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_ne(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_cmp_ps::<_CMP_NEQ_UQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn and(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_and_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn or(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_or_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_xor_ps(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn and_not(self, other: Self) -> Self {
+            unsafe { Avx2(_mm256_andnot_ps(other.0, self.0)) }
+        }
+
+        #[inline(always)]
+        fn blend(self, t: Self, f: Self) -> Self {
+            unsafe { Avx2(_mm256_blendv_ps(f.0, t.0, self.0)) }
+        }
+
+        #[inline(always)]
+        fn all(self) -> bool {
+            unsafe { _mm256_movemask_ps(self.0) == 0xff }
+        }
+
+        #[inline(always)]
+        fn pow2i(self) -> Self {
+            unsafe {
+                let exponent = _mm256_add_epi32(_mm256_cvtps_epi32(self.0), _mm256_set1_epi32(127));
+                Avx2(_mm256_castsi256_ps(_mm256_slli_epi32::<23>(exponent)))
+            }
+        }
+
+        #[inline(always)]
+        fn frexp(self) -> (Self, Self) {
+            unsafe {
+                let bits = _mm256_castps_si256(self.0);
+                let mantissa = _mm256_or_si256(
+                    _mm256_and_si256(bits, _mm256_set1_epi32(0x807fffffu32 as i32)),
+                    _mm256_set1_epi32(0x3f000000)
+                );
+                let exponent = _mm256_sub_epi32(
+                    _mm256_srli_epi32::<23>(_mm256_and_si256(bits, _mm256_set1_epi32(0x7f800000))),
+                    _mm256_set1_epi32(126)
+                );
+
+                (Avx2(_mm256_castsi256_ps(mantissa)), Avx2(_mm256_cvtepi32_ps(exponent)))
+            }
+        }
+    }
+
+    impl_ops!(Avx512, _mm512_add_ps, _mm512_sub_ps, _mm512_mul_ps, _mm512_div_ps);
+
+    impl Avx512 {
+        // AVX-512F compares into bit masks, expanded here to the lane masks Simd works with
+        #[inline(always)]
+        fn from_mask(mask: __mmask16) -> Self {
+            unsafe { Avx512(_mm512_castsi512_ps(_mm512_maskz_mov_epi32(mask, _mm512_set1_epi32(-1)))) }
+        }
+
+        #[inline(always)]
+        fn to_mask(self) -> __mmask16 {
+            unsafe {
+                let bits = _mm512_castps_si512(self.0);
+                _mm512_test_epi32_mask(bits, bits)
+            }
+        }
+
+        #[inline(always)]
+        fn to_bits(self) -> __m512i {
+            unsafe { _mm512_castps_si512(self.0) }
+        }
+
+        #[inline(always)]
+        fn from_bits(bits: __m512i) -> Self {
+            unsafe { Avx512(_mm512_castsi512_ps(bits)) }
+        }
+    }
+
+    // Only created inside run_avx512, after the CPU was checked for AVX-512F
+    impl Simd for Avx512 {
+        const LANES: usize = 16;
+
+        #[inline(always)]
+        fn splat(value: f32) -> Self {
+            unsafe { Avx512(_mm512_set1_ps(value)) }
+        }
+
+        #[inline(always)]
+        fn load(values: &[f32]) -> Self {
+            let values = &values[..16];
+            unsafe { Avx512(_mm512_loadu_ps(values.as_ptr())) }
+        }
+
+        #[inline(always)]
+        fn store(self, out: &mut [f32]) {
+            let out = &mut out[..16];
+            unsafe { _mm512_storeu_ps(out.as_mut_ptr(), self.0) }
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            unsafe { Avx512(_mm512_sqrt_ps(self.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_lt(self, other: Self) -> Self {
+            unsafe { Self::from_mask(_mm512_cmp_ps_mask::<_CMP_LT_OQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_le(self, other: Self) -> Self {
+            unsafe { Self::from_mask(_mm512_cmp_ps_mask::<_CMP_LE_OQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_eq(self, other: Self) -> Self {
+            unsafe { Self::from_mask(_mm512_cmp_ps_mask::<_CMP_EQ_OQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_ne(self, other: Self) -> Self {
+            unsafe { Self::from_mask(_mm512_cmp_ps_mask::<_CMP_NEQ_UQ>(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn and(self, other: Self) -> Self {
+            unsafe { Self::from_bits(_mm512_and_si512(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn or(self, other: Self) -> Self {
+            unsafe { Self::from_bits(_mm512_or_si512(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            unsafe { Self::from_bits(_mm512_xor_si512(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn and_not(self, other: Self) -> Self {
+            unsafe { Self::from_bits(_mm512_andnot_si512(other.to_bits(), self.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn blend(self, t: Self, f: Self) -> Self {
+            unsafe { Avx512(_mm512_mask_blend_ps(self.to_mask(), f.0, t.0)) }
+        }
+
+        #[inline(always)]
+        fn all(self) -> bool {
+            self.to_mask() == 0xffff
+        }
+
+        #[inline(always)]
+        fn pow2i(self) -> Self {
+            unsafe {
+                let exponent = _mm512_add_epi32(_mm512_cvtps_epi32(self.0), _mm512_set1_epi32(127));
+                Avx512(_mm512_castsi512_ps(_mm512_slli_epi32::<23>(exponent)))
+            }
+        }
+
+        #[inline(always)]
+        fn frexp(self) -> (Self, Self) {
+            unsafe {
+                let bits = _mm512_castps_si512(self.0);
+                let mantissa = _mm512_or_si512(
+                    _mm512_and_si512(bits, _mm512_set1_epi32(0x807fffffu32 as i32)),
+                    _mm512_set1_epi32(0x3f000000)
+                );
+                let exponent = _mm512_sub_epi32(
+                    _mm512_srli_epi32::<23>(_mm512_and_si512(bits, _mm512_set1_epi32(0x7f800000))),
+                    _mm512_set1_epi32(126)
+                );
+
+                (Avx512(_mm512_castsi512_ps(mantissa)), Avx512(_mm512_cvtepi32_ps(exponent)))
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use super::Simd;
+    use std::arch::aarch64::*;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Neon(float32x4_t);
+
+    // NEON is part of the aarch64 baseline
+    impl_ops!(Neon, vaddq_f32, vsubq_f32, vmulq_f32, vdivq_f32);
+
+    impl Neon {
+        #[inline(always)]
+        fn from_bits(bits: uint32x4_t) -> Self {
+            unsafe { Neon(vreinterpretq_f32_u32(bits)) }
+        }
+
+        #[inline(always)]
+        fn to_bits(self) -> uint32x4_t {
+            unsafe { vreinterpretq_u32_f32(self.0) }
+        }
+    }
+
+    #[allow(unused_unsafe)]
+    impl Simd for Neon {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        fn splat(value: f32) -> Self {
+            unsafe { Neon(vdupq_n_f32(value)) }
+        }
+
+        #[inline(always)]
+        fn load(values: &[f32]) -> Self {
+            let values = &values[..4];
+            unsafe { Neon(vld1q_f32(values.as_ptr())) }
+        }
+
+        #[inline(always)]
+        fn store(self, out: &mut [f32]) {
+            let out = &mut out[..4];
+            unsafe { vst1q_f32(out.as_mut_ptr(), self.0) }
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            unsafe { Neon(vsqrtq_f32(self.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_lt(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vcltq_f32(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_le(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vcleq_f32(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_eq(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vceqq_f32(self.0, other.0)) }
+        }
+
+        #[inline(always)]
+        fn cmp_ne(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vmvnq_u32(vceqq_f32(self.0, other.0))) }
+        }
+
+        #[inline(always)]
+        fn and(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vandq_u32(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn or(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vorrq_u32(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            unsafe { Self::from_bits(veorq_u32(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn and_not(self, other: Self) -> Self {
+            unsafe { Self::from_bits(vbicq_u32(self.to_bits(), other.to_bits())) }
+        }
+
+        #[inline(always)]
+        fn blend(self, t: Self, f: Self) -> Self {
+            unsafe { Neon(vbslq_f32(self.to_bits(), t.0, f.0)) }
+        }
+
+        #[inline(always)]
+        fn all(self) -> bool {
+            unsafe { vminvq_u32(self.to_bits()) != 0 }
+        }
+
+        #[inline(always)]
+        fn pow2i(self) -> Self {
+            unsafe {
+                let exponent = vaddq_s32(vcvtnq_s32_f32(self.0), vdupq_n_s32(127));
+                Neon(vreinterpretq_f32_s32(vshlq_n_s32::<23>(exponent)))
+            }
+        }
+
+        #[inline(always)]
+        fn frexp(self) -> (Self, Self) {
+            unsafe {
+                let bits = self.to_bits();
+                let mantissa = vorrq_u32(vandq_u32(bits, vdupq_n_u32(0x807fffff)), vdupq_n_u32(0x3f000000));
+                let exponent = vsubq_s32(
+                    vreinterpretq_s32_u32(vshrq_n_u32::<23>(vandq_u32(bits, vdupq_n_u32(0x7f800000)))),
+                    vdupq_n_s32(126)
+                );
+
+                (Self::from_bits(mantissa), Neon(vcvtq_f32_s32(exponent)))
+            }
+        }
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm {
+    use super::Simd;
+    use std::arch::wasm32::*;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Simd128(v128);
+
+    impl_ops!(Simd128, f32x4_add, f32x4_sub, f32x4_mul, f32x4_div);
+
+    #[allow(unused_unsafe)]
+    impl Simd for Simd128 {
+        const LANES: usize = 4;
+
+        #[inline(always)]
+        fn splat(value: f32) -> Self {
+            Simd128(f32x4_splat(value))
+        }
+
+        #[inline(always)]
+        fn load(values: &[f32]) -> Self {
+            let values = &values[..4];
+            unsafe { Simd128(v128_load(values.as_ptr() as *const v128)) }
+        }
+
+        #[inline(always)]
+        fn store(self, out: &mut [f32]) {
+            let out = &mut out[..4];
+            unsafe { v128_store(out.as_mut_ptr() as *mut v128, self.0) }
+        }
+
+        #[inline(always)]
+        fn sqrt(self) -> Self {
+            Simd128(f32x4_sqrt(self.0))
+        }
+
+        #[inline(always)]
+        fn cmp_lt(self, other: Self) -> Self {
+            Simd128(f32x4_lt(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn cmp_le(self, other: Self) -> Self {
+            Simd128(f32x4_le(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn cmp_eq(self, other: Self) -> Self {
+            Simd128(f32x4_eq(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn cmp_ne(self, other: Self) -> Self {
+            Simd128(f32x4_ne(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn and(self, other: Self) -> Self {
+            Simd128(v128_and(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn or(self, other: Self) -> Self {
+            Simd128(v128_or(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn xor(self, other: Self) -> Self {
+            Simd128(v128_xor(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn and_not(self, other: Self) -> Self {
+            Simd128(v128_andnot(self.0, other.0))
+        }
+
+        #[inline(always)]
+        fn blend(self, t: Self, f: Self) -> Self {
+            Simd128(v128_bitselect(t.0, f.0, self.0))
+        }
+
+        #[inline(always)]
+        fn all(self) -> bool {
+            i32x4_all_true(self.0)
+        }
+
+        #[inline(always)]
+        fn pow2i(self) -> Self {
+            let exponent = i32x4_add(i32x4_trunc_sat_f32x4(self.0), i32x4_splat(127));
+            Simd128(i32x4_shl(exponent, 23))
+        }
+
+        #[inline(always)]
+        fn frexp(self) -> (Self, Self) {
+            let mantissa = v128_or(v128_and(self.0, u32x4_splat(0x807fffff)), u32x4_splat(0x3f000000));
+            let exponent = i32x4_sub(u32x4_shr(v128_and(self.0, u32x4_splat(0x7f800000)), 23), i32x4_splat(126));
+
+            (Simd128(mantissa), Simd128(f32x4_convert_i32x4(exponent)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // exp, ln, acos and cos of every value through a kernel
+    struct MathKernel;
+
+    impl Kernel<1, 4> for MathKernel {
+        const PADDING: [f32; 1] = [1.0];
+
+        #[inline(always)]
+        fn apply<V: Simd>(&self, _active: V, [x]: [V; 1], outputs: &mut [V; 4]) {
+            *outputs = [x.exp(), x.ln(), x.acos(), x.cos()];
+        }
+    }
+
+    fn math_with(backend: Backend, values: &[f32]) -> [Vec<f32>; 4] {
+        let mut outputs: [Vec<f32>; 4] = std::array::from_fn(|_| vec![0.0; values.len()]);
+        map_into_with(backend, &MathKernel, [values], outputs.each_mut().map(|output| output.as_mut_slice()));
+        outputs
+    }
+
+    #[test]
+    fn math_accuracy() {
+        let values: Vec<f32> = (-1000..=1000).map(|i| (i as f32) * 0.01).collect();
+        let [exp, ln, acos, cos] = math_with(Backend::Scalar, &values);
+
+        for (idx, x) in values.iter().enumerate() {
+            let relative = |actual: f32, expected: f32| (actual - expected).abs() / expected.abs().max(1e-6);
+
+            assert!(relative(exp[idx], x.exp()) < 1e-6, "exp({}) = {}", x, exp[idx]);
+            assert!(relative(cos[idx], x.cos()) < 1e-5 || (cos[idx] - x.cos()).abs() < 1e-6, "cos({}) = {}", x, cos[idx]);
+
+            if *x > 0.0 {
+                assert!((ln[idx] - x.ln()).abs() < 1e-6, "ln({}) = {}", x, ln[idx]);
+            } else if *x < 0.0 {
+                assert!(ln[idx].is_nan());
+            } else {
+                assert_eq!(ln[idx], f32::NEG_INFINITY);
+            }
+
+            if x.abs() <= 1.0 {
+                assert!((acos[idx] - x.acos()).abs() < 1e-6, "acos({}) = {}", x, acos[idx]);
+            } else {
+                assert!(acos[idx].is_nan());
+            }
+        }
+
+        let [exp, ln, ..] = math_with(Backend::Scalar, &[f32::NAN, f32::INFINITY, 200.0, -200.0]);
+        assert!(exp[0].is_nan() && ln[0].is_nan());
+        assert_eq!(ln[1], f32::INFINITY);
+        assert_eq!(exp[2], 88f32.exp());
+        assert!(exp[3] > 0.0 && exp[3] < 1e-37);
+    }
+
+    #[test]
+    fn backends_match_scalar() {
+        // Odd length to go through the padded last vector of every backend
+        let values: Vec<f32> = (-333..=333).map(|i| (i as f32) * 0.037).collect();
+        let expected = math_with(Backend::Scalar, &values);
+
+        for backend in PREFERENCE.into_iter().filter(|backend| backend.is_supported()) {
+            let actual = math_with(backend, &values);
+
+            for (actual, expected) in actual.iter().zip(expected.iter()) {
+                for (a, e) in actual.iter().zip(expected.iter()) {
+                    assert!(a.to_bits() == e.to_bits() || (a.is_nan() && e.is_nan()), "{:?}: {} != {}", backend, a, e);
+                }
+            }
+        }
+    }
+
+    // Adds its input to the outputs' previous contents, and counts the vectors where the
+    // active mask is not exactly the lanes without padding
+    struct RemainderKernel {
+        mismatched: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Kernel<1, 2> for RemainderKernel {
+        const PADDING: [f32; 1] = [-1.0];
+
+        #[inline(always)]
+        fn apply<V: Simd>(&self, active: V, [x]: [V; 1], outputs: &mut [V; 2]) {
+            let padded = x.cmp_eq(V::splat(-1.0));
+            if !padded.xor(active).all() {
+                self.mismatched.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+
+            *outputs = [outputs[0] + x, active.and(V::splat(1.0))];
+        }
+    }
+
+    #[test]
+    fn every_remainder_length() {
+        for backend in PREFERENCE.into_iter().filter(|backend| backend.is_supported()) {
+            // Every remainder of every lane width, up to three of the widest vectors
+            for len in 0..=3 * MAX_LANES {
+                let kernel = RemainderKernel { mismatched: Default::default() };
+                let input: Vec<f32> = (0..len).map(|idx| idx as f32).collect();
+
+                // Past len is not part of the slices and must not be written
+                let mut sum = vec![7.0; len + MAX_LANES];
+                let mut active = vec![7.0; len + MAX_LANES];
+                map_into_with(backend, &kernel, [&input], [&mut sum[..len], &mut active[..len]]);
+
+                assert_eq!(kernel.mismatched.into_inner(), 0, "{:?} len {}", backend, len);
+
+                for idx in 0..len {
+                    assert_eq!(sum[idx], idx as f32 + 7.0, "{:?} len {} idx {}", backend, len, idx);
+                    assert_eq!(active[idx], 1.0, "{:?} len {} idx {}", backend, len, idx);
+                }
+
+                assert!(sum[len..].iter().chain(active[len..].iter()).all(|value| *value == 7.0), "{:?} len {}", backend, len);
+            }
+        }
+    }
+
+    // Records the lanes of the vector type it is run with
+    struct LanesKernel {
+        lanes: std::sync::atomic::AtomicUsize,
+    }
+
+    impl Kernel<1, 1> for LanesKernel {
+        const PADDING: [f32; 1] = [0.0];
+
+        #[inline(always)]
+        fn apply<V: Simd>(&self, _active: V, [x]: [V; 1], outputs: &mut [V; 1]) {
+            self.lanes.store(V::LANES, std::sync::atomic::Ordering::Relaxed);
+            *outputs = [x];
+        }
+    }
+
+    #[test]
+    fn lanes_of_dispatched_vector() {
+        for backend in PREFERENCE.into_iter().filter(|backend| backend.is_supported()) {
+            let kernel = LanesKernel { lanes: Default::default() };
+            let input = [0.0; 1];
+            let mut output = [0.0; 1];
+            map_into_with(backend, &kernel, [&input], [&mut output]);
+
+            assert_eq!(kernel.lanes.into_inner(), backend.lanes(), "{:?}", backend);
+        }
+    }
+
+    #[test]
+    fn backend_names() {
+        for backend in PREFERENCE {
+            assert_eq!(Backend::from_name(backend.name()), Some(backend));
+        }
+
+        assert!(Backend::Scalar.is_supported());
+        assert!(detected().is_supported());
+        assert!(backend().is_supported());
+        assert_eq!(Backend::from_name("mmx"), None);
+    }
+}
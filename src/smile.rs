@@ -0,0 +1,338 @@
+use crate::chain::OptionChain;
+use crate::consts::OptionDir;
+use wasm_bindgen::prelude::*;
+
+/*
+    Composite OTM smile per expiry.
+
+    In-the-money legs carry most of their value as intrinsic, so small errors in their mids
+    produce large implied volatility errors. Each expiry is instead represented by a single
+    smile made of OTM puts below the forward and OTM calls above it. The forward comes from
+    put-call parity, and the spot is paired with the matching implied dividend yield so both
+    sides are priced off the same forward.
+
+    Within blend_width of the forward (in log-moneyness) the call and put volatilities are
+    linearly blended to avoid a jump where the smile switches sides.
+*/
+
+// Implied volatilities outside of this range hit the edges of the bisection bracket in
+// bs32x8::implied_vol and are treated as failed inversions
+pub(crate) const MIN_VOL: f32 = 0.001;
+const MAX_VOL: f32 = 4.99;
+
+/// Implied volatility smile of a single expiry, sorted by strike
+#[derive(Debug, Clone, PartialEq)]
+pub struct Smile {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    pub strikes: Vec<f32>,
+    pub vols: Vec<f32>,
+}
+
+impl Smile {
+    // ln(K / F) for every strike
+    pub fn log_moneyness(&self) -> Vec<f32> {
+        self.strikes
+            .iter()
+            .map(|strike| (strike / self.forward).ln())
+            .collect()
+    }
+
+    // Linear interpolation in strike with flat extrapolation past the last quoted strikes
+    pub fn vol_at(&self, strike: f32) -> f32 {
+        interpolate(&self.strikes, &self.vols, strike)
+    }
+}
+
+// Linear interpolation of ys over sorted xs, flat outside of the range
+pub(crate) fn interpolate(xs: &[f32], ys: &[f32], x: f32) -> f32 {
+    if xs.is_empty() {
+        return f32::NAN;
+    }
+
+    if x <= xs[0] {
+        return ys[0];
+    }
+
+    if x >= xs[xs.len() - 1] {
+        return ys[ys.len() - 1];
+    }
+
+    let idx = xs.partition_point(|v| *v <= x);
+    let (x0, x1) = (xs[idx - 1], xs[idx]);
+    let (y0, y1) = (ys[idx - 1], ys[idx]);
+
+    if x1 == x0 {
+        return y0;
+    }
+
+    y0 + ((y1 - y0) * (x - x0)) / (x1 - x0)
+}
+
+// Continuous dividend yield that makes spot and forward consistent for a given rate
+pub(crate) fn implied_dividend_yield(
+    spot: f32,
+    forward: f32,
+    risk_free_rate: f32,
+    years_to_expiry: f32
+) -> f32 {
+    risk_free_rate - (forward / spot).ln() / years_to_expiry
+}
+
+pub(crate) fn is_valid_vol(vol: f32) -> bool {
+    (MIN_VOL..=MAX_VOL).contains(&vol)
+}
+
+fn composite_vol(
+    log_moneyness: f32,
+    call_vol: Option<f32>,
+    put_vol: Option<f32>,
+    blend_width: f32
+) -> Option<f32> {
+    match (call_vol, put_vol) {
+        (Some(call_vol), Some(put_vol)) if log_moneyness.abs() < blend_width => {
+            let weight = (log_moneyness + blend_width) / (2.0 * blend_width);
+            Some(weight * call_vol + (1.0 - weight) * put_vol)
+        }
+        (Some(call_vol), Some(put_vol)) if log_moneyness == 0.0 => Some((call_vol + put_vol) / 2.0),
+        (call_vol, _) if log_moneyness >= 0.0 => call_vol,
+        (_, put_vol) => put_vol,
+    }
+}
+
+pub(crate) fn expiry_smile(
+    chain: &OptionChain,
+    years_to_expiry: f32,
+    risk_free_rate: f32,
+    blend_width: f32,
+    max_iterations: i32,
+    threshold: f32
+) -> Option<Smile> {
+    let forward = chain.parity_forward(years_to_expiry, risk_free_rate)?;
+    let dividend_yield = implied_dividend_yield(
+        chain.spot,
+        forward,
+        risk_free_rate,
+        years_to_expiry
+    );
+    let expiry = chain.expiry(years_to_expiry);
+
+    // (strike, call vol, put vol) for every strike, merged across both sides
+    let mut points: Vec<(f32, Option<f32>, Option<f32>)> = Vec::new();
+
+    for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+        let (prices, strikes, _) = expiry.columns(option_dir);
+        let vols = expiry.implied_vol(
+            option_dir,
+            risk_free_rate,
+            dividend_yield,
+            max_iterations,
+            threshold
+        );
+
+        for i in 0..strikes.len() {
+            if prices[i] <= 0.0 || !is_valid_vol(vols[i]) {
+                continue;
+            }
+
+            let idx = match points.iter().position(|(strike, _, _)| *strike == strikes[i]) {
+                Some(idx) => idx,
+                None => {
+                    points.push((strikes[i], None, None));
+                    points.len() - 1
+                }
+            };
+
+            match option_dir {
+                OptionDir::CALL => {
+                    points[idx].1 = Some(vols[i]);
+                }
+                OptionDir::PUT => {
+                    points[idx].2 = Some(vols[i]);
+                }
+            }
+        }
+    }
+
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut strikes = Vec::new();
+    let mut vols = Vec::new();
+
+    for (strike, call_vol, put_vol) in points {
+        let log_moneyness = (strike / forward).ln();
+
+        if let Some(vol) = composite_vol(log_moneyness, call_vol, put_vol, blend_width) {
+            strikes.push(strike);
+            vols.push(vol);
+        }
+    }
+
+    if strikes.is_empty() {
+        return None;
+    }
+
+    Some(Smile {
+        years_to_expiry,
+        forward,
+        strikes,
+        vols,
+    })
+}
+
+// One composite OTM smile per expiry in ascending order. Expiries without a parity forward
+// or without any valid implied volatility are skipped.
+pub fn otm_smiles(
+    chain: &OptionChain,
+    risk_free_rate: f32,
+    blend_width: f32,
+    max_iterations: i32,
+    threshold: f32
+) -> Vec<Smile> {
+    chain
+        .expiries()
+        .into_iter()
+        .filter_map(|years_to_expiry| {
+            expiry_smile(
+                chain,
+                years_to_expiry,
+                risk_free_rate,
+                blend_width,
+                max_iterations,
+                threshold
+            )
+        })
+        .collect()
+}
+
+/*
+    Frontend entry point. Takes the same mid-price arrays as vol32x8::implied_vol, where row i
+    holds the call and put at strike[i] and years_to_expiry[i], and returns the composite OTM
+    implied volatility of every row. Rows without a valid volatility are set to 0.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn otm_implied_vol(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    blend_width: f32,
+    max_iterations: i32,
+    threshold: f32
+) -> Vec<f32> {
+    if
+        !(
+            call_price.len() == put_price.len() &&
+            put_price.len() == strike.len() &&
+            strike.len() == years_to_expiry.len()
+        )
+    {
+        return Vec::new();
+    }
+
+    let chain = OptionChain::from_mids(spot, call_price, put_price, strike, years_to_expiry);
+    let smiles = otm_smiles(&chain, risk_free_rate, blend_width, max_iterations, threshold);
+
+    (0..strike.len())
+        .map(|i| {
+            smiles
+                .iter()
+                .find(|smile| smile.years_to_expiry == years_to_expiry[i])
+                .and_then(|smile| {
+                    smile.strikes
+                        .iter()
+                        .position(|k| *k == strike[i])
+                        .map(|idx| smile.vols[idx])
+                })
+                .unwrap_or(0.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::read_hist;
+
+    fn skewed_vol(strike: f32, forward: f32) -> f32 {
+        0.2 - 0.3 * (strike / forward).ln()
+    }
+
+    // Chain priced off a known skew with no dividends
+    fn synthetic_chain(spot: f32, risk_free_rate: f32, years_to_expiry: f32) -> OptionChain {
+        let forward = spot * (risk_free_rate * years_to_expiry).exp();
+        let strikes: Vec<f32> = (80..=120).step_by(5).map(|k| k as f32).collect();
+        let n = strikes.len();
+
+        let call_prices: Vec<f32> = strikes
+            .iter()
+            .map(|k| {
+                bs::call_price(
+                    spot,
+                    *k,
+                    skewed_vol(*k, forward),
+                    risk_free_rate,
+                    0.0,
+                    years_to_expiry
+                )
+            })
+            .collect();
+        let put_prices: Vec<f32> = strikes
+            .iter()
+            .map(|k| {
+                bs::put_price(spot, *k, skewed_vol(*k, forward), risk_free_rate, 0.0, years_to_expiry)
+            })
+            .collect();
+
+        OptionChain::from_mids(
+            spot,
+            &call_prices,
+            &put_prices,
+            &strikes,
+            &vec![years_to_expiry; n]
+        )
+    }
+
+    #[test]
+    fn recovers_forward_and_skew() {
+        let chain = synthetic_chain(100.0, 0.03, 0.5);
+        let smiles = otm_smiles(&chain, 0.03, 0.02, 40, 0.00001);
+
+        assert_eq!(smiles.len(), 1);
+
+        let smile = &smiles[0];
+        let expected_forward = 100.0 * (0.03f32 * 0.5).exp();
+        assert!((smile.forward - expected_forward).abs() < 0.01, "Forward: {}", smile.forward);
+
+        for (strike, vol) in smile.strikes.iter().zip(smile.vols.iter()) {
+            let expected = skewed_vol(*strike, expected_forward);
+            assert!((vol - expected).abs() < 0.001, "Strike {} vol {} expected {}", strike, vol, expected);
+        }
+    }
+
+    #[test]
+    fn blend_is_continuous() {
+        assert_eq!(composite_vol(-0.1, Some(0.3), Some(0.2), 0.05), Some(0.2));
+        assert_eq!(composite_vol(0.1, Some(0.3), Some(0.2), 0.05), Some(0.3));
+        assert_eq!(composite_vol(0.0, Some(0.3), Some(0.2), 0.05), Some(0.25));
+        assert_eq!(composite_vol(0.0, Some(0.3), Some(0.2), 0.0), Some(0.25));
+        assert_eq!(composite_vol(-0.01, Some(0.3), None, 0.05), None);
+    }
+
+    #[test]
+    fn spy_smiles() {
+        let chain = read_hist::get_spy_chain();
+        let smiles = otm_smiles(&chain, 0.05, 0.01, 30, 0.0001);
+
+        assert!(smiles.len() > 10, "Num smiles: {}", smiles.len());
+
+        for smile in smiles.iter() {
+            assert!(smile.strikes.windows(2).all(|w| w[0] < w[1]));
+            assert!(smile.vols.iter().all(|v| is_valid_vol(*v)));
+        }
+    }
+}
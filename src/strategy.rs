@@ -0,0 +1,571 @@
+use crate::chain::OptionChain;
+use crate::consts::{ OptionDir, StrategyKind };
+use crate::portfolio::{ self, Position, PositionRisk };
+use crate::surface::{ self, VolSurface };
+use crate::vol32x8;
+use rayon::prelude::*;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    Payoff diagrams and theoretical P&L curves of common option strategies.
+
+    A strategy is a set of positions (see strategy_legs for the legs of each StrategyKind),
+    bought today at its mark-to-model value on the fitted surface. Its P&L at a horizon, in
+    years from today, is the value of the legs at that date minus that cost:
+      - legs expired by the horizon are worth their intrinsic value
+      - the others are priced with the SIMD pricer at the remaining time to expiry, keeping
+        the surface vol they are marked at today
+    The expiry P&L is taken at the first expiry of the legs, which for a calendar leaves the
+    far leg priced with its remaining time value.
+
+    Breakevens and the maximum profit and loss are read off the expiry P&L at the spots of the
+    grid, every strike and zero. This is exact for single expiry strategies, whose expiry P&L is
+    linear between strikes, and as fine as the grid for calendars. Profit or loss is unbounded
+    when the expiry P&L keeps rising or falling with spot, i.e. the legs are net long or short
+    calls.
+*/
+
+/// Expiry and horizon P&L of a strategy, with the legs as marked today
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StrategyProfile {
+    pub legs: Vec<PositionRisk>,
+    // Mark-to-model value paid for the legs, negative for a credit
+    pub cost: f32,
+    pub expiry: f32,
+    pub spots: Vec<f32>,
+    pub expiry_pnl: Vec<f32>,
+    // Row i holds the P&L at every spot at horizons[i]
+    pub horizons: Vec<f32>,
+    pub horizon_pnl: Vec<Vec<f32>>,
+    pub breakevens: Vec<f32>,
+    // None when unbounded. The maximum loss is the lowest P&L, so usually negative.
+    pub max_profit: Option<f32>,
+    pub max_loss: Option<f32>,
+}
+
+/*
+    Legs of quantity units of a strategy, with strikes in ascending order:
+      - CallVertical [low, high]: long the low call, short the high call
+      - PutVertical [low, high]: long the high put, short the low put
+      - Straddle [strike]: long a call and a put
+      - Strangle [put, call]: long a put and a call
+      - IronCondor [long put, short put, short call, long call]: short the inner strangle,
+        long the outer one
+      - Calendar [strike] with years_to_expiry [near, far]: short the near call, long the far call
+      - Butterfly [low, middle, high]: long the wings, short two middle calls
+    Every other strategy takes a single years_to_expiry. A negative quantity sells the strategy.
+    Returns None if the number of strikes or expiries does not match the strategy.
+*/
+pub fn strategy_legs(
+    kind: StrategyKind,
+    strikes: &[f32],
+    years_to_expiry: &[f32],
+    quantity: f32,
+    multiplier: f32
+) -> Option<Vec<Position>> {
+    let (num_strikes, num_expiries) = match kind {
+        StrategyKind::CallVertical | StrategyKind::PutVertical | StrategyKind::Strangle => (2, 1),
+        StrategyKind::Straddle => (1, 1),
+        StrategyKind::IronCondor => (4, 1),
+        StrategyKind::Calendar => (1, 2),
+        StrategyKind::Butterfly => (3, 1),
+    };
+
+    if strikes.len() != num_strikes || years_to_expiry.len() != num_expiries {
+        return None;
+    }
+
+    let years = years_to_expiry[0];
+    let leg = |option_dir: OptionDir, strike: f32, years_to_expiry: f32, units: f32| Position {
+        option_dir,
+        strike,
+        years_to_expiry,
+        quantity: units * quantity,
+        multiplier,
+    };
+
+    let legs = match kind {
+        StrategyKind::CallVertical =>
+            vec![leg(OptionDir::CALL, strikes[0], years, 1.0), leg(OptionDir::CALL, strikes[1], years, -1.0)],
+        StrategyKind::PutVertical =>
+            vec![leg(OptionDir::PUT, strikes[1], years, 1.0), leg(OptionDir::PUT, strikes[0], years, -1.0)],
+        StrategyKind::Straddle =>
+            vec![leg(OptionDir::CALL, strikes[0], years, 1.0), leg(OptionDir::PUT, strikes[0], years, 1.0)],
+        StrategyKind::Strangle =>
+            vec![leg(OptionDir::PUT, strikes[0], years, 1.0), leg(OptionDir::CALL, strikes[1], years, 1.0)],
+        StrategyKind::IronCondor =>
+            vec![
+                leg(OptionDir::PUT, strikes[0], years, 1.0),
+                leg(OptionDir::PUT, strikes[1], years, -1.0),
+                leg(OptionDir::CALL, strikes[2], years, -1.0),
+                leg(OptionDir::CALL, strikes[3], years, 1.0)
+            ],
+        StrategyKind::Calendar =>
+            vec![
+                leg(OptionDir::CALL, strikes[0], years_to_expiry[0], -1.0),
+                leg(OptionDir::CALL, strikes[0], years_to_expiry[1], 1.0)
+            ],
+        StrategyKind::Butterfly =>
+            vec![
+                leg(OptionDir::CALL, strikes[0], years, 1.0),
+                leg(OptionDir::CALL, strikes[1], years, -2.0),
+                leg(OptionDir::CALL, strikes[2], years, 1.0)
+            ],
+    };
+
+    Some(legs)
+}
+
+/*
+    Moves every leg onto the closest listed contract of the chain: the nearest expiry first,
+    then the nearest strike of the leg's direction at that expiry. Returns None if the chain
+    has no contract of a leg's direction.
+*/
+pub fn snap_to_chain(chain: &OptionChain, legs: &[Position]) -> Option<Vec<Position>> {
+    let distance = |a: f32, b: f32| (a - b).abs();
+
+    legs.iter()
+        .map(|leg| {
+            let quotes: Vec<_> = chain.quotes
+                .iter()
+                .filter(|quote| quote.option_dir == leg.option_dir)
+                .collect();
+
+            let years_to_expiry = quotes
+                .iter()
+                .map(|quote| quote.years_to_expiry)
+                .min_by(|a, b| distance(*a, leg.years_to_expiry).total_cmp(&distance(*b, leg.years_to_expiry)))?;
+            let strike = quotes
+                .iter()
+                .filter(|quote| quote.years_to_expiry == years_to_expiry)
+                .map(|quote| quote.strike)
+                .min_by(|a, b| distance(*a, leg.strike).total_cmp(&distance(*b, leg.strike)))?;
+
+            Some(Position { strike, years_to_expiry, ..*leg })
+        })
+        .collect()
+}
+
+fn intrinsic(option_dir: OptionDir, spot: f32, strike: f32) -> f32 {
+    match option_dir {
+        OptionDir::CALL => (spot - strike).max(0.0),
+        OptionDir::PUT => (strike - spot).max(0.0),
+    }
+}
+
+// Value of the legs at every spot, horizon years from today
+fn value_at(
+    legs: &[Position],
+    vols: &[f32],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    horizon: f32,
+    spots: &[f32]
+) -> Vec<f32> {
+    let mut value = vec![0.0; spots.len()];
+
+    if spots.is_empty() {
+        return value;
+    }
+
+    for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+        let (expired, live): (Vec<_>, Vec<_>) = legs
+            .iter()
+            .zip(vols.iter())
+            .filter(|(leg, _)| leg.option_dir == option_dir)
+            .partition(|(leg, _)| leg.years_to_expiry <= horizon);
+
+        for (value, spot) in value.iter_mut().zip(spots.iter()) {
+            for (leg, _) in expired.iter() {
+                *value += intrinsic(option_dir, *spot, leg.strike) * leg.quantity * leg.multiplier;
+            }
+        }
+
+        if live.is_empty() {
+            continue;
+        }
+
+        // One option per (spot, leg), in that order
+        let n = spots.len() * live.len();
+        let mut spot = Vec::with_capacity(n);
+        let mut strike = Vec::with_capacity(n);
+        let mut volatility = Vec::with_capacity(n);
+        let mut years_to_expiry = Vec::with_capacity(n);
+
+        for s in spots.iter() {
+            for (leg, vol) in live.iter() {
+                spot.push(*s);
+                strike.push(leg.strike);
+                volatility.push(**vol);
+                years_to_expiry.push(leg.years_to_expiry - horizon);
+            }
+        }
+
+        let prices = vol32x8::price(
+            option_dir,
+            &spot,
+            &strike,
+            &volatility,
+            &vec![risk_free_rate; n],
+            &vec![dividend_yield; n],
+            &years_to_expiry
+        );
+
+        value
+            .par_iter_mut()
+            .zip(prices.par_chunks(live.len()))
+            .for_each(|(value, prices)| {
+                *value += prices
+                    .iter()
+                    .zip(live.iter())
+                    .map(|(price, (leg, _))| price * leg.quantity * leg.multiplier)
+                    .sum::<f32>();
+            });
+    }
+
+    value
+}
+
+// Spots where the P&L crosses or touches zero, interpolated linearly between the points
+fn breakevens(spots: &[f32], pnl: &[f32]) -> Vec<f32> {
+    let mut breakevens: Vec<f32> = Vec::new();
+
+    for i in 0..spots.len() {
+        let breakeven = if pnl[i] == 0.0 {
+            Some(spots[i])
+        } else if i + 1 < spots.len() && pnl[i] * pnl[i + 1] < 0.0 {
+            Some(spots[i] - (pnl[i] * (spots[i + 1] - spots[i])) / (pnl[i + 1] - pnl[i]))
+        } else {
+            None
+        };
+
+        if let Some(breakeven) = breakeven {
+            if breakevens.last() != Some(&breakeven) {
+                breakevens.push(breakeven);
+            }
+        }
+    }
+
+    breakevens
+}
+
+pub fn strategy_profile(
+    surface: &VolSurface,
+    legs: &[Position],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    spots: &[f32],
+    horizons: &[f32]
+) -> StrategyProfile {
+    let risk = portfolio::portfolio_risk(surface, legs, risk_free_rate, dividend_yield);
+    let cost = risk.total.value;
+    let vols: Vec<f32> = risk.positions
+        .iter()
+        .map(|leg| leg.implied_vol)
+        .collect();
+    let expiry = legs
+        .iter()
+        .map(|leg| leg.years_to_expiry)
+        .fold(f32::INFINITY, f32::min);
+
+    let pnl_at = |horizon: f32, spots: &[f32]| -> Vec<f32> {
+        value_at(legs, &vols, risk_free_rate, dividend_yield, horizon, spots)
+            .iter()
+            .map(|value| value - cost)
+            .collect()
+    };
+
+    // The kinks of the expiry P&L are at the strikes
+    let mut points: Vec<f32> = spots
+        .iter()
+        .chain(legs.iter().map(|leg| &leg.strike))
+        .copied()
+        .chain([0.0])
+        .filter(|spot| *spot >= 0.0)
+        .collect();
+    points.sort_by(|a, b| a.total_cmp(b));
+    points.dedup();
+    let points_pnl = pnl_at(expiry, &points);
+
+    // Slope of the expiry P&L for large spots, where every call is worth spot less a constant
+    let slope: f32 = legs
+        .iter()
+        .filter(|leg| leg.option_dir == OptionDir::CALL)
+        .map(|leg| {
+            let remaining = (leg.years_to_expiry - expiry).max(0.0);
+            (-dividend_yield * remaining).exp() * leg.quantity * leg.multiplier
+        })
+        .sum();
+    let scale: f32 = legs
+        .iter()
+        .map(|leg| (leg.quantity * leg.multiplier).abs())
+        .sum();
+    let tolerance = 1e-4 * scale;
+
+    let max_pnl = points_pnl.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let min_pnl = points_pnl.iter().copied().fold(f32::INFINITY, f32::min);
+
+    StrategyProfile {
+        legs: risk.positions,
+        cost,
+        expiry,
+        spots: spots.to_vec(),
+        expiry_pnl: pnl_at(expiry, spots),
+        horizons: horizons.to_vec(),
+        horizon_pnl: horizons
+            .iter()
+            .map(|horizon| pnl_at(*horizon, spots))
+            .collect(),
+        breakevens: breakevens(&points, &points_pnl),
+        max_profit: if slope > tolerance { None } else { Some(max_pnl) },
+        max_loss: if slope < -tolerance { None } else { Some(min_pnl) },
+    }
+}
+
+/*
+    Frontend entry point. Builds the strategy on the contracts of the chain closest to the
+    given strikes and expiries (see strategy_legs and snap_to_chain), marks it to the surface
+    fitted as in portfolio::portfolio_json and returns its profile as JSON. Horizons are in
+    years from today. Unbounded profits or losses are null. Returns an empty profile for
+    invalid inputs.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn strategy_json(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32,
+    dividend_yield: f32,
+    kind: StrategyKind,
+    strategy_strikes: &[f32],
+    strategy_years_to_expiry: &[f32],
+    quantity: f32,
+    multiplier: f32,
+    spots: &[f32],
+    horizons: &[f32]
+) -> String {
+    let empty = || serde_json::to_string(&StrategyProfile::default()).unwrap_or_else(|_| String::from("{}"));
+
+    let surface = match surface::fit_mids(call_price, put_price, spot, strike, years_to_expiry, risk_free_rate) {
+        Some(surface) => surface,
+        None => {
+            return empty();
+        }
+    };
+    let chain = OptionChain::from_mids(spot, call_price, put_price, strike, years_to_expiry);
+    let legs = strategy_legs(kind, strategy_strikes, strategy_years_to_expiry, quantity, multiplier).and_then(|legs|
+        snap_to_chain(&chain, &legs)
+    );
+    let legs = match legs {
+        Some(legs) => legs,
+        None => {
+            return empty();
+        }
+    };
+
+    let profile = strategy_profile(&surface, &legs, risk_free_rate, dividend_yield, spots, horizons);
+
+    serde_json::to_string(&profile).unwrap_or_else(|_| empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::fixtures::flat_surface;
+
+    fn spots() -> Vec<f32> {
+        (60..=140).map(|s| s as f32).collect()
+    }
+
+    fn profile(kind: StrategyKind, strikes: &[f32], years_to_expiry: &[f32]) -> StrategyProfile {
+        let legs = strategy_legs(kind, strikes, years_to_expiry, 1.0, 100.0).unwrap();
+        strategy_profile(&flat_surface(0.2), &legs, 0.03, 0.0, &spots(), &[0.0])
+    }
+
+    fn close(got: f32, expected: f32, tolerance: f32) {
+        assert!((got - expected).abs() < tolerance, "Got: {}, Expected: {}", got, expected);
+    }
+
+    #[test]
+    fn leg_counts() {
+        let cases = [
+            (StrategyKind::CallVertical, vec![95.0, 105.0], vec![0.5], 2),
+            (StrategyKind::PutVertical, vec![95.0, 105.0], vec![0.5], 2),
+            (StrategyKind::Straddle, vec![100.0], vec![0.5], 2),
+            (StrategyKind::Strangle, vec![95.0, 105.0], vec![0.5], 2),
+            (StrategyKind::IronCondor, vec![85.0, 95.0, 105.0, 115.0], vec![0.5], 4),
+            (StrategyKind::Calendar, vec![100.0], vec![0.25, 0.5], 2),
+            (StrategyKind::Butterfly, vec![90.0, 100.0, 110.0], vec![0.5], 3),
+        ];
+
+        for (kind, strikes, years, num_legs) in cases {
+            let legs = strategy_legs(kind, &strikes, &years, 2.0, 100.0).unwrap();
+            assert_eq!(legs.len(), num_legs, "{:?}", kind);
+
+            // Each strategy is balanced except the straddle and strangle, which are long both sides
+            let net: f32 = legs.iter().map(|leg| leg.quantity).sum();
+            let expected = match kind {
+                StrategyKind::Straddle | StrategyKind::Strangle => 4.0,
+                _ => 0.0,
+            };
+            assert_eq!(net, expected, "{:?}", kind);
+        }
+
+        assert!(strategy_legs(StrategyKind::Straddle, &[95.0, 105.0], &[0.5], 1.0, 100.0).is_none());
+        assert!(strategy_legs(StrategyKind::Calendar, &[100.0], &[0.5], 1.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn call_vertical() {
+        let profile = profile(StrategyKind::CallVertical, &[95.0, 105.0], &[0.5]);
+        let cost = 100.0 *
+            (bs::price(OptionDir::CALL, 100.0, 95.0, 0.2, 0.03, 0.0, 0.5) -
+                bs::price(OptionDir::CALL, 100.0, 105.0, 0.2, 0.03, 0.0, 0.5));
+
+        close(profile.cost, cost, 0.5);
+        close(profile.max_profit.unwrap(), 1000.0 - cost, 0.5);
+        close(profile.max_loss.unwrap(), -cost, 0.5);
+        assert_eq!(profile.breakevens.len(), 1);
+        close(profile.breakevens[0], 95.0 + cost / 100.0, 0.01);
+
+        // At expiry the P&L is the payoff less the cost
+        for (spot, pnl) in profile.spots.iter().zip(profile.expiry_pnl.iter()) {
+            let payoff = 100.0 * ((spot - 95.0).clamp(0.0, 10.0));
+            close(*pnl, payoff - cost, 0.5);
+        }
+
+        // Today the P&L is zero at the current spot
+        let today = profile.spots.iter().position(|spot| *spot == 100.0).unwrap();
+        close(profile.horizon_pnl[0][today], 0.0, 0.1);
+    }
+
+    #[test]
+    fn straddle_and_strangle() {
+        let straddle = profile(StrategyKind::Straddle, &[100.0], &[0.5]);
+        let premium = straddle.cost / 100.0;
+
+        assert_eq!(straddle.max_profit, None);
+        close(straddle.max_loss.unwrap(), -straddle.cost, 0.5);
+        assert_eq!(straddle.breakevens.len(), 2);
+        close(straddle.breakevens[0], 100.0 - premium, 0.01);
+        close(straddle.breakevens[1], 100.0 + premium, 0.01);
+
+        // Sold, the loss is unbounded
+        let legs = strategy_legs(StrategyKind::Strangle, &[90.0, 110.0], &[0.5], -1.0, 100.0).unwrap();
+        let strangle = strategy_profile(&flat_surface(0.2), &legs, 0.03, 0.0, &spots(), &[]);
+
+        assert_eq!(strangle.max_loss, None);
+        close(strangle.max_profit.unwrap(), -strangle.cost, 0.5);
+        assert!(strangle.cost < 0.0);
+        assert!(strangle.horizon_pnl.is_empty());
+    }
+
+    #[test]
+    fn iron_condor_and_butterfly() {
+        let condor = profile(StrategyKind::IronCondor, &[85.0, 95.0, 105.0, 115.0], &[0.5]);
+
+        // Credit of the inner strangle less the outer one, capped loss of the wing width
+        assert!(condor.cost < 0.0);
+        close(condor.max_profit.unwrap(), -condor.cost, 0.5);
+        close(condor.max_loss.unwrap(), -condor.cost - 1000.0, 0.5);
+        assert_eq!(condor.breakevens.len(), 2);
+
+        let butterfly = profile(StrategyKind::Butterfly, &[90.0, 100.0, 110.0], &[0.5]);
+
+        assert!(butterfly.cost > 0.0);
+        close(butterfly.max_profit.unwrap(), 1000.0 - butterfly.cost, 0.5);
+        close(butterfly.max_loss.unwrap(), -butterfly.cost, 0.5);
+        assert_eq!(butterfly.breakevens.len(), 2);
+    }
+
+    #[test]
+    fn calendar_at_near_expiry() {
+        let profile = profile(StrategyKind::Calendar, &[100.0], &[0.25, 0.5]);
+
+        close(profile.expiry, 0.25, 1e-6);
+        assert!(profile.cost > 0.0);
+
+        // At the near expiry the short call is intrinsic and the long call has a quarter left
+        for (spot, pnl) in profile.spots.iter().zip(profile.expiry_pnl.iter()) {
+            let far = bs::price(OptionDir::CALL, *spot, 100.0, 0.2, 0.03, 0.0, 0.25);
+            let expected = 100.0 * (far - (spot - 100.0).max(0.0)) - profile.cost;
+            close(*pnl, expected, 0.5);
+        }
+
+        // Bounded both ways, the most it makes is at the strike
+        let best = profile.expiry_pnl
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        close(profile.max_profit.unwrap(), best, 0.01);
+        assert!(profile.max_loss.unwrap() >= -profile.cost - 0.5);
+        assert_eq!(profile.breakevens.len(), 2);
+    }
+
+    #[test]
+    fn json_snaps_to_the_chain() {
+        let mut call_price = Vec::new();
+        let mut put_price = Vec::new();
+        let mut strike = Vec::new();
+        let mut years_to_expiry = Vec::new();
+
+        for years in [0.25, 1.0] {
+            for k in (80..=120).step_by(5) {
+                let k = k as f32;
+                call_price.push(bs::price(OptionDir::CALL, 100.0, k, 0.2, 0.03, 0.0, years));
+                put_price.push(bs::price(OptionDir::PUT, 100.0, k, 0.2, 0.03, 0.0, years));
+                strike.push(k);
+                years_to_expiry.push(years);
+            }
+        }
+
+        let json = strategy_json(
+            &call_price,
+            &put_price,
+            100.0,
+            &strike,
+            &years_to_expiry,
+            0.03,
+            0.0,
+            StrategyKind::CallVertical,
+            &[96.0, 104.0],
+            &[0.3],
+            1.0,
+            100.0,
+            &[90.0, 100.0, 110.0],
+            &[0.1]
+        );
+        let profile: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let legs = profile["legs"].as_array().unwrap();
+
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0]["strike"], 95.0);
+        assert_eq!(legs[1]["strike"], 105.0);
+        assert_eq!(legs[0]["years_to_expiry"], 0.25);
+        assert_eq!(profile["horizon_pnl"].as_array().unwrap().len(), 1);
+        assert!(profile["max_profit"].as_f64().unwrap() > 0.0);
+
+        let bad = strategy_json(
+            &call_price,
+            &put_price,
+            100.0,
+            &strike,
+            &years_to_expiry,
+            0.03,
+            0.0,
+            StrategyKind::Calendar,
+            &[100.0],
+            &[0.25],
+            1.0,
+            100.0,
+            &[100.0],
+            &[]
+        );
+        assert_eq!(bad, serde_json::to_string(&StrategyProfile::default()).unwrap());
+    }
+}
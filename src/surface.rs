@@ -0,0 +1,300 @@
+use crate::chain::OptionChain;
+use crate::metrics;
+use crate::smile::{ self, Smile, MIN_VOL };
+use serde::Serialize;
+
+/*
+    Fitted implied volatility surface.
+
+    Each expiry's composite OTM smile is fitted with a quadratic in log-moneyness x = ln(K / F)
+        sigma(x) = c0 + c1 x + c2 x^2
+    weighted by a Gaussian in x with a width of WEIGHT_STD_DEVS ATM standard deviations, so the
+    thinly traded deep wings do not dominate the fit. Past the fitted strikes the variance
+    sigma^2 continues linearly in x with its slope at the last fitted strike, so the smile stays
+    continuously differentiable and the wing variance grows at most linearly, unlike the
+    quadratic's.
+
+    Between expiries the total variance w = sigma^2 T is interpolated linearly in T at the same
+    log-moneyness, with the forward interpolated linearly from the spot at T = 0. Before the
+    first expiry the first smile is used as is, and after the last expiry the last one.
+*/
+
+const WEIGHT_STD_DEVS: f32 = 2.0;
+const BLEND_WIDTH: f32 = 0.01;
+
+/// Quadratic fit of one expiry's smile in log-moneyness
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FittedSmile {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    pub coefficients: [f32; 3],
+    // Log-moneyness range of the fitted strikes
+    pub min_log_moneyness: f32,
+    pub max_log_moneyness: f32,
+    // Root mean squared error of the fit over the quoted strikes
+    pub rmse: f32,
+}
+
+impl FittedSmile {
+    pub fn vol(&self, log_moneyness: f32) -> f32 {
+        let x = log_moneyness.clamp(self.min_log_moneyness, self.max_log_moneyness);
+        let [c0, c1, c2] = self.coefficients;
+        let vol = c0 + c1 * x + c2 * x * x;
+
+        if vol.is_nan() || vol <= MIN_VOL {
+            return MIN_VOL;
+        }
+
+        // Zero inside the fitted strikes
+        let distance = log_moneyness - x;
+        let variance = vol * vol + 2.0 * vol * (c1 + 2.0 * c2 * x) * distance;
+
+        variance.max(MIN_VOL * MIN_VOL).sqrt()
+    }
+
+    pub fn vol_at(&self, strike: f32) -> f32 {
+        self.vol((strike / self.forward).ln())
+    }
+}
+
+/// Fitted smiles of every expiry, sorted by expiry
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VolSurface {
+    pub spot: f32,
+    pub smiles: Vec<FittedSmile>,
+}
+
+impl VolSurface {
+    // Forward interpolated linearly in time, starting from the spot today
+    pub fn forward(&self, years_to_expiry: f32) -> f32 {
+        let mut years = vec![0.0];
+        let mut forwards = vec![self.spot];
+
+        for smile in self.smiles.iter() {
+            years.push(smile.years_to_expiry);
+            forwards.push(smile.forward);
+        }
+
+        smile::interpolate(&years, &forwards, years_to_expiry)
+    }
+
+    // NaN without any fitted smile or for expiries at or before today
+    pub fn vol(&self, strike: f32, years_to_expiry: f32) -> f32 {
+        if self.smiles.is_empty() || years_to_expiry <= 0.0 {
+            return f32::NAN;
+        }
+
+        let x = (strike / self.forward(years_to_expiry)).ln();
+        let idx = self.smiles.partition_point(|smile| smile.years_to_expiry <= years_to_expiry);
+
+        if idx == 0 {
+            return self.smiles[0].vol(x);
+        }
+
+        let prev = &self.smiles[idx - 1];
+
+        if idx == self.smiles.len() || prev.years_to_expiry == years_to_expiry {
+            return prev.vol(x);
+        }
+
+        let next = &self.smiles[idx];
+        let prev_variance = prev.vol(x).powi(2) * prev.years_to_expiry;
+        let next_variance = next.vol(x).powi(2) * next.years_to_expiry;
+        let weight =
+            (years_to_expiry - prev.years_to_expiry) /
+            (next.years_to_expiry - prev.years_to_expiry);
+        let total_variance = prev_variance + weight * (next_variance - prev_variance);
+
+        (total_variance.max(0.0) / years_to_expiry).sqrt()
+    }
+
+    // Vols on a grid, where row i holds every strike at years_to_expiry[i]
+    pub fn grid(&self, strikes: &[f32], years_to_expiry: &[f32]) -> Vec<Vec<f32>> {
+        years_to_expiry
+            .iter()
+            .map(|years| {
+                strikes
+                    .iter()
+                    .map(|strike| self.vol(*strike, *years))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// Returns None with fewer than 3 strikes or a degenerate fit
+pub fn fit_smile(smile: &Smile) -> Option<FittedSmile> {
+    let xs = smile.log_moneyness();
+
+    if xs.len() < 3 {
+        return None;
+    }
+
+    let width = WEIGHT_STD_DEVS * smile.vol_at(smile.forward) * smile.years_to_expiry.sqrt();
+    let weights: Vec<f32> = xs
+        .iter()
+        .map(|x| (-0.5 * (x / width).powi(2)).exp())
+        .collect();
+
+    let coefficients = metrics::fit_quadratic(&xs, &smile.vols, &weights)?;
+
+    let mut fitted = FittedSmile {
+        years_to_expiry: smile.years_to_expiry,
+        forward: smile.forward,
+        coefficients,
+        min_log_moneyness: xs[0],
+        max_log_moneyness: xs[xs.len() - 1],
+        rmse: 0.0,
+    };
+
+    let squared_error: f32 = xs
+        .iter()
+        .zip(smile.vols.iter())
+        .map(|(x, vol)| (fitted.vol(*x) - vol).powi(2))
+        .sum();
+    fitted.rmse = (squared_error / (xs.len() as f32)).sqrt();
+
+    Some(fitted)
+}
+
+// Fitted composite OTM smile of a single expiry of the chain
+pub fn fit_expiry(
+    chain: &OptionChain,
+    years_to_expiry: f32,
+    risk_free_rate: f32,
+    max_iterations: i32,
+    threshold: f32
+) -> Option<FittedSmile> {
+    smile::expiry_smile(
+        chain,
+        years_to_expiry,
+        risk_free_rate,
+        BLEND_WIDTH,
+        max_iterations,
+        threshold
+    ).and_then(|smile| fit_smile(&smile))
+}
+
+pub fn fit_surface(
+    chain: &OptionChain,
+    risk_free_rate: f32,
+    max_iterations: i32,
+    threshold: f32
+) -> VolSurface {
+    let smiles = smile::otm_smiles(chain, risk_free_rate, BLEND_WIDTH, max_iterations, threshold);
+
+    VolSurface {
+        spot: chain.spot,
+        smiles: smiles.iter().filter_map(fit_smile).collect(),
+    }
+}
+
+/*
+    Fits the surface to the mid-price arrays used by the frontend entry points, see
+    OptionChain::from_mids, with the solver settings of smile_metrics_json. None if the array
+    lengths do not match.
+*/
+pub fn fit_mids(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32
+) -> Option<VolSurface> {
+    if
+        !(
+            call_price.len() == put_price.len() &&
+            put_price.len() == strike.len() &&
+            strike.len() == years_to_expiry.len()
+        )
+    {
+        return None;
+    }
+
+    let chain = OptionChain::from_mids(spot, call_price, put_price, strike, years_to_expiry);
+
+    Some(fit_surface(&chain, risk_free_rate, 20, 0.0001))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_hist;
+
+    fn flat_surface(vols: &[(f32, f32)]) -> VolSurface {
+        let smiles = vols
+            .iter()
+            .map(|(years, vol)| FittedSmile {
+                years_to_expiry: *years,
+                forward: 100.0,
+                coefficients: [*vol, 0.0, 0.0],
+                min_log_moneyness: -1.0,
+                max_log_moneyness: 1.0,
+                rmse: 0.0,
+            })
+            .collect();
+
+        VolSurface { spot: 100.0, smiles }
+    }
+
+    #[test]
+    fn fit_recovers_quadratic() {
+        let forward = 100.0;
+        let strikes: Vec<f32> = (70..=130).map(|k| k as f32).collect();
+        let vols = strikes
+            .iter()
+            .map(|k| {
+                let x = (k / forward).ln();
+                0.2 - 0.3 * x + 0.5 * x * x
+            })
+            .collect();
+        let smile = Smile { years_to_expiry: 0.5, forward, strikes, vols };
+
+        let fitted = fit_smile(&smile).unwrap();
+
+        assert!((fitted.coefficients[0] - 0.2).abs() < 1e-4);
+        assert!((fitted.coefficients[1] + 0.3).abs() < 1e-3);
+        assert!((fitted.coefficients[2] - 0.5).abs() < 1e-2);
+        assert!(fitted.rmse < 1e-4);
+
+        // Past the last fitted strike the variance continues with the slope of the fit
+        let x = (130.0f32 / forward).ln();
+        let vol = fitted.vol(x);
+        let variance_slope = 2.0 * vol * (fitted.coefficients[1] + 2.0 * fitted.coefficients[2] * x);
+        let expected = (vol * vol + variance_slope * 0.2).sqrt();
+        assert!((fitted.vol(x + 0.2) - expected).abs() < 1e-6);
+
+        let h = 1e-3;
+        let inner_slope = (fitted.vol(x) - fitted.vol(x - h)) / h;
+        let outer_slope = (fitted.vol(x + h) - fitted.vol(x)) / h;
+        assert!((inner_slope - outer_slope).abs() < 1e-2, "Inner: {} Outer: {}", inner_slope, outer_slope);
+    }
+
+    #[test]
+    fn total_variance_interpolation() {
+        let surface = flat_surface(&[(0.25, 0.2), (1.0, 0.3)]);
+
+        // w = 0.01 + (0.09 - 0.01) / 3
+        let expected = (0.036667f32 / 0.5).sqrt();
+
+        assert!((surface.vol(100.0, 0.5) - expected).abs() < 1e-4);
+        assert!((surface.vol(100.0, 0.1) - 0.2).abs() < 1e-6);
+        assert!((surface.vol(100.0, 2.0) - 0.3).abs() < 1e-6);
+        assert!(surface.vol(100.0, 0.0).is_nan());
+        assert_eq!(surface.grid(&[90.0, 100.0], &[0.25, 1.0]), vec![vec![0.2, 0.2], vec![0.3, 0.3]]);
+    }
+
+    #[test]
+    fn spy_surface() {
+        let chain = read_hist::get_spy_chain();
+        let surface = fit_surface(&chain, 0.05, 30, 0.0001);
+
+        assert!(!surface.smiles.is_empty());
+
+        for smile in surface.smiles.iter() {
+            let vol = surface.vol(smile.forward, smile.years_to_expiry);
+            assert!(vol > 0.03 && vol < 1.0, "Expiry: {} Vol: {}", smile.years_to_expiry, vol);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::smile;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/*
+    ATM volatility term structure.
+
+    From ATM vols sigma_i at expiries T_i the total variance is w_i = sigma_i^2 T_i, and the
+    forward variance between consecutive expiries is
+        (w_i - w_(i-1)) / (T_i - T_(i-1))
+    with the first expiry measured from today. Total variance must not decrease with
+    maturity, so a negative forward variance is a calendar arbitrage. Those points are flagged
+    and their forward vol is NaN.
+*/
+
+/// One expiry of the term structure
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TermPoint {
+    pub years_to_expiry: f32,
+    pub atm_vol: f32,
+    pub total_variance: f32,
+    pub forward_variance: f32,
+    pub forward_vol: f32,
+    pub calendar_arbitrage: bool,
+}
+
+// Term structure from ATM vols, sorted by expiry. Expiries at or before today are skipped.
+pub fn term_structure(years_to_expiry: &[f32], atm_vols: &[f32]) -> Vec<TermPoint> {
+    let mut points: Vec<(f32, f32)> = years_to_expiry
+        .iter()
+        .zip(atm_vols.iter())
+        .filter(|(years, vol)| **years > 0.0 && vol.is_finite())
+        .map(|(years, vol)| (*years, *vol))
+        .collect();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut term_structure: Vec<TermPoint> = Vec::with_capacity(points.len());
+    let (mut prev_years, mut prev_variance) = (0.0, 0.0);
+
+    for (years, vol) in points {
+        let total_variance = vol * vol * years;
+        let forward_variance = (total_variance - prev_variance) / (years - prev_years);
+        let calendar_arbitrage = forward_variance < 0.0;
+
+        term_structure.push(TermPoint {
+            years_to_expiry: years,
+            atm_vol: vol,
+            total_variance,
+            forward_variance,
+            forward_vol: if calendar_arbitrage { f32::NAN } else { forward_variance.sqrt() },
+            calendar_arbitrage,
+        });
+
+        prev_years = years;
+        prev_variance = total_variance;
+    }
+
+    term_structure
+}
+
+/*
+    Groups the output of vol32x8::implied_vol by years_to_expiry and interpolates each expiry's
+    implied volatility at the spot. Failed inversions (0 or the top of the bisection bracket)
+    are ignored. Returns (years_to_expiry, atm_vol) in ascending expiry.
+*/
+pub fn atm_vols(
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    implied_vol: &[f32]
+) -> (Vec<f32>, Vec<f32>) {
+    let mut expiries: Vec<f32> = years_to_expiry.to_vec();
+    expiries.sort_by(|a, b| a.total_cmp(b));
+    expiries.dedup();
+
+    let mut atm_years = Vec::new();
+    let mut atm_vols = Vec::new();
+
+    for expiry in expiries {
+        let mut points: Vec<(f32, f32)> = (0..strike.len())
+            .filter(|i| years_to_expiry[*i] == expiry && smile::is_valid_vol(implied_vol[*i]))
+            .map(|i| (strike[i], implied_vol[i]))
+            .collect();
+
+        if points.is_empty() {
+            continue;
+        }
+
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let (strikes, vols): (Vec<f32>, Vec<f32>) = points.into_iter().unzip();
+
+        atm_years.push(expiry);
+        atm_vols.push(smile::interpolate(&strikes, &vols, spot));
+    }
+
+    (atm_years, atm_vols)
+}
+
+pub fn term_structure_from_implied_vol(
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    implied_vol: &[f32]
+) -> Vec<TermPoint> {
+    let (years, vols) = atm_vols(spot, strike, years_to_expiry, implied_vol);
+    term_structure(&years, &vols)
+}
+
+/*
+    Frontend entry point taking the strikes, expiries and output of vol32x8::implied_vol.
+    Returns the term structure as a JSON array with one object per expiry.
+*/
+#[wasm_bindgen]
+pub fn term_structure_json(
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    implied_vol: &[f32]
+) -> String {
+    if !(strike.len() == years_to_expiry.len() && years_to_expiry.len() == implied_vol.len()) {
+        return String::from("[]");
+    }
+
+    let term_structure = term_structure_from_implied_vol(spot, strike, years_to_expiry, implied_vol);
+
+    serde_json::to_string(&term_structure).unwrap_or_else(|_| String::from("[]"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consts::OptionDir;
+    use crate::read_hist;
+    use crate::vol32x8;
+
+    #[test]
+    fn forward_vols() {
+        let term_structure = term_structure(&[1.0, 0.5], &[0.25, 0.2]);
+
+        assert_eq!(term_structure[0].years_to_expiry, 0.5);
+        assert!((term_structure[0].forward_vol - 0.2).abs() < 1e-6);
+
+        // w = 0.02 at 6 months and 0.0625 at 1 year, so the forward variance is 0.085
+        assert!((term_structure[1].total_variance - 0.0625).abs() < 1e-6);
+        assert!((term_structure[1].forward_variance - 0.085).abs() < 1e-6);
+        assert!(!term_structure[1].calendar_arbitrage);
+    }
+
+    #[test]
+    fn flags_calendar_arbitrage() {
+        // Total variance falls from 0.045 to 0.04
+        let term_structure = term_structure(&[0.5, 1.0], &[0.3, 0.2]);
+
+        assert!(term_structure[1].calendar_arbitrage);
+        assert!(term_structure[1].forward_vol.is_nan());
+    }
+
+    #[test]
+    fn spy_term_structure() {
+        let (spot, call_prices, call_strikes, _, _, years_to_expiry, _, _, _) =
+            read_hist::get_spy_data();
+
+        let n = call_prices.len();
+        let implied_vol = vol32x8::implied_vol(
+            OptionDir::CALL,
+            &call_prices,
+            &vec![spot; n],
+            &call_strikes,
+            &vec![0.05; n],
+            &vec![0.0; n],
+            &years_to_expiry,
+            30,
+            0.0001
+        );
+
+        let term_structure = term_structure_from_implied_vol(
+            spot,
+            &call_strikes,
+            &years_to_expiry,
+            &implied_vol
+        );
+
+        assert!(term_structure.len() > 10);
+        assert!(term_structure.windows(2).all(|w| w[0].years_to_expiry < w[1].years_to_expiry));
+    }
+}
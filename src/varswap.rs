@@ -0,0 +1,201 @@
+use crate::bs;
+use crate::smile::Smile;
+
+/*
+    Fair variance and volatility swap strikes per expiry.
+
+    The variance strike is replicated from the OTM strip of a composite smile (Demeterfi,
+    Derman, Kamal and Zou 1999). Taking the forward as the boundary between puts and calls,
+    the log-contract terms vanish and
+
+        K_var = 2/T * [ integral_0^F P(K)/K^2 dK + integral_F^inf C(K)/K^2 dK ]
+
+    with P and C undiscounted (forward) prices. Two corrections are applied to the raw strip:
+      - Discrete strikes: the integral is evaluated on a fine log-strike grid over the
+        interpolated smile instead of only at the quoted strikes.
+      - Wings: past the quoted strikes the wing volatilities are held flat out to
+        WING_STD_DEVS standard deviations, so the truncated tails are not lost.
+
+    The volatility swap strike applies the Brockhaus-Long convexity adjustment
+        K_vol ~ sqrt(K_var) - Var(V) / (8 K_var^(3/2))
+    with the realized variance V assumed lognormal with volatility vol_of_vol.
+*/
+
+const WING_STD_DEVS: f32 = 6.0;
+const NUM_GRID_POINTS: usize = 2001;
+
+/// Fair strikes of a single expiry. Variances are annualized, volatilities are decimals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarianceSwapStrike {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    // Trapezoid over the quoted strikes only, before corrections
+    pub strip_variance: f32,
+    pub variance_strike: f32,
+    pub vol_swap_strike: f32,
+    pub convexity_adjustment: f32,
+}
+
+// Undiscounted price of the OTM option at strike, put below the forward and call above
+fn otm_forward_price(smile: &Smile, strike: f32) -> f32 {
+    let vol = smile.vol_at(strike);
+
+    if strike < smile.forward {
+        bs::put_price(smile.forward, strike, vol, 0.0, 0.0, smile.years_to_expiry)
+    } else {
+        bs::call_price(smile.forward, strike, vol, 0.0, 0.0, smile.years_to_expiry)
+    }
+}
+
+// Trapezoid rule over the quoted strikes, with the forward inserted as a node
+fn strip_variance(smile: &Smile) -> f32 {
+    let mut strikes = smile.strikes.clone();
+
+    if !strikes.contains(&smile.forward) {
+        strikes.push(smile.forward);
+        strikes.sort_by(|a, b| a.total_cmp(b));
+    }
+
+    let integrand: Vec<f32> = strikes
+        .iter()
+        .map(|strike| otm_forward_price(smile, *strike) / (strike * strike))
+        .collect();
+
+    let mut sum = 0.0;
+
+    for i in 1..strikes.len() {
+        sum += ((strikes[i] - strikes[i - 1]) * (integrand[i] + integrand[i - 1])) / 2.0;
+    }
+
+    (2.0 / smile.years_to_expiry) * sum
+}
+
+// Replication integral in log-moneyness x = ln(K / F), where dK / K^2 = e^(-x) / F dx
+fn replicated_variance(smile: &Smile) -> f32 {
+    let forward = smile.forward;
+    let sqrt_t = smile.years_to_expiry.sqrt();
+    let log_moneyness = smile.log_moneyness();
+
+    let low = log_moneyness[0].min(-WING_STD_DEVS * smile.vols[0] * sqrt_t);
+    let high = log_moneyness[log_moneyness.len() - 1].max(
+        WING_STD_DEVS * smile.vols[smile.vols.len() - 1] * sqrt_t
+    );
+    let step = (high - low) / ((NUM_GRID_POINTS - 1) as f32);
+
+    let mut sum = 0.0;
+
+    for i in 0..NUM_GRID_POINTS {
+        let x = low + (i as f32) * step;
+        let weight = if i == 0 || i == NUM_GRID_POINTS - 1 { 0.5 } else { 1.0 };
+        let price = otm_forward_price(smile, forward * x.exp());
+
+        sum += (weight * price * (-x).exp()) / forward;
+    }
+
+    (2.0 / smile.years_to_expiry) * sum * step
+}
+
+pub fn variance_swap_strike(smile: &Smile, vol_of_vol: f32) -> Option<VarianceSwapStrike> {
+    if smile.strikes.is_empty() || smile.years_to_expiry <= 0.0 {
+        return None;
+    }
+
+    let variance_strike = replicated_variance(smile);
+
+    if variance_strike.is_nan() || variance_strike <= 0.0 {
+        return None;
+    }
+
+    // Variance of a lognormal realized variance with mean K_var
+    let variance_of_variance =
+        variance_strike * variance_strike * ((vol_of_vol * vol_of_vol * smile.years_to_expiry).exp() - 1.0);
+    let convexity_adjustment = variance_of_variance / (8.0 * variance_strike.powf(1.5));
+
+    Some(VarianceSwapStrike {
+        years_to_expiry: smile.years_to_expiry,
+        forward: smile.forward,
+        strip_variance: strip_variance(smile),
+        variance_strike,
+        vol_swap_strike: variance_strike.sqrt() - convexity_adjustment,
+        convexity_adjustment,
+    })
+}
+
+// Fair strikes for every smile, in the order of the smiles (ascending expiry for otm_smiles)
+pub fn variance_swap_term_structure(smiles: &[Smile], vol_of_vol: f32) -> Vec<VarianceSwapStrike> {
+    smiles
+        .iter()
+        .filter_map(|smile| variance_swap_strike(smile, vol_of_vol))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_hist;
+    use crate::smile;
+
+    fn flat_smile(vol: f32, low: i32, high: i32) -> Smile {
+        let strikes: Vec<f32> = (low..=high).map(|k| k as f32).collect();
+        let vols = vec![vol; strikes.len()];
+
+        Smile {
+            years_to_expiry: 0.25,
+            forward: 100.0,
+            strikes,
+            vols,
+        }
+    }
+
+    #[test]
+    fn flat_smile_recovers_variance() {
+        let result = variance_swap_strike(&flat_smile(0.2, 50, 200), 0.0).unwrap();
+
+        assert!((result.variance_strike - 0.04).abs() < 0.0002, "Got: {}", result.variance_strike);
+        assert!((result.vol_swap_strike - 0.2).abs() < 0.0005);
+        assert_eq!(result.convexity_adjustment, 0.0);
+    }
+
+    #[test]
+    fn wings_extrapolated() {
+        // Strikes only cover about one standard deviation, so the raw strip misses the tails
+        let result = variance_swap_strike(&flat_smile(0.2, 90, 110), 0.0).unwrap();
+
+        assert!(result.strip_variance < 0.035, "Strip: {}", result.strip_variance);
+        assert!((result.variance_strike - 0.04).abs() < 0.0002, "Got: {}", result.variance_strike);
+    }
+
+    #[test]
+    fn convexity_lowers_vol_strike() {
+        let result = variance_swap_strike(&flat_smile(0.2, 50, 200), 1.0).unwrap();
+
+        assert!(result.convexity_adjustment > 0.0);
+        assert!(result.vol_swap_strike < result.variance_strike.sqrt());
+    }
+
+    #[test]
+    fn skew_raises_variance() {
+        let mut smile = flat_smile(0.2, 50, 200);
+        smile.vols = smile.strikes
+            .iter()
+            .map(|k| 0.2 - 0.2 * (k / 100.0).ln())
+            .collect();
+
+        let result = variance_swap_strike(&smile, 0.0).unwrap();
+
+        assert!(result.variance_strike > 0.04, "Got: {}", result.variance_strike);
+    }
+
+    #[test]
+    fn spy_term_structure() {
+        let chain = read_hist::get_spy_chain();
+        let smiles = smile::otm_smiles(&chain, 0.05, 0.01, 30, 0.0001);
+        let term_structure = variance_swap_term_structure(&smiles, 0.0);
+
+        assert!(!term_structure.is_empty());
+
+        for strike in term_structure.iter() {
+            assert!(strike.vol_swap_strike > 0.05 && strike.vol_swap_strike < 1.0);
+        }
+    }
+}
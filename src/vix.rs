@@ -0,0 +1,350 @@
+use crate::chain::{ OptionChain, OptionQuote };
+use crate::consts::OptionDir;
+use wasm_bindgen::prelude::*;
+
+/*
+    Model-free 30 day variance index following the CBOE VIX white paper.
+
+    For the near and next term, two expiries with more than 23 and less than 37 days to expiration
+    bracketing 30 days where possible:
+      1. F from put-call parity at the strike with the smallest call/put mid difference
+      2. K0 is the first strike at or below F
+      3. OTM puts below K0 and OTM calls above K0 are selected, moving away from K0 and
+         skipping zero bids, until two consecutive zero bids are found
+      4. sigma^2 = 2/T * sum(dK/K^2 * e^(RT) * Q(K)) - 1/T * (F/K0 - 1)^2, where Q(K0) is the
+         average of the call and put mids and dK is half the distance between neighbouring
+         selected strikes
+    The two term variances are then interpolated in total variance to 30 days.
+*/
+
+const TARGET_YEARS: f32 = 30.0 / 365.0;
+const MIN_TERM_YEARS: f32 = 23.0 / 365.0;
+const MAX_TERM_YEARS: f32 = 37.0 / 365.0;
+
+/// Variance contribution of a single expiry
+#[derive(Debug, Clone, PartialEq)]
+pub struct TermVariance {
+    pub years_to_expiry: f32,
+    pub forward: f32,
+    pub k0: f32,
+    pub variance: f32,
+    pub num_strikes: usize,
+}
+
+/// Annualized 30 day volatility index in vol points, with the two terms it was built from
+#[derive(Debug, Clone, PartialEq)]
+pub struct VixResult {
+    pub index: f32,
+    pub near: TermVariance,
+    pub next: TermVariance,
+}
+
+// Strike with its call and put quotes, sorted ascending by strike
+fn strike_pairs(expiry: &OptionChain) -> Vec<(f32, Option<&OptionQuote>, Option<&OptionQuote>)> {
+    let mut pairs: Vec<(f32, Option<&OptionQuote>, Option<&OptionQuote>)> = Vec::new();
+
+    for quote in expiry.quotes.iter() {
+        let idx = match pairs.iter().position(|(strike, _, _)| *strike == quote.strike) {
+            Some(idx) => idx,
+            None => {
+                pairs.push((quote.strike, None, None));
+                pairs.len() - 1
+            }
+        };
+
+        match quote.option_dir {
+            OptionDir::CALL if pairs[idx].1.is_none() => {
+                pairs[idx].1 = Some(quote);
+            }
+            OptionDir::PUT if pairs[idx].2.is_none() => {
+                pairs[idx].2 = Some(quote);
+            }
+            _ => {}
+        }
+    }
+
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    pairs
+}
+
+// Walks away from K0 collecting (strike, mid) of quotes with a bid, stopping at two
+// consecutive zero bids
+fn select_otm<'a>(quotes: impl Iterator<Item = (f32, Option<&'a OptionQuote>)>) -> Vec<(f32, f32)> {
+    let mut selected = Vec::new();
+    let mut zero_bids = 0;
+
+    for (strike, quote) in quotes {
+        match quote {
+            Some(quote) if quote.bid > 0.0 => {
+                zero_bids = 0;
+                selected.push((strike, quote.mid()));
+            }
+            _ => {
+                zero_bids += 1;
+
+                if zero_bids >= 2 {
+                    break;
+                }
+            }
+        }
+    }
+
+    selected
+}
+
+pub fn term_variance(
+    chain: &OptionChain,
+    years_to_expiry: f32,
+    risk_free_rate: f32
+) -> Option<TermVariance> {
+    let forward = chain.parity_forward(years_to_expiry, risk_free_rate)?;
+    let expiry = chain.expiry(years_to_expiry);
+    let pairs = strike_pairs(&expiry);
+
+    let k0_idx = pairs.iter().rposition(|(strike, _, _)| *strike <= forward)?;
+    let (k0, k0_call, k0_put) = pairs[k0_idx];
+
+    let k0_price = match (k0_call, k0_put) {
+        (Some(call), Some(put)) => (call.mid() + put.mid()) / 2.0,
+        (Some(quote), None) | (None, Some(quote)) => quote.mid(),
+        (None, None) => {
+            return None;
+        }
+    };
+
+    let mut puts = select_otm(
+        pairs[..k0_idx]
+            .iter()
+            .rev()
+            .map(|(strike, _, put)| (*strike, *put))
+    );
+    let calls = select_otm(
+        pairs[k0_idx + 1..]
+            .iter()
+            .map(|(strike, call, _)| (*strike, *call))
+    );
+
+    puts.reverse();
+
+    let mut strip = puts;
+    strip.push((k0, k0_price));
+    strip.extend(calls);
+
+    if strip.len() < 2 {
+        return None;
+    }
+
+    let growth = (risk_free_rate * years_to_expiry).exp();
+    let n = strip.len();
+    let mut sum = 0.0;
+
+    for i in 0..n {
+        let delta_strike = if i == 0 {
+            strip[1].0 - strip[0].0
+        } else if i == n - 1 {
+            strip[n - 1].0 - strip[n - 2].0
+        } else {
+            (strip[i + 1].0 - strip[i - 1].0) / 2.0
+        };
+        let (strike, price) = strip[i];
+
+        sum += (delta_strike / (strike * strike)) * growth * price;
+    }
+
+    let variance =
+        (2.0 / years_to_expiry) * sum -
+        (1.0 / years_to_expiry) * (forward / k0 - 1.0) * (forward / k0 - 1.0);
+
+    Some(TermVariance {
+        years_to_expiry,
+        forward,
+        k0,
+        variance,
+        num_strikes: n,
+    })
+}
+
+// Expiries used for the near and next term, taken from those inside the 23 to 37 day window: the
+// last one at or before 30 days and the one after it. Returns None with fewer than two expiries in
+// the window.
+fn select_terms(expiries: &[f32]) -> Option<(f32, f32)> {
+    let window: Vec<f32> = expiries
+        .iter()
+        .copied()
+        .filter(|years| *years > MIN_TERM_YEARS && *years < MAX_TERM_YEARS)
+        .collect();
+
+    if window.len() < 2 {
+        return None;
+    }
+
+    let near_idx = window
+        .iter()
+        .rposition(|years| *years <= TARGET_YEARS)
+        .unwrap_or(0)
+        .min(window.len() - 2);
+
+    Some((window[near_idx], window[near_idx + 1]))
+}
+
+pub fn vix(chain: &OptionChain, risk_free_rate: f32) -> Option<VixResult> {
+    // Expiries that expire today have no time value left to measure
+    let expiries: Vec<f32> = chain
+        .expiries()
+        .into_iter()
+        .filter(|years| *years > 0.0)
+        .collect();
+    let (near_years, next_years) = select_terms(&expiries)?;
+
+    let near = term_variance(chain, near_years, risk_free_rate)?;
+    let next = term_variance(chain, next_years, risk_free_rate)?;
+
+    let near_weight = (next_years - TARGET_YEARS) / (next_years - near_years);
+    let next_weight = (TARGET_YEARS - near_years) / (next_years - near_years);
+    let variance =
+        (near_years * near.variance * near_weight + next_years * next.variance * next_weight) /
+        TARGET_YEARS;
+
+    Some(VixResult {
+        index: 100.0 * variance.max(0.0).sqrt(),
+        near,
+        next,
+    })
+}
+
+/*
+    Calculates the volatility index from the mid-price arrays used by vol32x8::implied_vol.
+    Mids stand in for bids when applying the zero-bid cutoff. Returns 0 if the index cannot be
+    calculated, e.g. when fewer than two expiries are between 23 and 37 days out.
+*/
+#[wasm_bindgen]
+pub fn vix_index(
+    call_price: &[f32],
+    put_price: &[f32],
+    spot: f32,
+    strike: &[f32],
+    years_to_expiry: &[f32],
+    risk_free_rate: f32
+) -> f32 {
+    if
+        !(
+            call_price.len() == put_price.len() &&
+            put_price.len() == strike.len() &&
+            strike.len() == years_to_expiry.len()
+        )
+    {
+        return 0.0;
+    }
+
+    let chain = OptionChain::from_mids(spot, call_price, put_price, strike, years_to_expiry);
+
+    vix(&chain, risk_free_rate).map_or(0.0, |result| result.index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bs;
+    use crate::read_hist;
+
+    fn quote(option_dir: OptionDir, strike: f32, bid: f32, ask: f32) -> OptionQuote {
+        OptionQuote {
+            option_dir,
+            name: String::new(),
+            strike,
+            years_to_expiry: 0.1,
+            bid,
+            ask,
+            volume: 0,
+            open_interest: 0,
+        }
+    }
+
+    #[test]
+    fn zero_bid_cutoff() {
+        let quotes = [
+            quote(OptionDir::PUT, 95.0, 0.5, 0.6),
+            quote(OptionDir::PUT, 90.0, 0.0, 0.1),
+            quote(OptionDir::PUT, 85.0, 0.1, 0.2),
+            quote(OptionDir::PUT, 80.0, 0.0, 0.1),
+            quote(OptionDir::PUT, 75.0, 0.0, 0.1),
+            quote(OptionDir::PUT, 70.0, 0.1, 0.2),
+        ];
+
+        let selected = select_otm(quotes.iter().map(|quote| (quote.strike, Some(quote))));
+
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].0, 95.0);
+        assert_eq!(selected[1].0, 85.0);
+    }
+
+    #[test]
+    fn term_selection() {
+        let days = |days: &[f32]| days.iter().map(|d| d / 365.0).collect::<Vec<f32>>();
+
+        assert_eq!(select_terms(&days(&[7.0, 22.0, 29.0, 36.0, 43.0])), Some((29.0 / 365.0, 36.0 / 365.0)));
+        assert_eq!(select_terms(&days(&[24.0, 27.0, 30.0, 33.0])), Some((30.0 / 365.0, 33.0 / 365.0)));
+        assert_eq!(select_terms(&days(&[31.0, 35.0])), Some((31.0 / 365.0, 35.0 / 365.0)));
+        assert_eq!(select_terms(&days(&[24.0, 28.0])), Some((24.0 / 365.0, 28.0 / 365.0)));
+        assert_eq!(select_terms(&days(&[20.0, 30.0, 40.0])), None);
+        assert_eq!(select_terms(&days(&[23.0, 37.0])), None);
+    }
+
+    #[test]
+    fn flat_vol_matches_black_scholes() {
+        // The model-free variance of a flat smile is the Black-Scholes variance, up to the
+        // discretization of the strike strip
+        let (spot, volatility, rate) = (100.0, 0.2, 0.03);
+        let mut quotes = Vec::new();
+
+        for days in [20.0, 27.0, 34.0, 45.0] {
+            let years_to_expiry = days / 365.0;
+
+            for strike in (200..=800).map(|k| (k as f32) / 4.0) {
+                for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+                    let price = bs::price(option_dir, spot, strike, volatility, rate, 0.0, years_to_expiry);
+
+                    quotes.push(OptionQuote { years_to_expiry, ..quote(option_dir, strike, price, price) });
+                }
+            }
+        }
+
+        let result = vix(&OptionChain::new(spot, quotes), rate).unwrap();
+
+        assert_eq!(result.near.years_to_expiry, 27.0 / 365.0);
+        assert_eq!(result.next.years_to_expiry, 34.0 / 365.0);
+        assert!((result.index - 20.0).abs() < 0.01, "Index: {}", result.index);
+    }
+
+    #[test]
+    fn spy_reference() {
+        // Reference values from an independent f64 implementation of the white paper over
+        // data/SPYQuoteData.dat with R = 5%. Quoted Feb 8 2024 at 11:23 AM, the near term is
+        // Mar 08 2024 (29 days 4h 37m to the close) and the next term Mar 15 2024.
+        let chain = read_hist::get_spy_chain();
+        let result = vix(&chain, 0.05).unwrap();
+
+        assert!((result.near.years_to_expiry * 365.0 - 29.19236).abs() < 1e-4);
+        assert!((result.next.years_to_expiry * 365.0 - 36.19236).abs() < 1e-4);
+        assert_eq!((result.near.num_strikes, result.next.num_strikes), (99, 181));
+
+        let expected = [
+            (result.near.forward, 499.7239),
+            (result.near.k0, 499.0),
+            (result.near.variance, 0.01661034),
+            (result.next.forward, 499.5779),
+            (result.next.k0, 499.0),
+            (result.next.variance, 0.0186521),
+            (result.index, 12.9979),
+        ];
+
+        for (actual, expected) in expected {
+            assert!(
+                (actual - expected).abs() < 1e-3 * expected,
+                "Got: {}, Expected: {}",
+                actual,
+                expected
+            );
+        }
+    }
+}
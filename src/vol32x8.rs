@@ -1,11 +1,21 @@
-use wide::*;
-use crate::consts::OptionDir;
+use crate::consts::{ OptionDir, VolMethod };
 use crate::bs32x8;
-use crate::read_hist;
-use bytemuck::cast;
+use crate::simd;
 use rayon::prelude::*;
 use wasm_bindgen::prelude::*;
-use crate::bs;
+
+// Threads the kernels below are split across. In the browser this is 1 unless the page was
+// built with the wasm-threads feature and init_thread_pool has been awaited.
+#[wasm_bindgen]
+pub fn num_threads() -> usize {
+    rayon::current_num_threads()
+}
+
+// Instruction set the kernels below run on, one of the simd::Backend names such as "avx2"
+#[wasm_bindgen]
+pub fn simd_backend() -> String {
+    simd::backend().name().to_string()
+}
 
 /* 
     Source: https://github.com/ronniec95/black_scholes
@@ -22,6 +32,7 @@ use crate::bs;
 */
 
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn implied_vol(
     option_dir: OptionDir,
     price: &[f32],
@@ -54,45 +65,232 @@ pub fn implied_vol(
         return Vec::new();
     }
 
-    let implied_vol = (0..(num_options - 1) / 8 + 1)
-        .into_par_iter()
-        .map(|idx| {
-            let start_idx = idx * 8;
-            let end_idx = std::cmp::min(num_options, start_idx + 8);
-
-            let price = f32x8::from(&price[start_idx..end_idx]);
-            let spot = f32x8::from(&spot[start_idx..end_idx]);
-            let strike = f32x8::from(&strike[start_idx..end_idx]);
-            let years_to_expiry = f32x8::from(&years_to_expiry[start_idx..end_idx]);
-            let risk_free_rate = f32x8::from(&risk_free_rate[start_idx..end_idx]);
-            let dividend_yield = f32x8::from(&dividend_yield[start_idx..end_idx]);
-
-            let res: [f32; 8] = cast(
-                bs32x8::implied_vol_f32x8(
-                    option_dir,
-                    price,
-                    spot,
-                    strike,
-                    risk_free_rate,
-                    dividend_yield,
-                    years_to_expiry,
-                    threshold,
-                    max_iterations
-                )
-            );
+    let kernel = bs32x8::ImpliedVolKernel { option_dir, max_iterations, threshold };
+    let [vol] = simd::map(&kernel, [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry]);
 
-            res[0..end_idx - start_idx].to_vec()
-        })
-        .reduce(
-            || Vec::new(),
-            |mut acc: Vec<f32>, x: Vec<f32>| {
-                // Parallel reduction, in this case, appending results to the accumulator
-                acc.extend_from_slice(&x);
-                acc
-            }
-        );
+    vol
+}
+
+/*
+    Closed-form approximations of implied_vol for quick previews (see bs32x8::approx_vol),
+    with the same inputs but no iterations. Brenner-Subrahmanyam is only accurate at the money,
+    Corrado-Miller and Li also hold up for moderately in or out of the money options.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn implied_vol_approx(
+    option_dir: OptionDir,
+    price: &[f32],
+    spot: &[f32],
+    strike: &[f32],
+    risk_free_rate: &[f32],
+    dividend_yield: &[f32],
+    years_to_expiry: &[f32],
+    method: VolMethod
+) -> Vec<f32> {
+    if
+        !(
+            price.len() == spot.len() &&
+            spot.len() == strike.len() &&
+            strike.len() == risk_free_rate.len() &&
+            risk_free_rate.len() == dividend_yield.len() &&
+            dividend_yield.len() == years_to_expiry.len()
+        )
+    {
+        return Vec::new();
+    }
+
+    let kernel = bs32x8::ApproxVolKernel { option_dir, method };
+    let [vol] = simd::map(&kernel, [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry]);
+
+    vol
+}
+
+/*
+    Warm started implied_vol for intraday recomputation. Each option's bisection starts from
+    +/- half_width around one Newton step from the guess instead of the full [0, 5] bracket,
+    where the guess is typically the option's vol from the previous snapshot or the fitted
+    surface. Options whose root is outside of that bracket continue on the side of it that
+    holds the root, and options whose guess is not a positive number (e.g. NaN for a new
+    contract) are solved from the full bracket, so a bad guess costs time but not accuracy.
+
+    With half_width = 0.005 and threshold = 0.0001 the bisection takes about 7 steps instead of 16
+    for options whose root is inside the narrow bracket. A vector runs until all of its lanes
+    converge, so one option falling back to the full bracket costs its whole vector the full 16
+    steps; on the AAPL calls after a 0.5% move this makes the warm start about 1.5x faster.
+*/
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn implied_vol_guess(
+    option_dir: OptionDir,
+    price: &[f32],
+    spot: &[f32],
+    strike: &[f32],
+    risk_free_rate: &[f32],
+    dividend_yield: &[f32],
+    years_to_expiry: &[f32],
+    guess: &[f32],
+    half_width: f32,
+    max_iterations: i32,
+    threshold: f32
+) -> Vec<f32> {
+    if
+        !(
+            price.len() == spot.len() &&
+            spot.len() == strike.len() &&
+            strike.len() == risk_free_rate.len() &&
+            risk_free_rate.len() == dividend_yield.len() &&
+            dividend_yield.len() == years_to_expiry.len() &&
+            years_to_expiry.len() == guess.len() &&
+            1 < max_iterations &&
+            0.0 < threshold
+        )
+    {
+        return Vec::new();
+    }
+
+    let num_options = spot.len();
+
+    if num_options == 0 {
+        return Vec::new();
+    }
+
+    // The kernel solves in place, starting from the guess
+    let mut vol = guess.to_vec();
+    let kernel = bs32x8::ImpliedVolGuessKernel { option_dir, half_width, max_iterations, threshold };
+    simd::map_into(
+        &kernel,
+        [price, spot, strike, risk_free_rate, dividend_yield, years_to_expiry],
+        [&mut vol]
+    );
+
+    vol
+}
+
+/*
+    Black-Scholes price of every option, with the same input layout as delta. Used to mark
+    positions to the fitted surface's vols.
+*/
+#[wasm_bindgen]
+pub fn price(
+    option_dir: OptionDir,
+    spot: &[f32],
+    strike: &[f32],
+    volatility: &[f32],
+    risk_free_rate: &[f32],
+    dividend_yield: &[f32],
+    years_to_expiry: &[f32]
+) -> Vec<f32> {
+    if
+        !(
+            spot.len() == strike.len() &&
+            strike.len() == volatility.len() &&
+            volatility.len() == risk_free_rate.len() &&
+            risk_free_rate.len() == dividend_yield.len() &&
+            dividend_yield.len() == years_to_expiry.len()
+        )
+    {
+        return Vec::new();
+    }
+
+    let num_options = spot.len();
+
+    if num_options == 0 {
+        return Vec::new();
+    }
+
+    let kernel = bs32x8::PriceKernel { option_dir };
+    let [price] = simd::map(&kernel, [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry]);
+
+    price
+}
 
-    implied_vol
+/*
+    Black-Scholes delta of every option, with the same input layout as implied_vol.
+    Typically called with the output of implied_vol as the volatility.
+*/
+#[wasm_bindgen]
+pub fn delta(
+    option_dir: OptionDir,
+    spot: &[f32],
+    strike: &[f32],
+    volatility: &[f32],
+    risk_free_rate: &[f32],
+    dividend_yield: &[f32],
+    years_to_expiry: &[f32]
+) -> Vec<f32> {
+    if
+        !(
+            spot.len() == strike.len() &&
+            strike.len() == volatility.len() &&
+            volatility.len() == risk_free_rate.len() &&
+            risk_free_rate.len() == dividend_yield.len() &&
+            dividend_yield.len() == years_to_expiry.len()
+        )
+    {
+        return Vec::new();
+    }
+
+    let num_options = spot.len();
+
+    if num_options == 0 {
+        return Vec::new();
+    }
+
+    let kernel = bs32x8::DeltaKernel { option_dir };
+    let [delta] = simd::map(&kernel, [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry]);
+
+    delta
+}
+
+/// Black-Scholes Greeks per option. Theta is per year, vega and rho per unit (1.0 = 100%) move.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Greeks {
+    pub delta: Vec<f32>,
+    pub gamma: Vec<f32>,
+    pub vega: Vec<f32>,
+    pub theta: Vec<f32>,
+    pub rho: Vec<f32>,
+}
+
+/*
+    All first order Greeks and gamma of every option, with the same input layout as delta.
+    Returns empty vectors if the input lengths do not match.
+*/
+pub fn greeks(
+    option_dir: OptionDir,
+    spot: &[f32],
+    strike: &[f32],
+    volatility: &[f32],
+    risk_free_rate: &[f32],
+    dividend_yield: &[f32],
+    years_to_expiry: &[f32]
+) -> Greeks {
+    if
+        !(
+            spot.len() == strike.len() &&
+            strike.len() == volatility.len() &&
+            volatility.len() == risk_free_rate.len() &&
+            risk_free_rate.len() == dividend_yield.len() &&
+            dividend_yield.len() == years_to_expiry.len()
+        )
+    {
+        return Greeks::default();
+    }
+
+    let num_options = spot.len();
+
+    if num_options == 0 {
+        return Greeks::default();
+    }
+
+    let kernel = bs32x8::GreeksKernel { option_dir };
+    let [delta, gamma, vega, theta, rho] = simd::map(
+        &kernel,
+        [spot, strike, volatility, risk_free_rate, dividend_yield, years_to_expiry]
+    );
+
+    Greeks { delta, gamma, vega, theta, rho }
 }
 
 /* 
@@ -124,64 +322,19 @@ pub fn parity_interest_rate(
         return 0.0;
     }
 
-    let rate = (0..(num_options - 1) / 8 + 1)
-        .into_par_iter()
-        .map(|idx| {
-            let start_idx = idx * 8;
-            let end_idx = std::cmp::min(num_options, start_idx + 8);
-
-            if end_idx - start_idx < 8 {
-                // Less than 8 elements left, so we calculate iteratively.
-                // This is because if we call parity_interest_rate_f32x8 with less than 8 elements,
-                // some of the elements will be set to NaN due to zero division.
-                let mut sum = 0.0;
-
-                for counter in 0..end_idx - start_idx {
-                    sum += bs::parity_interest_rate(
-                        call_price[start_idx + counter],
-                        put_price[start_idx + counter],
-                        spot[start_idx + counter],
-                        strike[start_idx + counter],
-                        years_to_expiry[start_idx + counter]
-                    );
-                }
-
-                sum
-            } else {
-                // Utilize SIMD
-                let call_price = f32x8::from(&call_price[start_idx..end_idx]);
-                let put_price = f32x8::from(&put_price[start_idx..end_idx]);
-                let spot = f32x8::from(&spot[start_idx..end_idx]);
-                let strike = f32x8::from(&strike[start_idx..end_idx]);
-                let years_to_expiry = f32x8::from(&years_to_expiry[start_idx..end_idx]);
-
-                let res: f32x8 = bs32x8::parity_interest_rate_f32x8(
-                    call_price,
-                    put_price,
-                    spot,
-                    strike,
-                    years_to_expiry
-                );
-
-                res.reduce_add()
-            }
-        })
-        .reduce(
-            || 0.0,
-            |mut acc: f32, x: f32| {
-                // Parallel reduction, in this case, appending results to the accumulator
-                acc + x
-            }
-        );
+    let [rates] = simd::map(&bs32x8::ParityInterestRateKernel, [call_price, put_price, spot, strike, years_to_expiry]);
 
-    rate / (num_options as f32)
+    rates.par_iter().sum::<f32>() / (num_options as f32)
 }
 
+// implied_vol_check_large leaves the put columns unused and implied_vol_check_bad compares
+// the length to zero
 #[cfg(test)]
+#[allow(unused_variables, clippy::len_zero)]
 mod tests {
     use super::*;
     use crate::bs;
-    use bytemuck::cast;
+    use crate::read_hist;
 
     #[test]
     fn interest_rate_check_small() {
@@ -282,6 +435,167 @@ mod tests {
         assert!(vol.len() == n, "Num results: {}", n);
     }
 
+    #[test]
+    fn every_remainder_length() {
+        // Distinct options, so a lane picking up its neighbour's inputs shows
+        let option = |idx: usize| {
+            let strike = 90.0 + idx as f32;
+            let years_to_expiry = 0.25 + 0.01 * idx as f32;
+            (strike, years_to_expiry)
+        };
+
+        for len in 0..=40 {
+            let (strike, years_to_expiry): (Vec<f32>, Vec<f32>) = (0..len).map(option).unzip();
+            let spot = vec![100.0; len];
+            let rate = vec![0.02; len];
+            let dividend = vec![0.0; len];
+            let vol = vec![0.25; len];
+
+            let call_greeks = greeks(OptionDir::CALL, &spot, &strike, &vol, &rate, &dividend, &years_to_expiry);
+            let put_greeks = greeks(OptionDir::PUT, &spot, &strike, &vol, &rate, &dividend, &years_to_expiry);
+            let call_price: Vec<f32> = (0..len).map(|idx| 10.0 - 0.1 * idx as f32).collect();
+            let implied = implied_vol(
+                OptionDir::CALL,
+                &call_price,
+                &spot,
+                &strike,
+                &rate,
+                &dividend,
+                &years_to_expiry,
+                20,
+                0.0001
+            );
+
+            assert_eq!(implied.len(), len);
+            assert_eq!(call_greeks.delta.len(), len);
+
+            for idx in 0..len {
+                let single = |values: &[f32]| [values[idx]];
+                let expected = implied_vol(
+                    OptionDir::CALL,
+                    &single(&call_price),
+                    &single(&spot),
+                    &single(&strike),
+                    &single(&rate),
+                    &single(&dividend),
+                    &single(&years_to_expiry),
+                    20,
+                    0.0001
+                );
+                let expected_delta = delta(OptionDir::PUT, &single(&spot), &single(&strike), &single(&vol), &single(&rate), &single(&dividend), &single(&years_to_expiry));
+
+                assert_eq!(implied[idx], expected[0], "len {} idx {}", len, idx);
+                assert_eq!(put_greeks.delta[idx], expected_delta[0], "len {} idx {}", len, idx);
+                assert!(call_greeks.gamma[idx].is_finite() && call_greeks.gamma[idx] > 0.0, "len {} idx {}", len, idx);
+            }
+
+            // The mean of equal rates is that rate, with no NaN from the padded lanes
+            if len > 0 {
+                let rate = parity_interest_rate(&vec![8.247; len], &vec![5.785; len], &vec![100.0; len], &vec![100.0; len], &vec![0.5; len]);
+                assert!((rate - 0.0498).abs() < 0.0001, "len {} rate {}", len, rate);
+            }
+        }
+    }
+
+    #[test]
+    fn implied_vol_check_approx() {
+        let price = [8.0, 6.0, 4.5, 3.0, 7.0, 5.5, 4.0, 3.0, 6.0];
+        let n = price.len();
+        let spot = vec![120.0; n];
+        let strike = [115.0, 118.0, 120.0, 122.0, 115.0, 118.0, 120.0, 122.0, 120.0];
+        let risk_free_rate = vec![0.02; n];
+        let dividend_yield = vec![0.0; n];
+        let years_to_expiry = vec![0.25; n];
+
+        let expected = implied_vol(
+            OptionDir::CALL,
+            &price,
+            &spot,
+            &strike,
+            &risk_free_rate,
+            &dividend_yield,
+            &years_to_expiry,
+            20,
+            0.0001
+        );
+
+        for method in [VolMethod::CorradoMiller, VolMethod::Li] {
+            let vol = implied_vol_approx(
+                OptionDir::CALL,
+                &price,
+                &spot,
+                &strike,
+                &risk_free_rate,
+                &dividend_yield,
+                &years_to_expiry,
+                method
+            );
+
+            assert_eq!(vol.len(), n);
+
+            for (vol, expected) in vol.iter().zip(expected.iter()) {
+                assert!((vol - expected).abs() < 0.01, "{:?} Got: {}, Expected: {}", method, vol, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn greeks_check_medium() {
+        let n = 11;
+        let strike: Vec<f32> = (0..n).map(|i| 100.0 + (i as f32)).collect();
+
+        let greeks = greeks(
+            OptionDir::PUT,
+            &vec![105.0; n],
+            &strike,
+            &vec![0.2; n],
+            &vec![0.02; n],
+            &vec![0.0; n],
+            &vec![0.5; n]
+        );
+        let delta = delta(
+            OptionDir::PUT,
+            &vec![105.0; n],
+            &strike,
+            &vec![0.2; n],
+            &vec![0.02; n],
+            &vec![0.0; n],
+            &vec![0.5; n]
+        );
+
+        assert_eq!(greeks.delta.len(), n);
+        assert_eq!(greeks.rho.len(), n);
+        assert_eq!(greeks.delta, delta);
+    }
+
+    #[test]
+    fn price_check_medium() {
+        let n = 11;
+        let strike: Vec<f32> = (0..n).map(|i| 100.0 + (i as f32)).collect();
+
+        for option_dir in [OptionDir::CALL, OptionDir::PUT] {
+            let price = price(
+                option_dir,
+                &vec![105.0; n],
+                &strike,
+                &vec![0.2; n],
+                &vec![0.02; n],
+                &vec![0.01; n],
+                &vec![0.5; n]
+            );
+
+            assert_eq!(price.len(), n);
+
+            for (price, strike) in price.iter().zip(strike.iter()) {
+                let expected = bs::price(option_dir, 105.0, *strike, 0.2, 0.02, 0.01, 0.5);
+
+                assert!((price - expected).abs() < 0.001, "Got: {}, Expected: {}", price, expected);
+            }
+        }
+
+        assert!(price(OptionDir::CALL, &[105.0], &[100.0], &[0.2], &[0.02], &[0.0], &[]).is_empty());
+    }
+
     #[test]
     fn implied_vol_check_bad() {
         let vol = implied_vol(